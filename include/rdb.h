@@ -0,0 +1,40 @@
+/*
+ * Interface C de rust-database (feature `ffi`), définie dans src/ffi.rs.
+ * tests/ffi_header.rs compile, lie et exécute un programme qui n'utilise
+ * que cet en-tête : `cargo test --features ffi` signale tout écart.
+ *
+ * Codes de retour : 0 = succès, 1 = clé absente (rdb_get), -1 = erreur.
+ */
+#ifndef RDB_H
+#define RDB_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef struct RdbHandle RdbHandle;
+
+RdbHandle *rdb_open(const char *path, uint64_t max_size);
+
+int rdb_get(const RdbHandle *handle,
+            const uint8_t *key, size_t key_len,
+            uint8_t **out_value, size_t *out_len);
+
+int rdb_set(const RdbHandle *handle,
+            const uint8_t *key, size_t key_len,
+            const uint8_t *value, size_t value_len);
+
+int rdb_delete(const RdbHandle *handle, const uint8_t *key, size_t key_len);
+
+void rdb_free(uint8_t *value, size_t len);
+
+void rdb_close(RdbHandle *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* RDB_H */
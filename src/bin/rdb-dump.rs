@@ -0,0 +1,414 @@
+//! Inspection hors ligne d'un fichier de base, sans ouvrir de `MyDatabase` :
+//! aucun verrou, et un journal corrompu reste lisible jusqu'au bout.
+//!
+//! Usage: rdb-dump [--json] [--summary] <fichier.db>
+
+use rust_database::codec::{self, FLAG_BITS, META_FLAG, RECORD_HEADER_LEN};
+use rust_database::{DatabaseError, IoOp, Language};
+use rust_database::{Dictionary, EntryType};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::process;
+
+/// `println!` du message `fr` ou `en` selon la langue `lang`, avec les
+/// mêmes arguments.
+macro_rules! say {
+    ($lang:expr, $fr:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $lang {
+            Language::Fr => println!($fr $(, $arg)*),
+            Language::En => println!($en $(, $arg)*),
+        }
+    };
+}
+
+/// Enregistrement lu dans le journal.
+struct Dumped {
+    offset: u64,
+    size: u64,
+    entry_type: EntryType,
+    key: Vec<u8>,
+    value_len: usize,
+    status: Status,
+    /// Code de l'erreur de décodage (`DatabaseError::code`).
+    error: Option<&'static str>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    ChecksumMismatch,
+    InvalidValue,
+}
+
+impl Status {
+    fn label(self, lang: Language) -> &'static str {
+        match (self, lang) {
+            (Status::Ok, _) => "ok",
+            (Status::ChecksumMismatch, _) => "checksum",
+            (Status::InvalidValue, Language::Fr) => "valeur invalide",
+            (Status::InvalidValue, Language::En) => "invalid value",
+        }
+    }
+}
+
+/// Fin anormale du parcours.
+enum Stop {
+    /// Enregistrement incomplet en fin de fichier.
+    Truncated { offset: u64, missing: u64 },
+    /// Octet de type inconnu : impossible de se resynchroniser.
+    UnknownType { offset: u64, byte: u8 },
+}
+
+#[derive(Default)]
+struct Summary {
+    records: u64,
+    data: u64,
+    tombstones: u64,
+    merges: u64,
+    checksum_failures: u64,
+    invalid_values: u64,
+    file_size: u64,
+    live_keys: u64,
+    live_bytes: u64,
+}
+
+fn main() {
+    let lang = Language::from_env();
+    let mut json = false;
+    let mut summary_only = false;
+    let mut path = None;
+    for arg in std::env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--summary" => summary_only = true,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg),
+            _ => usage(),
+        }
+    }
+    let Some(path) = path else { usage() };
+
+    let bytes = match std::fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            let err = DatabaseError::io(IoOp::LogRead, &path, err);
+            if json {
+                println!(
+                    "{{\"error\":{{\"code\":\"{}\",\"message\":{}}}}}",
+                    err.code(),
+                    json_string(&err.localized(lang).to_string())
+                );
+            } else {
+                match lang {
+                    Language::Fr => eprintln!("Erreur [{}]: {}", err.code(), err),
+                    Language::En => eprintln!("Error [{}]: {}", err.code(), err.localized(lang)),
+                }
+            }
+            process::exit(1);
+        }
+    };
+
+    let (records, stop) = parse(&bytes);
+    let summary = summarize(&records, bytes.len() as u64);
+    if json {
+        print_json(&records, stop.as_ref(), &summary, summary_only);
+    } else {
+        print_text(lang, &records, stop.as_ref(), &summary, summary_only);
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("Usage: rdb-dump [--json] [--summary] <fichier.db>");
+    process::exit(2);
+}
+
+/// Les valeurs compressées avec un dictionnaire sont décodées avec le
+/// dernier dictionnaire rencontré ; une référence (`BLOB_FLAG`) doit
+/// désigner un enregistrement `Blob` valide qui la précède.
+fn parse(bytes: &[u8]) -> (Vec<Dumped>, Option<Stop>) {
+    let mut records = Vec::new();
+    let mut pos = 0usize;
+    let mut dictionary = None;
+    let mut blobs = HashSet::new();
+
+    while pos < bytes.len() {
+        let offset = pos as u64;
+        let rest = &bytes[pos..];
+        if rest.len() < RECORD_HEADER_LEN {
+            let missing = (RECORD_HEADER_LEN - rest.len()) as u64;
+            return (records, Some(Stop::Truncated { offset, missing }));
+        }
+        let Some(entry_type) = EntryType::from_byte(rest[0] & !FLAG_BITS) else {
+            let byte = rest[0];
+            return (records, Some(Stop::UnknownType { offset, byte }));
+        };
+        let key_len = u32::from_be_bytes([rest[1], rest[2], rest[3], rest[4]]) as u64;
+        let value_len = u32::from_be_bytes([rest[5], rest[6], rest[7], rest[8]]) as u64;
+        let meta_end = if rest[0] & META_FLAG != 0 {
+            let Some(&meta_len) = rest.get(RECORD_HEADER_LEN) else {
+                return (records, Some(Stop::Truncated { offset, missing: 1 }));
+            };
+            RECORD_HEADER_LEN + 1 + meta_len as usize
+        } else {
+            RECORD_HEADER_LEN
+        };
+        let size = meta_end as u64 + key_len + value_len + 4;
+        if size > rest.len() as u64 {
+            let missing = size - rest.len() as u64;
+            return (records, Some(Stop::Truncated { offset, missing }));
+        }
+
+        let encoded = &rest[..size as usize];
+        let key_end = meta_end + key_len as usize;
+        let decoded = codec::decode_record_with_dictionary(encoded, dictionary.as_ref()).and_then(
+            |(decoded, len)| match decoded.blob {
+                Some(blob) if !blobs.contains(&blob) => Err(DatabaseError::InvalidFormat),
+                _ => Ok((decoded, len)),
+            },
+        );
+        if let Ok((decoded, _)) = &decoded
+            && decoded.record.entry_type == EntryType::Blob
+        {
+            blobs.insert(offset);
+        }
+        if let Ok((decoded, _)) = &decoded
+            && decoded.record.entry_type == EntryType::Dictionary
+            && let Ok(id) = <[u8; 4]>::try_from(decoded.record.key.as_slice())
+        {
+            dictionary = Some(Dictionary {
+                id: u32::from_be_bytes(id),
+                bytes: decoded.record.value.clone(),
+            });
+        }
+        let status = match &decoded {
+            Ok(_) => Status::Ok,
+            Err(DatabaseError::CorruptedData) => Status::ChecksumMismatch,
+            Err(_) => Status::InvalidValue,
+        };
+        records.push(Dumped {
+            offset,
+            size,
+            entry_type,
+            key: encoded[meta_end..key_end].to_vec(),
+            value_len: value_len as usize,
+            status,
+            error: decoded.err().map(|err| err.code()),
+        });
+        pos += size as usize;
+    }
+
+    (records, None)
+}
+
+/// Les octets vivants sont estimés par clé : dernier enregistrement `Data` et
+/// opérandes de fusion qui le suivent ; un tombstone rend la clé morte. Les
+/// valeurs partagées (`Blob`) comptent toutes comme vivantes.
+fn summarize(records: &[Dumped], file_size: u64) -> Summary {
+    let mut summary = Summary {
+        file_size,
+        ..Summary::default()
+    };
+    let mut live: HashMap<&[u8], Option<u64>> = HashMap::new();
+
+    for record in records {
+        summary.records += 1;
+        match record.status {
+            Status::Ok => {}
+            Status::ChecksumMismatch => summary.checksum_failures += 1,
+            Status::InvalidValue => summary.invalid_values += 1,
+        }
+        match record.entry_type {
+            EntryType::Dictionary => continue,
+            EntryType::Blob => {
+                summary.live_bytes += record.size;
+                continue;
+            }
+            _ => {}
+        }
+        let run = live.entry(&record.key).or_insert(None);
+        match record.entry_type {
+            EntryType::Data => {
+                summary.data += 1;
+                *run = Some(record.size);
+            }
+            EntryType::Tombstone => {
+                summary.tombstones += 1;
+                *run = None;
+            }
+            EntryType::Merge => {
+                summary.merges += 1;
+                *run = Some(run.unwrap_or(0) + record.size);
+            }
+            EntryType::Dictionary | EntryType::Blob => {}
+        }
+    }
+
+    for size in live.values().flatten() {
+        summary.live_keys += 1;
+        summary.live_bytes += size;
+    }
+    summary
+}
+
+fn type_name(entry_type: EntryType) -> &'static str {
+    match entry_type {
+        EntryType::Data => "DATA",
+        EntryType::Tombstone => "TOMBSTONE",
+        EntryType::Merge => "MERGE",
+        EntryType::Dictionary => "DICTIONARY",
+        EntryType::Blob => "BLOB",
+    }
+}
+
+fn display_bytes(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(text) => text.to_string(),
+        Err(_) => {
+            let mut out = String::from("0x");
+            for b in bytes {
+                let _ = write!(out, "{:02x}", b);
+            }
+            out
+        }
+    }
+}
+
+fn print_text(
+    lang: Language,
+    records: &[Dumped],
+    stop: Option<&Stop>,
+    summary: &Summary,
+    summary_only: bool,
+) {
+    if !summary_only {
+        for (idx, record) in records.iter().enumerate() {
+            println!(
+                "#{idx} offset={} size={} type={} key={} value_len={} checksum={}",
+                record.offset,
+                record.size,
+                type_name(record.entry_type),
+                display_bytes(&record.key),
+                record.value_len,
+                record.status.label(lang)
+            );
+        }
+    }
+    match stop {
+        Some(Stop::Truncated { offset, missing }) => say!(
+            lang,
+            "Enregistrement tronqué à l'offset {} ({} octets manquants)",
+            "Truncated record at offset {} ({} bytes missing)",
+            offset,
+            missing
+        ),
+        Some(Stop::UnknownType { offset, byte }) => say!(
+            lang,
+            "Type inconnu 0x{:02x} à l'offset {} : lecture interrompue",
+            "Unknown type 0x{:02x} at offset {}: reading stopped",
+            byte,
+            offset
+        ),
+        None => {}
+    }
+
+    let dead = summary.file_size.saturating_sub(summary.live_bytes);
+    say!(lang, "--- Statistiques ---", "--- Statistics ---");
+    say!(
+        lang,
+        "Taille du fichier : {} octets",
+        "File size: {} bytes",
+        summary.file_size
+    );
+    say!(
+        lang,
+        "Enregistrements : {} (data {}, tombstones {}, fusions {})",
+        "Records: {} (data {}, tombstones {}, merges {})",
+        summary.records,
+        summary.data,
+        summary.tombstones,
+        summary.merges
+    );
+    say!(
+        lang,
+        "Échecs de checksum : {}, valeurs invalides : {}",
+        "Checksum failures: {}, invalid values: {}",
+        summary.checksum_failures,
+        summary.invalid_values
+    );
+    say!(
+        lang,
+        "Clés vivantes : {} ({} octets), octets morts estimés : {}",
+        "Live keys: {} ({} bytes), estimated dead bytes: {}",
+        summary.live_keys,
+        summary.live_bytes,
+        dead
+    );
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn print_json(records: &[Dumped], stop: Option<&Stop>, summary: &Summary, summary_only: bool) {
+    let mut out = String::from("{");
+    if !summary_only {
+        out.push_str("\"records\":[");
+        for (idx, record) in records.iter().enumerate() {
+            if idx > 0 {
+                out.push(',');
+            }
+            let _ = write!(
+                out,
+                "{{\"offset\":{},\"size\":{},\"type\":\"{}\",\"key\":{},\"value_len\":{},\"checksum\":{},\"error\":{}}}",
+                record.offset,
+                record.size,
+                type_name(record.entry_type),
+                json_string(&display_bytes(&record.key)),
+                record.value_len,
+                // Valeur stable, indépendante de `RDB_LANG`.
+                json_string(record.status.label(Language::Fr)),
+                record.error.map_or("null".to_string(), json_string)
+            );
+        }
+        out.push_str("],");
+    }
+
+    let stop = match stop {
+        Some(Stop::Truncated { offset, missing }) => format!(
+            "{{\"kind\":\"truncated\",\"offset\":{},\"missing\":{}}}",
+            offset, missing
+        ),
+        Some(Stop::UnknownType { offset, byte }) => format!(
+            "{{\"kind\":\"unknown_type\",\"offset\":{},\"byte\":{}}}",
+            offset, byte
+        ),
+        None => "null".to_string(),
+    };
+    let _ = write!(
+        out,
+        "\"stop\":{},\"stats\":{{\"file_size\":{},\"records\":{},\"data\":{},\"tombstones\":{},\"merges\":{},\"checksum_failures\":{},\"invalid_values\":{},\"live_keys\":{},\"live_bytes\":{},\"dead_bytes\":{}}}}}",
+        stop,
+        summary.file_size,
+        summary.records,
+        summary.data,
+        summary.tombstones,
+        summary.merges,
+        summary.checksum_failures,
+        summary.invalid_values,
+        summary.live_keys,
+        summary.live_bytes,
+        summary.file_size.saturating_sub(summary.live_bytes)
+    );
+    println!("{}", out);
+}
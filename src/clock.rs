@@ -0,0 +1,54 @@
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source de temps de la base (horodatages, planification de la compaction).
+///
+/// Les attentes actives (contre-pression, limitation de débit) restent
+/// mesurées sur l'horloge monotone du système.
+pub trait Clock: fmt::Debug + Send + Sync {
+    /// Horodatage courant en millisecondes depuis l'époque UNIX.
+    fn now_millis(&self) -> u64;
+}
+
+/// Horloge système, utilisée par défaut.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0)
+    }
+}
+
+/// Horloge pilotée à la main, pour tester sans attendre.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    now_ms: AtomicU64,
+}
+
+impl ManualClock {
+    pub fn new(now_ms: u64) -> Self {
+        Self {
+            now_ms: AtomicU64::new(now_ms),
+        }
+    }
+
+    pub fn set(&self, now_ms: u64) {
+        self.now_ms.store(now_ms, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now_ms
+            .fetch_add(by.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> u64 {
+        self.now_ms.load(Ordering::Relaxed)
+    }
+}
@@ -6,14 +6,48 @@ pub trait Compressor {
     fn decode(input: &[u8]) -> Result<Vec<u8>, DatabaseError>;
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 /// Type d'entrée dans le journal.
 pub enum EntryType {
     Data,
     Tombstone,
+    /// Opérande de fusion appliquée à la valeur précédente de la clé.
+    Merge,
+    /// Dictionnaire de compression du journal (voir `Dictionary`) ; ce n'est
+    /// pas une entrée de la base.
+    Dictionary,
+    /// Valeur partagée par les entrées qui la référencent (`BLOB_FLAG`) ;
+    /// la clé est l'empreinte de la valeur (`content_hash`). Ce n'est pas une
+    /// entrée de la base.
+    Blob,
+}
+
+impl EntryType {
+    /// Octet de type stocké en tête d'enregistrement.
+    pub fn to_byte(self) -> u8 {
+        match self {
+            EntryType::Data => 0,
+            EntryType::Tombstone => 1,
+            EntryType::Merge => 2,
+            EntryType::Dictionary => 3,
+            EntryType::Blob => 4,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 => Some(EntryType::Data),
+            1 => Some(EntryType::Tombstone),
+            2 => Some(EntryType::Merge),
+            3 => Some(EntryType::Dictionary),
+            4 => Some(EntryType::Blob),
+            _ => None,
+        }
+    }
 }
 
 /// Entrée logique du journal (clé/valeur).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct DataEntry {
     pub entry_type: EntryType,
     pub key: Vec<u8>,
@@ -21,34 +55,473 @@ pub struct DataEntry {
 }
 
 impl DataEntry {
-    /// Sérialise une entrée en format binaire.
-    /// \[Type (1B)\] \[Taille Clé (4B)\] \[Taille Valeur (4B)\] \[Clé\] \[Valeur\] \[Checksum (4B)\]
+    /// Sérialise une entrée en format binaire (voir `encode_record`).
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut buffer = Vec::new();
+        encode_record(self)
+    }
 
-        let type_byte = match self.entry_type {
-            EntryType::Data => 0u8,
-            EntryType::Tombstone => 1u8,
-        };
-        buffer.push(type_byte);
+    /// Comme `to_bytes`, en ajoutant l'encodage à la fin de `out` (un tampon
+    /// réutilisé d'une entrée à l'autre). Retourne la taille ajoutée.
+    pub fn write_to(&self, out: &mut Vec<u8>) -> usize {
+        encode_record_into(self, &[], None, None, out)
+    }
+}
 
-        let key_len = (self.key.len() as u32).to_be_bytes();
-        let encoded_value = Lz77::encode(&self.value);
-        let val_len = (encoded_value.len() as u32).to_be_bytes();
+/// Enregistrement du journal décodé.
+pub type Record = DataEntry;
 
-        buffer.extend_from_slice(&key_len);
-        buffer.extend_from_slice(&val_len);
-        buffer.extend_from_slice(&self.key);
-        buffer.extend_from_slice(&encoded_value);
+/// Taille de l'en-tête : type (1 octet), taille de clé et taille de valeur (4 octets chacune).
+pub const RECORD_HEADER_LEN: usize = 9;
+const CHECKSUM_LEN: usize = 4;
+/// Bit de l'octet de type signalant des métadonnées utilisateur après l'en-tête.
+pub const META_FLAG: u8 = 0x80;
+/// Bit de l'octet de type signalant une valeur stockée sans compression
+/// (voir `DatabaseConfig::min_compression_savings`).
+pub const RAW_FLAG: u8 = 0x40;
+/// Bit de l'octet de type signalant une valeur compressée avec le
+/// dictionnaire du journal (voir `DatabaseConfig::compression_dictionary`) :
+/// la valeur stockée commence par l'identifiant du dictionnaire (4 octets).
+pub const DICT_FLAG: u8 = 0x20;
+/// Bit de l'octet de type signalant une entrée `Data` dont la valeur est
+/// celle de l'enregistrement `EntryType::Blob` du même journal : la valeur
+/// stockée est la position de celui-ci (8 octets), sans compression (voir
+/// `DatabaseConfig::dedup_min_size`).
+pub const BLOB_FLAG: u8 = 0x10;
+/// Bits de l'octet de type qui ne font pas partie du type lui-même.
+pub const FLAG_BITS: u8 = META_FLAG | RAW_FLAG | DICT_FLAG | BLOB_FLAG;
+/// Expansion maximale d'une valeur LZ77 bien formée : une référence de 4
+/// octets produit au plus 255 octets.
+pub const MAX_EXPANSION: usize = 64;
+/// Taille maximale d'un enregistrement permise par le format : sa taille est
+/// indexée sur 32 bits.
+pub const MAX_RECORD_SIZE: u32 = u32::MAX;
+/// Taille maximale des métadonnées d'un enregistrement.
+pub const MAX_META_LEN: usize = u8::MAX as usize;
+/// Taille maximale d'un dictionnaire : la moitié de la fenêtre LZ77, pour
+/// que le début d'une valeur puisse référencer tout le dictionnaire.
+pub const MAX_DICTIONARY_LEN: usize = 2048;
+const DICTIONARY_ID_LEN: usize = 4;
+const BLOB_OFFSET_LEN: usize = 8;
+/// Taille maximale d'un enregistrement de dictionnaire.
+pub(crate) const MAX_DICTIONARY_RECORD_LEN: usize =
+    RECORD_HEADER_LEN + DICTIONARY_ID_LEN + MAX_DICTIONARY_LEN + CHECKSUM_LEN;
 
-        let mut checksum: u32 = 0;
-        for byte in &buffer {
-            checksum = checksum.wrapping_add(*byte as u32);
+/// Encode un enregistrement :
+/// \[Type (1B)\] \[Taille Clé (4B)\] \[Taille Valeur (4B)\] \[Clé\] \[Valeur\] \[Checksum (4B)\]
+///
+/// La valeur est compressée (LZ77) ; le checksum est la somme des octets qui le précèdent.
+pub fn encode_record(record: &Record) -> Vec<u8> {
+    encode_record_with_meta(record, &[])
+}
+
+/// Comme `encode_record`, avec des métadonnées utilisateur (au plus
+/// `MAX_META_LEN` octets). Si `meta` n'est pas vide, l'octet de type porte
+/// `META_FLAG` et l'en-tête est suivi de \[Taille Méta (1B)\] \[Méta\].
+pub(crate) fn encode_record_with_meta(record: &Record, meta: &[u8]) -> Vec<u8> {
+    let mut buffer = Vec::new();
+    encode_record_into(record, meta, None, None, &mut buffer);
+    buffer
+}
+
+/// Ajoute l'encodage de `record` à la fin de `out`, sans tampon
+/// intermédiaire : la valeur est compressée directement à sa place et sa
+/// taille reportée ensuite dans l'en-tête. Avec `dictionary`, une valeur non
+/// vide est compressée avec lui (`DICT_FLAG`). Avec `min_savings` (en %), une
+/// valeur que la compression ne réduit pas assez est stockée telle quelle,
+/// l'octet de type portant `RAW_FLAG`. Retourne la taille ajoutée.
+pub(crate) fn encode_record_into(
+    record: &Record,
+    meta: &[u8],
+    min_savings: Option<u8>,
+    dictionary: Option<&Dictionary>,
+    out: &mut Vec<u8>,
+) -> usize {
+    let start = out.len();
+    if meta.is_empty() {
+        out.push(record.entry_type.to_byte());
+    } else {
+        out.push(record.entry_type.to_byte() | META_FLAG);
+    }
+
+    out.extend_from_slice(&(record.key.len() as u32).to_be_bytes());
+    let val_len_at = out.len();
+    out.extend_from_slice(&[0; 4]);
+    if !meta.is_empty() {
+        out.push(meta.len() as u8);
+        out.extend_from_slice(meta);
+    }
+    out.extend_from_slice(&record.key);
+    let value_start = out.len();
+    match dictionary.filter(|_| !record.value.is_empty()) {
+        Some(dictionary) => {
+            out[start] |= DICT_FLAG;
+            out.extend_from_slice(&dictionary.id.to_be_bytes());
+            let mut data = Vec::with_capacity(dictionary.bytes.len() + record.value.len());
+            data.extend_from_slice(&dictionary.bytes);
+            data.extend_from_slice(&record.value);
+            lz77_encode_range(&data, dictionary.bytes.len(), out);
         }
-        buffer.extend_from_slice(&checksum.to_be_bytes());
+        None => lz77_encode_into(&record.value, out),
+    }
+    if let Some(percent) = min_savings {
+        let compressed = (out.len() - value_start) as u64;
+        let budget = record.value.len() as u64 * u64::from(100 - percent.min(100));
+        if compressed * 100 > budget {
+            out.truncate(value_start);
+            out.extend_from_slice(&record.value);
+            out[start] = (out[start] & !DICT_FLAG) | RAW_FLAG;
+        }
+    }
+    let val_len = ((out.len() - value_start) as u32).to_be_bytes();
+    out[val_len_at..val_len_at + 4].copy_from_slice(&val_len);
 
-        buffer
+    let checksum = checksum(&out[start..]);
+    out.extend_from_slice(&checksum.to_be_bytes());
+
+    out.len() - start
+}
+
+/// Ajoute à la fin de `out` une entrée `Data` de clé `key` dont la valeur
+/// est celle de l'enregistrement `Blob` situé en `blob_offset` (`BLOB_FLAG`).
+/// Retourne la taille ajoutée.
+pub(crate) fn encode_reference_into(
+    key: &[u8],
+    meta: &[u8],
+    blob_offset: u64,
+    out: &mut Vec<u8>,
+) -> usize {
+    let start = out.len();
+    let mut type_byte = EntryType::Data.to_byte() | BLOB_FLAG;
+    if !meta.is_empty() {
+        type_byte |= META_FLAG;
     }
+    out.push(type_byte);
+    out.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    out.extend_from_slice(&(BLOB_OFFSET_LEN as u32).to_be_bytes());
+    if !meta.is_empty() {
+        out.push(meta.len() as u8);
+        out.extend_from_slice(meta);
+    }
+    out.extend_from_slice(key);
+    out.extend_from_slice(&blob_offset.to_be_bytes());
+    let checksum = checksum(&out[start..]);
+    out.extend_from_slice(&checksum.to_be_bytes());
+    out.len() - start
+}
+
+/// Fait pointer la référence encodée dans `record` (voir
+/// `encode_reference_into`) vers l'enregistrement `Blob` situé en
+/// `blob_offset`, checksum compris.
+pub(crate) fn relocate_reference(record: &mut [u8], blob_offset: u64) {
+    let end = record.len() - CHECKSUM_LEN;
+    record[end - BLOB_OFFSET_LEN..end].copy_from_slice(&blob_offset.to_be_bytes());
+    let checksum = checksum(&record[..end]);
+    record[end..].copy_from_slice(&checksum.to_be_bytes());
+}
+
+/// Empreinte d'une valeur partagée (FNV-1a sur 64 bits), clé de son
+/// enregistrement `Blob`. Deux valeurs de même empreinte ne sont partagées
+/// qu'après comparaison de leurs octets.
+pub fn content_hash(value: &[u8]) -> u64 {
+    value.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Taille encodée de l'enregistrement sans métadonnées dont `header` est
+/// l'en-tête.
+pub(crate) fn record_len(header: &[u8; RECORD_HEADER_LEN]) -> u64 {
+    let key_len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]);
+    let value_len = u32::from_be_bytes([header[5], header[6], header[7], header[8]]);
+    (RECORD_HEADER_LEN + CHECKSUM_LEN) as u64 + u64::from(key_len) + u64::from(value_len)
+}
+
+/// Enregistrement décodé par `decode_record`, avec ce que l'en-tête porte
+/// en plus de l'entrée.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedRecord {
+    /// Entrée, valeur décompressée.
+    pub record: Record,
+    /// Métadonnées utilisateur (`META_FLAG`), vides si absentes.
+    pub meta: Vec<u8>,
+    /// La valeur était stockée sans compression (`RAW_FLAG`).
+    pub raw: bool,
+    /// Dictionnaire avec lequel la valeur était compressée (`DICT_FLAG`).
+    pub dictionary: Option<u32>,
+    /// Position de l'enregistrement `Blob` portant la valeur (`BLOB_FLAG`) ;
+    /// `record.value` est alors vide.
+    pub blob: Option<u64>,
+}
+
+/// Décode l'enregistrement situé en tête de `input` et retourne aussi sa
+/// taille encodée, pour enchaîner sur le suivant.
+///
+/// Ne panique sur aucune entrée : un enregistrement tronqué ou dont le
+/// checksum ne correspond pas donne `CorruptedData`, un type ou une valeur
+/// compressée invalide donne `InvalidFormat`, de même qu'une valeur
+/// compressée avec un dictionnaire (voir `decode_record_with_dictionary`).
+pub fn decode_record(input: &[u8]) -> Result<(DecodedRecord, usize), DatabaseError> {
+    decode_record_with_dictionary(input, None)
+}
+
+/// Comme `decode_record`, une valeur marquée `DICT_FLAG` étant décompressée
+/// avec `dictionary`, qui doit porter le même identifiant.
+pub fn decode_record_with_dictionary(
+    input: &[u8],
+    dictionary: Option<&Dictionary>,
+) -> Result<(DecodedRecord, usize), DatabaseError> {
+    let raw = verify_record(input)?;
+    let decoded = DecodedRecord {
+        record: Record {
+            entry_type: raw.entry_type,
+            key: raw.key.to_vec(),
+            value: match raw.blob {
+                Some(_) => Vec::new(),
+                None => raw.decode_value(MAX_EXPANSION, dictionary)?,
+            },
+        },
+        meta: raw.meta.to_vec(),
+        raw: raw.stored_raw,
+        dictionary: raw.dictionary,
+        blob: raw.blob,
+    };
+    Ok((decoded, raw.len))
+}
+
+/// Dictionnaire de compression partagé par les valeurs d'un journal : des
+/// octets typiques des valeurs, que la compression d'une valeur peut
+/// référencer comme s'ils la précédaient. Il est écrit en tête du journal par
+/// la compaction, dans un enregistrement `EntryType::Dictionary` dont la clé
+/// est l'identifiant et la valeur les octets, stockés bruts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dictionary {
+    pub id: u32,
+    pub bytes: Vec<u8>,
+}
+
+impl Dictionary {
+    /// Construit un dictionnaire à partir de valeurs échantillons : chacune
+    /// y est ajoutée, tronquée au quart de `MAX_DICTIONARY_LEN`, sauf si le
+    /// dictionnaire la compresse déjà de moitié. `None` si aucun échantillon
+    /// n'a été retenu.
+    pub fn train(id: u32, samples: &[Vec<u8>]) -> Option<Self> {
+        let mut bytes = Vec::new();
+        let mut data = Vec::new();
+        let mut compressed = Vec::new();
+        for sample in samples {
+            let sample = &sample[..sample.len().min(MAX_DICTIONARY_LEN / 4)];
+            if sample.is_empty() || bytes.len() + sample.len() > MAX_DICTIONARY_LEN {
+                continue;
+            }
+            data.clear();
+            data.extend_from_slice(&bytes);
+            data.extend_from_slice(sample);
+            compressed.clear();
+            lz77_encode_range(&data, bytes.len(), &mut compressed);
+            if compressed.len() * 2 > sample.len() {
+                bytes.extend_from_slice(sample);
+            }
+        }
+        (!bytes.is_empty()).then_some(Self { id, bytes })
+    }
+
+    /// Enregistrement du journal portant ce dictionnaire.
+    pub fn to_record(&self) -> Vec<u8> {
+        let mut out = vec![EntryType::Dictionary.to_byte() | RAW_FLAG];
+        out.extend_from_slice(&(DICTIONARY_ID_LEN as u32).to_be_bytes());
+        out.extend_from_slice(&(self.bytes.len() as u32).to_be_bytes());
+        out.extend_from_slice(&self.id.to_be_bytes());
+        out.extend_from_slice(&self.bytes);
+        let checksum = checksum(&out);
+        out.extend_from_slice(&checksum.to_be_bytes());
+        out
+    }
+
+    /// Taille de `to_record`.
+    pub fn record_len(&self) -> usize {
+        RECORD_HEADER_LEN + DICTIONARY_ID_LEN + self.bytes.len() + CHECKSUM_LEN
+    }
+
+    /// Dictionnaire porté par `record` ; `InvalidFormat` si ce n'est pas un
+    /// enregistrement de dictionnaire valide.
+    pub(crate) fn from_record(record: &RawRecord<'_>) -> Result<Self, DatabaseError> {
+        let id = <[u8; DICTIONARY_ID_LEN]>::try_from(record.key)
+            .map_err(|_| DatabaseError::InvalidFormat)?;
+        if record.entry_type != EntryType::Dictionary
+            || !record.stored_raw
+            || record.value.len() > MAX_DICTIONARY_LEN
+        {
+            return Err(DatabaseError::InvalidFormat);
+        }
+        Ok(Self {
+            id: u32::from_be_bytes(id),
+            bytes: record.value.to_vec(),
+        })
+    }
+}
+
+/// Enregistrement validé dont la valeur n'est pas encore décodée.
+pub(crate) struct RawRecord<'a> {
+    pub(crate) entry_type: EntryType,
+    pub(crate) key: &'a [u8],
+    pub(crate) value: &'a [u8],
+    /// Métadonnées utilisateur, vides si absentes.
+    pub(crate) meta: &'a [u8],
+    /// Taille encodée totale.
+    pub(crate) len: usize,
+    /// La valeur est stockée sans compression (`RAW_FLAG`).
+    pub(crate) stored_raw: bool,
+    /// Dictionnaire de la valeur compressée (`DICT_FLAG`) ; `value` ne
+    /// contient plus son identifiant.
+    pub(crate) dictionary: Option<u32>,
+    /// Position de l'enregistrement `Blob` portant la valeur (`BLOB_FLAG`) ;
+    /// `value` est alors vide.
+    pub(crate) blob: Option<u64>,
+}
+
+impl RawRecord<'_> {
+    /// Valeur décompressée si besoin. Une valeur qui dépasserait
+    /// `max_expansion` fois sa taille stockée est rejetée
+    /// (`DatabaseError::CorruptedData`) avant d'être produite. Une valeur
+    /// `DICT_FLAG` exige le dictionnaire de même identifiant, faute de quoi
+    /// elle est rejetée (`DatabaseError::InvalidFormat`), de même qu'une
+    /// référence `BLOB_FLAG`, à résoudre dans le journal.
+    pub(crate) fn decode_value(
+        &self,
+        max_expansion: usize,
+        dictionary: Option<&Dictionary>,
+    ) -> Result<Vec<u8>, DatabaseError> {
+        let mut out = Vec::new();
+        self.decode_value_into(&mut out, max_expansion, dictionary)?;
+        Ok(out)
+    }
+
+    /// Comme `decode_value`, en remplaçant le contenu de `out`.
+    pub(crate) fn decode_value_into(
+        &self,
+        out: &mut Vec<u8>,
+        max_expansion: usize,
+        dictionary: Option<&Dictionary>,
+    ) -> Result<(), DatabaseError> {
+        if self.blob.is_some() {
+            return Err(DatabaseError::InvalidFormat);
+        }
+        if self.stored_raw {
+            out.clear();
+            out.extend_from_slice(self.value);
+            return Ok(());
+        }
+        let history = self.history(dictionary)?;
+        lz77_decode_into(
+            history,
+            self.value,
+            out,
+            decode_limit(self.value, max_expansion),
+        )
+    }
+
+    /// Taille de la valeur décodée, calculée sans la produire, avec la même
+    /// limite que `decode_value`.
+    pub(crate) fn decoded_len(
+        &self,
+        max_expansion: usize,
+        dictionary: Option<&Dictionary>,
+    ) -> Result<usize, DatabaseError> {
+        if self.blob.is_some() {
+            return Err(DatabaseError::InvalidFormat);
+        }
+        if self.stored_raw {
+            return Ok(self.value.len());
+        }
+        let history = self.history(dictionary)?.len();
+        decoded_len(history, self.value, decode_limit(self.value, max_expansion))
+    }
+
+    /// Octets précédant la valeur pour sa décompression : ceux du
+    /// dictionnaire si elle en a un.
+    fn history<'d>(&self, dictionary: Option<&'d Dictionary>) -> Result<&'d [u8], DatabaseError> {
+        match (self.dictionary, dictionary) {
+            (None, _) => Ok(&[]),
+            (Some(id), Some(dictionary)) if dictionary.id == id => Ok(&dictionary.bytes),
+            (Some(_), _) => Err(DatabaseError::InvalidFormat),
+        }
+    }
+}
+
+/// Valide l'en-tête et le checksum de l'enregistrement en tête de `input`
+/// sans décompresser sa valeur.
+pub(crate) fn verify_record(input: &[u8]) -> Result<RawRecord<'_>, DatabaseError> {
+    let header = input
+        .get(..RECORD_HEADER_LEN)
+        .ok_or(DatabaseError::CorruptedData)?;
+    let entry_type =
+        EntryType::from_byte(header[0] & !FLAG_BITS).ok_or(DatabaseError::InvalidFormat)?;
+    let key_len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    let value_len = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) as usize;
+    let meta_end = if header[0] & META_FLAG != 0 {
+        let meta_len = *input
+            .get(RECORD_HEADER_LEN)
+            .ok_or(DatabaseError::CorruptedData)? as usize;
+        RECORD_HEADER_LEN + 1 + meta_len
+    } else {
+        RECORD_HEADER_LEN
+    };
+
+    let body_len = meta_end
+        .checked_add(key_len)
+        .and_then(|len| len.checked_add(value_len))
+        .ok_or(DatabaseError::InvalidFormat)?;
+    let total_len = body_len
+        .checked_add(CHECKSUM_LEN)
+        .ok_or(DatabaseError::InvalidFormat)?;
+    let encoded = input.get(..total_len).ok_or(DatabaseError::CorruptedData)?;
+
+    let (body, stored) = encoded.split_at(body_len);
+    let stored = u32::from_be_bytes([stored[0], stored[1], stored[2], stored[3]]);
+    if checksum(body) != stored {
+        return Err(DatabaseError::CorruptedData);
+    }
+
+    let meta = body
+        .get(RECORD_HEADER_LEN + 1..meta_end)
+        .unwrap_or_default();
+    let (key, mut value) = body[meta_end..].split_at(key_len);
+    let stored_raw = header[0] & RAW_FLAG != 0;
+    let mut dictionary = None;
+    if header[0] & DICT_FLAG != 0 {
+        let (id, compressed) = value
+            .split_first_chunk::<DICTIONARY_ID_LEN>()
+            .filter(|_| !stored_raw)
+            .ok_or(DatabaseError::InvalidFormat)?;
+        dictionary = Some(u32::from_be_bytes(*id));
+        value = compressed;
+    }
+    let mut blob = None;
+    if header[0] & BLOB_FLAG != 0 {
+        let offset = <[u8; BLOB_OFFSET_LEN]>::try_from(value)
+            .ok()
+            .filter(|_| entry_type == EntryType::Data && header[0] & (RAW_FLAG | DICT_FLAG) == 0)
+            .ok_or(DatabaseError::InvalidFormat)?;
+        blob = Some(u64::from_be_bytes(offset));
+        value = &[];
+    }
+    Ok(RawRecord {
+        entry_type,
+        key,
+        value,
+        meta,
+        len: total_len,
+        stored_raw,
+        dictionary,
+        blob,
+    })
+}
+
+/// Somme additive des octets, telle que stockée en fin d'enregistrement.
+pub(crate) fn checksum(bytes: &[u8]) -> u32 {
+    bytes
+        .iter()
+        .fold(0u32, |sum, byte| sum.wrapping_add(*byte as u32))
 }
 
 /// Implémentation LZ77 simplifiée.
@@ -60,51 +533,70 @@ impl Compressor for Lz77 {
     }
 
     fn decode(input: &[u8]) -> Result<Vec<u8>, DatabaseError> {
-        lz77_decode(input)
+        let mut out = Vec::new();
+        lz77_decode_into(&[], input, &mut out, decode_limit(input, MAX_EXPANSION))?;
+        Ok(out)
     }
 }
 
 fn lz77_encode(input: &[u8]) -> Vec<u8> {
-    if input.is_empty() {
-        return Vec::new();
-    }
-
     let mut out = Vec::new();
-    let mut literals: Vec<u8> = Vec::new();
-    let mut i = 0;
+    lz77_encode_into(input, &mut out);
+    out
+}
+
+/// Compresse `input` à la suite de `out`. Les littéraux sont recopiés
+/// directement depuis `input`, par séries d'au plus 255 octets.
+fn lz77_encode_into(input: &[u8], out: &mut Vec<u8>) {
+    lz77_encode_range(input, 0, out);
+}
+
+/// Compresse `input[start..]` à la suite de `out`, les références arrière
+/// pouvant viser `input[..start]` (un dictionnaire), qui n'est pas écrit.
+fn lz77_encode_range(input: &[u8], start: usize, out: &mut Vec<u8>) {
+    let mut literals_start = start;
+    let mut i = start;
 
     while i < input.len() {
         let (dist, len) = find_longest_match(input, i);
         if len >= 3 {
-            if !literals.is_empty() {
-                emit_literals(&mut out, &mut literals);
-            }
+            emit_literals(out, &input[literals_start..i]);
             out.push(1);
             out.extend_from_slice(&(dist as u16).to_be_bytes());
             out.push(len as u8);
             i += len;
+            literals_start = i;
         } else {
-            literals.push(input[i]);
-            if literals.len() == u8::MAX as usize {
-                emit_literals(&mut out, &mut literals);
-            }
             i += 1;
+            if i - literals_start == u8::MAX as usize {
+                emit_literals(out, &input[literals_start..i]);
+                literals_start = i;
+            }
         }
     }
 
-    if !literals.is_empty() {
-        emit_literals(&mut out, &mut literals);
-    }
-
-    out
+    emit_literals(out, &input[literals_start..]);
 }
 
-fn lz77_decode(input: &[u8]) -> Result<Vec<u8>, DatabaseError> {
-    if input.is_empty() {
-        return Ok(Vec::new());
-    }
+/// Taille décodée maximale admise pour `input` : `max_expansion` fois sa
+/// taille (au moins une fois).
+fn decode_limit(input: &[u8], max_expansion: usize) -> usize {
+    input.len().saturating_mul(max_expansion.max(1))
+}
 
-    let mut out = Vec::new();
+/// Décompresse `input` en remplaçant le contenu de `out` (sa capacité est
+/// réutilisée), les références arrière pouvant viser `history` (un
+/// dictionnaire). Échoue avec `DatabaseError::CorruptedData` dès que la
+/// sortie dépasserait `limit` octets.
+fn lz77_decode_into(
+    history: &[u8],
+    input: &[u8],
+    out: &mut Vec<u8>,
+    limit: usize,
+) -> Result<(), DatabaseError> {
+    out.clear();
+    out.extend_from_slice(history);
+    let limit = limit.saturating_add(history.len());
     let mut i = 0usize;
     while i < input.len() {
         let tag = input[i];
@@ -120,6 +612,9 @@ fn lz77_decode(input: &[u8]) -> Result<Vec<u8>, DatabaseError> {
                 if len == 0 || i + len > input.len() {
                     return Err(DatabaseError::InvalidFormat);
                 }
+                if out.len() + len > limit {
+                    return Err(DatabaseError::CorruptedData);
+                }
                 out.extend_from_slice(&input[i..i + len]);
                 i += len;
             }
@@ -134,6 +629,9 @@ fn lz77_decode(input: &[u8]) -> Result<Vec<u8>, DatabaseError> {
                 if dist == 0 || len == 0 || dist > out.len() {
                     return Err(DatabaseError::InvalidFormat);
                 }
+                if out.len() + len > limit {
+                    return Err(DatabaseError::CorruptedData);
+                }
                 for _ in 0..len {
                     let b = out[out.len() - dist];
                     out.push(b);
@@ -143,14 +641,68 @@ fn lz77_decode(input: &[u8]) -> Result<Vec<u8>, DatabaseError> {
         }
     }
 
-    Ok(out)
+    out.drain(..history.len());
+    Ok(())
+}
+
+/// Taille de la valeur compressée d'un enregistrement encodé, lue dans son
+/// en-tête.
+pub(crate) fn stored_value_len(encoded: &[u8]) -> usize {
+    u32::from_be_bytes([encoded[5], encoded[6], encoded[7], encoded[8]]) as usize
+}
+
+/// Taille décompressée de `input`, calculée sans produire la sortie. Les
+/// mêmes contrôles que `lz77_decode_into` s'appliquent, `history` étant la
+/// taille du dictionnaire.
+fn decoded_len(history: usize, input: &[u8], limit: usize) -> Result<usize, DatabaseError> {
+    let limit = limit.saturating_add(history);
+    let mut len = history;
+    let mut i = 0usize;
+    while i < input.len() {
+        let tag = input[i];
+        i += 1;
+
+        match tag {
+            0 => {
+                let count = *input.get(i).ok_or(DatabaseError::InvalidFormat)? as usize;
+                i += 1;
+                if count == 0 || i + count > input.len() {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                len += count;
+                i += count;
+                if len > limit {
+                    return Err(DatabaseError::CorruptedData);
+                }
+            }
+            1 => {
+                if i + 2 >= input.len() {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                let dist = u16::from_be_bytes([input[i], input[i + 1]]) as usize;
+                let count = input[i + 2] as usize;
+                i += 3;
+                if dist == 0 || count == 0 || dist > len {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                len += count;
+                if len > limit {
+                    return Err(DatabaseError::CorruptedData);
+                }
+            }
+            _ => return Err(DatabaseError::InvalidFormat),
+        }
+    }
+    Ok(len - history)
 }
 
-fn emit_literals(out: &mut Vec<u8>, literals: &mut Vec<u8>) {
+fn emit_literals(out: &mut Vec<u8>, literals: &[u8]) {
+    if literals.is_empty() {
+        return;
+    }
     out.push(0);
     out.push(literals.len() as u8);
     out.extend_from_slice(literals);
-    literals.clear();
 }
 
 fn find_longest_match(input: &[u8], pos: usize) -> (usize, usize) {
@@ -177,3 +729,248 @@ fn find_longest_match(input: &[u8], pos: usize) -> (usize, usize) {
 
     (best_dist, best_len)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Enregistrement `Data` de clé `k` dont la valeur stockée est `value`,
+    /// avec un checksum valide : le décodage atteint la valeur compressée.
+    fn data_record(value: &[u8]) -> Vec<u8> {
+        let mut out = vec![0];
+        out.extend_from_slice(&1u32.to_be_bytes());
+        out.extend_from_slice(&(value.len() as u32).to_be_bytes());
+        out.push(b'k');
+        out.extend_from_slice(value);
+        let sum = checksum(&out);
+        out.extend_from_slice(&sum.to_be_bytes());
+        out
+    }
+
+    /// Entrées minimisées issues du fuzzing de `decode_record` : chacune doit
+    /// être rejetée sans panic.
+    const REGRESSIONS: &[&[u8]] = &[
+        &[],
+        &[0, 0, 0, 0],
+        // Tailles de clé et de valeur maximales : débordement sur 32 bits.
+        &[
+            0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0,
+        ],
+        &[0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0],
+        // Métadonnées annoncées mais absentes, ou plus longues que l'entrée.
+        &[0x80, 0, 0, 0, 0, 0, 0, 0, 0],
+        &[0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 1, 2],
+        // Type inconnu.
+        &[0x07, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        // Checksum faux.
+        &[0, 0, 0, 0, 1, 0, 0, 0, 0, b'k', 0, 0, 0, 0],
+    ];
+
+    #[test]
+    fn malformed_records_are_rejected() {
+        for input in REGRESSIONS {
+            assert!(decode_record(input).is_err(), "{:?}", input);
+            assert!(verify_record(input).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn malformed_compressed_values_are_rejected() {
+        let values: &[&[u8]] = &[
+            // Référence arrière avant toute sortie.
+            &[1, 0, 1, 4],
+            // Référence arrière tronquée.
+            &[0, 1, b'a', 1, 0],
+            // Littéraux annoncés au-delà de la fin.
+            &[0, 200, b'a'],
+            &[0, 0],
+            &[0],
+            // Étiquette inconnue.
+            &[2, 0, 0],
+        ];
+        for value in values {
+            let input = data_record(value);
+            assert!(verify_record(&input).is_ok(), "{:?}", value);
+            assert!(decode_record(&input).is_err(), "{:?}", value);
+            let record = verify_record(&input).unwrap();
+            assert!(
+                record.decoded_len(MAX_EXPANSION, None).is_err(),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn expansion_beyond_limit_is_rejected() {
+        // Un littéral puis des copies de 255 octets : 1 + 255 × 4 octets
+        // décodés pour 19 stockés.
+        let mut value = vec![0, 1, b'a'];
+        for _ in 0..4 {
+            value.extend_from_slice(&[1, 0, 1, 255]);
+        }
+        let input = data_record(&value);
+        let record = verify_record(&input).unwrap();
+        assert_eq!(record.decoded_len(MAX_EXPANSION, None).unwrap(), 1021);
+        assert!(matches!(
+            record.decode_value(8, None),
+            Err(DatabaseError::CorruptedData)
+        ));
+        assert!(matches!(
+            record.decoded_len(8, None),
+            Err(DatabaseError::CorruptedData)
+        ));
+    }
+
+    #[test]
+    fn valid_record_roundtrips() {
+        let record = Record {
+            entry_type: EntryType::Data,
+            key: b"cle".to_vec(),
+            value: b"valeur valeur valeur".to_vec(),
+        };
+        let mut encoded = Vec::new();
+        let len = encode_record_into(&record, b"m", None, None, &mut encoded);
+        let (decoded, read) = decode_record(&encoded).unwrap();
+        assert_eq!(read, len);
+        assert_eq!(decoded.record, record);
+        assert_eq!(decoded.meta, b"m");
+        assert!(!decoded.raw);
+    }
+
+    #[test]
+    fn raw_flag_and_missing_meta_are_reported() {
+        let record = Record {
+            entry_type: EntryType::Data,
+            key: b"k".to_vec(),
+            value: b"x".to_vec(),
+        };
+        let mut encoded = Vec::new();
+        // Une valeur d'un octet grossit à la compression : stockée brute.
+        encode_record_into(&record, b"", Some(0), None, &mut encoded);
+        let (decoded, _) = decode_record(&encoded).unwrap();
+        assert_eq!(decoded.record, record);
+        assert!(decoded.meta.is_empty());
+        assert!(decoded.raw);
+    }
+
+    /// Petites valeurs JSON de même forme, que LZ77 seul réduit à peine.
+    fn json_values(count: usize) -> Vec<Vec<u8>> {
+        (0..count)
+            .map(|i| {
+                format!(
+                    r#"{{"id":{i},"name":"utilisateur {i}","email":"u{i}@example.com","active":true,"roles":["lecteur"]}}"#
+                )
+                .into_bytes()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dictionary_shrinks_similar_values() {
+        let values = json_values(100);
+        let dictionary = Dictionary::train(7, &values[..10]).unwrap();
+        assert!(dictionary.bytes.len() <= MAX_DICTIONARY_LEN);
+
+        let (mut plain, mut trained) = (Vec::new(), Vec::new());
+        for value in &values[10..] {
+            let record = Record {
+                entry_type: EntryType::Data,
+                key: b"k".to_vec(),
+                value: value.clone(),
+            };
+            encode_record_into(&record, &[], None, None, &mut plain);
+            let start = trained.len();
+            encode_record_into(&record, &[], None, Some(&dictionary), &mut trained);
+            let (decoded, _) =
+                decode_record_with_dictionary(&trained[start..], Some(&dictionary)).unwrap();
+            assert_eq!(decoded.record, record);
+            assert_eq!(decoded.dictionary, Some(7));
+        }
+        assert!(
+            trained.len() * 2 < plain.len(),
+            "{} / {}",
+            trained.len(),
+            plain.len()
+        );
+    }
+
+    #[test]
+    fn dictionary_values_need_the_same_dictionary() {
+        let dictionary = Dictionary {
+            id: 1,
+            bytes: b"valeur de reference".to_vec(),
+        };
+        let record = Record {
+            entry_type: EntryType::Data,
+            key: b"k".to_vec(),
+            value: b"une valeur de reference".to_vec(),
+        };
+        let mut encoded = Vec::new();
+        encode_record_into(&record, &[], None, Some(&dictionary), &mut encoded);
+        assert_eq!(encoded[0] & DICT_FLAG, DICT_FLAG);
+
+        let other = Dictionary {
+            id: 2,
+            ..dictionary.clone()
+        };
+        for wrong in [None, Some(&other)] {
+            assert!(matches!(
+                decode_record_with_dictionary(&encoded, wrong),
+                Err(DatabaseError::InvalidFormat)
+            ));
+        }
+        let raw = verify_record(&encoded).unwrap();
+        assert_eq!(
+            raw.decoded_len(MAX_EXPANSION, Some(&dictionary)).unwrap(),
+            record.value.len()
+        );
+    }
+
+    #[test]
+    fn dictionary_record_roundtrips() {
+        let dictionary = Dictionary {
+            id: 0x0102_0304,
+            bytes: b"octets du dictionnaire".to_vec(),
+        };
+        let encoded = dictionary.to_record();
+        assert_eq!(encoded.len(), dictionary.record_len());
+        let (decoded, _) = decode_record(&encoded).unwrap();
+        assert_eq!(decoded.record.entry_type, EntryType::Dictionary);
+        assert!(decoded.raw);
+        let raw = verify_record(&encoded).unwrap();
+        assert_eq!(Dictionary::from_record(&raw).unwrap(), dictionary);
+    }
+
+    #[test]
+    fn blob_references_are_relocated() {
+        let mut encoded = Vec::new();
+        encode_reference_into(b"k", b"meta", 42, &mut encoded);
+        let (decoded, len) = decode_record(&encoded).unwrap();
+        assert_eq!(len, encoded.len());
+        assert_eq!((decoded.blob, decoded.meta), (Some(42), b"meta".to_vec()));
+        assert!(decoded.record.value.is_empty());
+        assert!(matches!(
+            verify_record(&encoded)
+                .unwrap()
+                .decode_value(MAX_EXPANSION, None),
+            Err(DatabaseError::InvalidFormat)
+        ));
+
+        relocate_reference(&mut encoded, 0x0102_0304_0506);
+        assert_eq!(
+            verify_record(&encoded).unwrap().blob,
+            Some(0x0102_0304_0506)
+        );
+
+        // Une référence n'a pas d'autre représentation de sa valeur.
+        encoded[0] |= RAW_FLAG;
+        let checksum_at = encoded.len() - CHECKSUM_LEN;
+        let checksum = checksum(&encoded[..checksum_at]).to_be_bytes();
+        encoded[checksum_at..].copy_from_slice(&checksum);
+        assert!(matches!(
+            verify_record(&encoded),
+            Err(DatabaseError::InvalidFormat)
+        ));
+    }
+}
@@ -0,0 +1,424 @@
+use crate::db::{KeyValue, MyDatabase};
+use crate::error::DatabaseError;
+use crate::merge::{self, MergeOp, Value};
+use std::collections::BTreeSet;
+
+impl MyDatabase {
+    /// Ajoute des éléments en tête de liste (créée si absente).
+    /// Retourne la nouvelle longueur ; sans élément, rien n'est écrit.
+    pub fn lpush(&self, key: Vec<u8>, items: Vec<Vec<u8>>) -> Result<usize, DatabaseError> {
+        self.push(key, items, true)
+    }
+
+    /// Ajoute des éléments en fin de liste (créée si absente).
+    /// Retourne la nouvelle longueur ; sans élément, rien n'est écrit.
+    pub fn rpush(&self, key: Vec<u8>, items: Vec<Vec<u8>>) -> Result<usize, DatabaseError> {
+        self.push(key, items, false)
+    }
+
+    /// Retire et retourne le premier élément de la liste.
+    pub fn lpop(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.pop(key, true)
+    }
+
+    /// Retire et retourne le dernier élément de la liste.
+    pub fn rpop(&self, key: Vec<u8>) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.pop(key, false)
+    }
+
+    /// Retourne les éléments entre `start` et `stop` inclus. Les indices
+    /// négatifs partent de la fin (`-1` = dernier élément).
+    pub fn lrange(&self, key: &[u8], start: i64, stop: i64) -> Result<Vec<Vec<u8>>, DatabaseError> {
+        let list = match self.read_value(key)? {
+            None => return Ok(Vec::new()),
+            Some(Value::List(list)) => list,
+            Some(_) => return Err(DatabaseError::WrongType),
+        };
+
+        let len = list.len() as i64;
+        let start = if start < 0 {
+            (len + start).max(0)
+        } else {
+            start
+        };
+        let stop = if stop < 0 {
+            len + stop
+        } else {
+            stop.min(len - 1)
+        };
+        if start > stop {
+            return Ok(Vec::new());
+        }
+        Ok(list
+            .into_iter()
+            .skip(start as usize)
+            .take((stop - start + 1) as usize)
+            .collect())
+    }
+
+    /// Définit un champ d'une table de hachage (créée si absente).
+    /// Retourne `true` si le champ n'existait pas.
+    pub fn hset(
+        &self,
+        key: Vec<u8>,
+        field: Vec<u8>,
+        value: Vec<u8>,
+    ) -> Result<bool, DatabaseError> {
+        self.update(key, |current| {
+            let created = match current {
+                None => true,
+                Some(Value::Hash(map)) => !map.contains_key(&field),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            Ok((Some(MergeOp::HashSet { field, value }), created))
+        })
+    }
+
+    /// Lit un champ d'une table de hachage.
+    pub fn hget(&self, key: &[u8], field: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(None),
+            Some(Value::Hash(mut map)) => Ok(map.remove(field)),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Supprime des champs d'une table de hachage. Retourne le nombre de
+    /// champs effectivement supprimés.
+    pub fn hdel(&self, key: Vec<u8>, fields: Vec<Vec<u8>>) -> Result<usize, DatabaseError> {
+        self.update(key, |current| {
+            let removed = match current {
+                None => return Ok((None, 0)),
+                Some(Value::Hash(map)) => fields.iter().filter(|f| map.contains_key(*f)).count(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            if removed == 0 {
+                return Ok((None, 0));
+            }
+            Ok((Some(MergeOp::HashDel { fields }), removed))
+        })
+    }
+
+    /// Retourne tous les champs d'une table de hachage, triés par nom.
+    pub fn hgetall(&self, key: &[u8]) -> Result<Vec<KeyValue>, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(Vec::new()),
+            Some(Value::Hash(map)) => Ok(map.into_iter().collect()),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Ajoute des membres à un ensemble (créé si absent). Retourne le nombre
+    /// de membres réellement ajoutés.
+    pub fn sadd(&self, key: Vec<u8>, members: Vec<Vec<u8>>) -> Result<usize, DatabaseError> {
+        self.update(key, |current| {
+            let added = match current {
+                None => members.iter().collect::<BTreeSet<_>>().len(),
+                Some(Value::Set(set)) => members
+                    .iter()
+                    .filter(|m| !set.contains(*m))
+                    .collect::<BTreeSet<_>>()
+                    .len(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            if added == 0 {
+                return Ok((None, 0));
+            }
+            Ok((Some(MergeOp::SetAdd { members }), added))
+        })
+    }
+
+    /// Retire des membres d'un ensemble. Retourne le nombre de membres
+    /// effectivement retirés.
+    pub fn srem(&self, key: Vec<u8>, members: Vec<Vec<u8>>) -> Result<usize, DatabaseError> {
+        self.update(key, |current| {
+            let removed = match current {
+                None => return Ok((None, 0)),
+                Some(Value::Set(set)) => members
+                    .iter()
+                    .filter(|m| set.contains(*m))
+                    .collect::<BTreeSet<_>>()
+                    .len(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            if removed == 0 {
+                return Ok((None, 0));
+            }
+            Ok((Some(MergeOp::SetRem { members }), removed))
+        })
+    }
+
+    /// Indique si `member` appartient à l'ensemble.
+    pub fn sismember(&self, key: &[u8], member: &[u8]) -> Result<bool, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(false),
+            Some(Value::Set(set)) => Ok(set.contains(member)),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Retourne les membres de l'ensemble, triés.
+    pub fn smembers(&self, key: &[u8]) -> Result<Vec<Vec<u8>>, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(Vec::new()),
+            Some(Value::Set(set)) => Ok(set.into_iter().collect()),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Ajoute ou met à jour un membre d'un ensemble trié (créé si absent).
+    /// Retourne `true` si le membre est nouveau.
+    pub fn zadd(&self, key: Vec<u8>, score: f64, member: Vec<u8>) -> Result<bool, DatabaseError> {
+        if score.is_nan() {
+            return Err(DatabaseError::ParseError("score NaN refusé".to_string()));
+        }
+        self.update(key, |current| {
+            let created = match current {
+                None => true,
+                Some(Value::SortedSet(zset)) => zset.score(&member).is_none(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            Ok((Some(MergeOp::ZAdd { score, member }), created))
+        })
+    }
+
+    /// Retire des membres d'un ensemble trié. Retourne le nombre de membres
+    /// effectivement retirés.
+    pub fn zrem(&self, key: Vec<u8>, members: Vec<Vec<u8>>) -> Result<usize, DatabaseError> {
+        self.update(key, |current| {
+            let removed = match current {
+                None => return Ok((None, 0)),
+                Some(Value::SortedSet(zset)) => members
+                    .iter()
+                    .filter(|m| zset.score(m).is_some())
+                    .collect::<BTreeSet<_>>()
+                    .len(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            if removed == 0 {
+                return Ok((None, 0));
+            }
+            Ok((Some(MergeOp::ZRem { members }), removed))
+        })
+    }
+
+    /// Score d'un membre d'un ensemble trié.
+    pub fn zscore(&self, key: &[u8], member: &[u8]) -> Result<Option<f64>, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(None),
+            Some(Value::SortedSet(zset)) => Ok(zset.score(member)),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Membres dont le score est compris entre `min` et `max` inclus, par
+    /// score croissant puis par membre.
+    pub fn zrange_by_score(
+        &self,
+        key: &[u8],
+        min: f64,
+        max: f64,
+    ) -> Result<Vec<(Vec<u8>, f64)>, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(Vec::new()),
+            Some(Value::SortedSet(zset)) => Ok(zset.range_by_score(min, max)),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Positionne un bit d'une valeur simple (créée et étendue avec des zéros
+    /// si besoin) via un petit enregistrement delta. Retourne l'ancien bit.
+    pub fn setbit(&self, key: Vec<u8>, offset: u64, bit: bool) -> Result<bool, DatabaseError> {
+        if offset > merge::MAX_BIT_OFFSET {
+            return Err(DatabaseError::ParseError(format!(
+                "décalage de bit hors limites: {}",
+                offset
+            )));
+        }
+        self.update(key, |current| {
+            let previous = match current {
+                None => false,
+                Some(Value::Bytes(bytes)) => merge::get_bit(bytes, offset),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            Ok((Some(MergeOp::SetBit { offset, bit }), previous))
+        })
+    }
+
+    /// Lit un bit d'une valeur simple (0 si la clé ou le bit n'existe pas).
+    pub fn getbit(&self, key: &[u8], offset: u64) -> Result<bool, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(false),
+            Some(Value::Bytes(bytes)) => Ok(merge::get_bit(&bytes, offset)),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    /// Nombre de bits à 1 dans une valeur simple.
+    pub fn bitcount(&self, key: &[u8]) -> Result<u64, DatabaseError> {
+        match self.read_value(key)? {
+            None => Ok(0),
+            Some(Value::Bytes(bytes)) => {
+                Ok(bytes.iter().map(|byte| u64::from(byte.count_ones())).sum())
+            }
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
+
+    fn push(&self, key: Vec<u8>, items: Vec<Vec<u8>>, front: bool) -> Result<usize, DatabaseError> {
+        self.update(key, |current| {
+            let len = match current {
+                None => 0,
+                Some(Value::List(list)) => list.len(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            if items.is_empty() {
+                return Ok((None, len));
+            }
+            let new_len = len + items.len();
+            Ok((Some(MergeOp::Push { front, items }), new_len))
+        })
+    }
+
+    fn pop(&self, key: Vec<u8>, front: bool) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.update(key, |current| {
+            let item = match current {
+                None => return Ok((None, None)),
+                Some(Value::List(list)) if front => list.front().cloned(),
+                Some(Value::List(list)) => list.back().cloned(),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            Ok((Some(MergeOp::Pop { front, count: 1 }), item))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::DatabaseError;
+    use crate::testutil::TempDir;
+
+    fn items(values: &[&str]) -> Vec<Vec<u8>> {
+        values.iter().map(|v| v.as_bytes().to_vec()).collect()
+    }
+
+    #[test]
+    fn lists_push_pop_and_range() {
+        let dir = TempDir::new("collections-list");
+        let db = dir.open();
+        assert_eq!(db.rpush(b"l".to_vec(), items(&["b", "c"])).unwrap(), 2);
+        assert_eq!(db.lpush(b"l".to_vec(), items(&["a", "z"])).unwrap(), 4);
+        assert_eq!(
+            db.lrange(b"l", 0, -1).unwrap(),
+            items(&["z", "a", "b", "c"])
+        );
+        assert_eq!(db.lrange(b"l", -2, 10).unwrap(), items(&["b", "c"]));
+        assert_eq!(db.lrange(b"l", 3, 1).unwrap(), items(&[]));
+        assert_eq!(db.lpop(b"l".to_vec()).unwrap(), Some(b"z".to_vec()));
+        assert_eq!(db.rpop(b"l".to_vec()).unwrap(), Some(b"c".to_vec()));
+        assert_eq!(db.lrange(b"l", 0, -1).unwrap(), items(&["a", "b"]));
+        assert_eq!(db.lpop(b"absent".to_vec()).unwrap(), None);
+    }
+
+    #[test]
+    fn empty_push_writes_nothing() {
+        let dir = TempDir::new("collections-empty-push");
+        let db = dir.open();
+        db.rpush(b"l".to_vec(), items(&["a"])).unwrap();
+        let size = db.file_size().unwrap();
+        assert_eq!(db.lpush(b"l".to_vec(), Vec::new()).unwrap(), 1);
+        assert_eq!(db.rpush(b"absent".to_vec(), Vec::new()).unwrap(), 0);
+        assert_eq!(db.file_size().unwrap(), size);
+        assert!(!db.contains_key(b"absent").unwrap());
+    }
+
+    #[test]
+    fn hashes_and_sets() {
+        let dir = TempDir::new("collections-hash-set");
+        let db = dir.open();
+        assert!(
+            db.hset(b"h".to_vec(), b"f".to_vec(), b"1".to_vec())
+                .unwrap()
+        );
+        assert!(
+            !db.hset(b"h".to_vec(), b"f".to_vec(), b"2".to_vec())
+                .unwrap()
+        );
+        db.hset(b"h".to_vec(), b"g".to_vec(), b"3".to_vec())
+            .unwrap();
+        assert_eq!(db.hget(b"h", b"f").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(db.hdel(b"h".to_vec(), items(&["g", "x"])).unwrap(), 1);
+        assert_eq!(
+            db.hgetall(b"h").unwrap(),
+            vec![(b"f".to_vec(), b"2".to_vec())]
+        );
+
+        assert_eq!(db.sadd(b"s".to_vec(), items(&["a", "b", "a"])).unwrap(), 2);
+        assert_eq!(db.sadd(b"s".to_vec(), items(&["b"])).unwrap(), 0);
+        assert_eq!(db.srem(b"s".to_vec(), items(&["a", "x"])).unwrap(), 1);
+        assert!(db.sismember(b"s", b"b").unwrap());
+        assert_eq!(db.smembers(b"s").unwrap(), items(&["b"]));
+    }
+
+    #[test]
+    fn sorted_sets_and_bits() {
+        let dir = TempDir::new("collections-zset-bits");
+        let db = dir.open();
+        assert!(db.zadd(b"z".to_vec(), 2.0, b"b".to_vec()).unwrap());
+        assert!(db.zadd(b"z".to_vec(), 1.0, b"a".to_vec()).unwrap());
+        assert!(!db.zadd(b"z".to_vec(), 3.0, b"a".to_vec()).unwrap());
+        assert!(db.zadd(b"z".to_vec(), f64::NAN, b"c".to_vec()).is_err());
+        assert_eq!(db.zscore(b"z", b"a").unwrap(), Some(3.0));
+        assert_eq!(
+            db.zrange_by_score(b"z", 0.0, 10.0).unwrap(),
+            vec![(b"b".to_vec(), 2.0), (b"a".to_vec(), 3.0)]
+        );
+        assert_eq!(db.zrem(b"z".to_vec(), items(&["b"])).unwrap(), 1);
+
+        assert!(!db.setbit(b"bits".to_vec(), 9, true).unwrap());
+        assert!(db.setbit(b"bits".to_vec(), 9, true).unwrap());
+        assert!(db.getbit(b"bits", 9).unwrap());
+        assert!(!db.getbit(b"bits", 1000).unwrap());
+        assert_eq!(db.bitcount(b"bits").unwrap(), 1);
+    }
+
+    #[test]
+    fn wrong_type_is_rejected() {
+        let dir = TempDir::new("collections-wrong-type");
+        let db = dir.open();
+        db.rpush(b"l".to_vec(), items(&["a"])).unwrap();
+        db.set(b"v".to_vec(), b"x".to_vec()).unwrap();
+        assert!(matches!(
+            db.sadd(b"l".to_vec(), items(&["a"])),
+            Err(DatabaseError::WrongType)
+        ));
+        assert!(matches!(db.hget(b"l", b"f"), Err(DatabaseError::WrongType)));
+        assert!(matches!(
+            db.lpush(b"v".to_vec(), items(&["a"])),
+            Err(DatabaseError::WrongType)
+        ));
+        assert_eq!(db.lrange(b"l", 0, -1).unwrap(), items(&["a"]));
+    }
+
+    #[test]
+    fn collections_survive_reopen_and_compaction() {
+        let dir = TempDir::new("collections-reopen");
+        {
+            let db = dir.open();
+            db.rpush(b"l".to_vec(), items(&["a", "b"])).unwrap();
+            db.lpop(b"l".to_vec()).unwrap();
+            db.hset(b"h".to_vec(), b"f".to_vec(), b"1".to_vec())
+                .unwrap();
+            db.sadd(b"s".to_vec(), items(&["x", "y"])).unwrap();
+        }
+        let db = dir.open();
+        assert_eq!(db.lrange(b"l", 0, -1).unwrap(), items(&["b"]));
+        db.compact().unwrap();
+        assert_eq!(db.lrange(b"l", 0, -1).unwrap(), items(&["b"]));
+        assert_eq!(db.hget(b"h", b"f").unwrap(), Some(b"1".to_vec()));
+        drop(db);
+        let db = dir.open();
+        assert_eq!(db.smembers(b"s").unwrap(), items(&["x", "y"]));
+        assert_eq!(db.lrange(b"l", 0, -1).unwrap(), items(&["b"]));
+    }
+}
@@ -0,0 +1,1130 @@
+//! Analyse des lignes saisies dans le REPL : découpage en mots, puis
+//! reconnaissance de la commande et validation de ses arguments.
+
+use crate::table::Format;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Commande du REPL, arguments validés.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Set {
+        key: Vec<u8>,
+        value: SetValue,
+    },
+    Get {
+        key: Vec<u8>,
+        /// Fichier où écrire la valeur (`--file`).
+        file: Option<PathBuf>,
+    },
+    Size {
+        key: Vec<u8>,
+    },
+    Delete {
+        key: Vec<u8>,
+    },
+    Purge {
+        key: Vec<u8>,
+    },
+    /// `LPUSH` si `front`, sinon `RPUSH`.
+    Push {
+        front: bool,
+        key: Vec<u8>,
+        items: Vec<Vec<u8>>,
+    },
+    /// `LPOP` si `front`, sinon `RPOP`.
+    Pop {
+        front: bool,
+        key: Vec<u8>,
+    },
+    LRange {
+        key: Vec<u8>,
+        start: i64,
+        stop: i64,
+    },
+    HSet {
+        key: Vec<u8>,
+        field: Vec<u8>,
+        value: Vec<u8>,
+    },
+    HGet {
+        key: Vec<u8>,
+        field: Vec<u8>,
+    },
+    HDel {
+        key: Vec<u8>,
+        fields: Vec<Vec<u8>>,
+    },
+    HGetAll {
+        key: Vec<u8>,
+    },
+    /// `SREM` si `remove`, sinon `SADD`.
+    SetUpdate {
+        remove: bool,
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+    SIsMember {
+        key: Vec<u8>,
+        member: Vec<u8>,
+    },
+    SMembers {
+        key: Vec<u8>,
+    },
+    ZAdd {
+        key: Vec<u8>,
+        score: f64,
+        member: Vec<u8>,
+    },
+    ZRem {
+        key: Vec<u8>,
+        members: Vec<Vec<u8>>,
+    },
+    ZScore {
+        key: Vec<u8>,
+        member: Vec<u8>,
+    },
+    ZRangeByScore {
+        key: Vec<u8>,
+        min: f64,
+        max: f64,
+    },
+    SetBit {
+        key: Vec<u8>,
+        offset: u64,
+        bit: bool,
+    },
+    GetBit {
+        key: Vec<u8>,
+        offset: u64,
+    },
+    BitCount {
+        key: Vec<u8>,
+    },
+    Warmup(Warmup),
+    Export {
+        prefix: Vec<u8>,
+        path: PathBuf,
+    },
+    Compact(CompactMode),
+    Scan {
+        prefix: Vec<u8>,
+        /// Rendu demandé (`--format`) ; une table par défaut.
+        format: Option<Format>,
+    },
+    Log {
+        limit: Option<usize>,
+        /// Rendu demandé (`--format`) ; une table par défaut.
+        format: Option<Format>,
+    },
+    Reindex,
+    Stats {
+        /// Rendu demandé (`--format`) ; des lignes de texte par défaut.
+        format: Option<Format>,
+    },
+    Health,
+    /// Aide générale, ou d'une commande (`topic`, en majuscules).
+    Help {
+        topic: Option<String>,
+    },
+    Exit,
+}
+
+/// Source de la valeur de `SET`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SetValue {
+    /// Mots restants de la ligne, joints par une espace.
+    Inline(Vec<u8>),
+    /// Contenu d'un fichier (`--file`).
+    File(PathBuf),
+    /// Lignes suivantes de l'entrée, jusqu'à la fin de l'entrée (`--stdin`)
+    /// ou jusqu'à une ligne égale au délimiteur (`<<MOT`).
+    Lines { delimiter: Option<String> },
+}
+
+/// Clés à précharger par `WARMUP`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warmup {
+    /// Toutes les clés d'un préfixe (vide = toutes).
+    Prefix(Vec<u8>),
+    /// Clés données (`--keys`).
+    Keys(Vec<Vec<u8>>),
+}
+
+/// Variante de `COMPACT`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompactMode {
+    Run,
+    /// Estimation seule (`--dry-run`).
+    DryRun,
+    /// Compaction en arrière-plan avec avancement (`--progress`).
+    Progress,
+}
+
+/// Ligne que le REPL ne peut pas exécuter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// Mot de commande inconnu.
+    Unknown(String),
+    /// Arguments manquants ou invalides pour la commande nommée.
+    Usage(&'static str),
+    /// Guillemet ouvrant sans guillemet fermant.
+    UnterminatedQuote,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Unknown(name) => write!(f, "Commande inconnue: {}", name),
+            ParseError::Usage(name) => match find(name) {
+                Some(spec) => spec.write_usage(f),
+                None => write!(f, "Usage: {}", name),
+            },
+            ParseError::UnterminatedQuote => write!(f, "Guillemet non fermé"),
+        }
+    }
+}
+
+impl Command {
+    /// Mot de commande, tel qu'affiché dans les messages.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Set { .. } => "SET",
+            Command::Get { .. } => "GET",
+            Command::Size { .. } => "SIZE",
+            Command::Delete { .. } => "DELETE",
+            Command::Purge { .. } => "PURGE",
+            Command::Push { front: true, .. } => "LPUSH",
+            Command::Push { front: false, .. } => "RPUSH",
+            Command::Pop { front: true, .. } => "LPOP",
+            Command::Pop { front: false, .. } => "RPOP",
+            Command::LRange { .. } => "LRANGE",
+            Command::HSet { .. } => "HSET",
+            Command::HGet { .. } => "HGET",
+            Command::HDel { .. } => "HDEL",
+            Command::HGetAll { .. } => "HGETALL",
+            Command::SetUpdate { remove: false, .. } => "SADD",
+            Command::SetUpdate { remove: true, .. } => "SREM",
+            Command::SIsMember { .. } => "SISMEMBER",
+            Command::SMembers { .. } => "SMEMBERS",
+            Command::ZAdd { .. } => "ZADD",
+            Command::ZRem { .. } => "ZREM",
+            Command::ZScore { .. } => "ZSCORE",
+            Command::ZRangeByScore { .. } => "ZRANGEBYSCORE",
+            Command::SetBit { .. } => "SETBIT",
+            Command::GetBit { .. } => "GETBIT",
+            Command::BitCount { .. } => "BITCOUNT",
+            Command::Warmup(_) => "WARMUP",
+            Command::Export { .. } => "EXPORT",
+            Command::Compact(_) => "COMPACT",
+            Command::Scan { .. } => "SCAN",
+            Command::Log { .. } => "LOG",
+            Command::Reindex => "REINDEX",
+            Command::Stats { .. } => "STATS",
+            Command::Health => "HEALTH",
+            Command::Help { .. } => "HELP",
+            Command::Exit => "EXIT",
+        }
+    }
+}
+
+/// Analyse une ligne saisie ; `None` pour une ligne blanche. Le mot de
+/// commande est insensible à la casse.
+pub fn parse(line: &str) -> Result<Option<Command>, ParseError> {
+    let words = tokenize(line)?;
+    let Some((name, args)) = words.split_first() else {
+        return Ok(None);
+    };
+    let name = name.to_uppercase();
+    let Some(spec) = find(&name) else {
+        return Err(ParseError::Unknown(name));
+    };
+    let name = spec.name;
+    if max_args(name).is_some_and(|max| args.len() > max) {
+        return Err(ParseError::Usage(name));
+    }
+    let command = match name {
+        "SET" => parse_set(args, name)?,
+        "GET" => Command::Get {
+            key: key(args, 0, name)?,
+            file: match args {
+                [_, flag, path, ..] if flag == "--file" => Some(PathBuf::from(path)),
+                _ => None,
+            },
+        },
+        "SIZE" => Command::Size {
+            key: key(args, 0, name)?,
+        },
+        "DELETE" => Command::Delete {
+            key: key(args, 0, name)?,
+        },
+        "PURGE" => Command::Purge {
+            key: key(args, 0, name)?,
+        },
+        "LPUSH" | "RPUSH" => Command::Push {
+            front: name == "LPUSH",
+            key: key(args, 0, name)?,
+            items: rest(args, 1, name)?,
+        },
+        "LPOP" | "RPOP" => Command::Pop {
+            front: name == "LPOP",
+            key: key(args, 0, name)?,
+        },
+        "LRANGE" => Command::LRange {
+            key: key(args, 0, name)?,
+            start: number(args, 1, name)?,
+            stop: number(args, 2, name)?,
+        },
+        "HSET" => Command::HSet {
+            key: key(args, 0, name)?,
+            field: key(args, 1, name)?,
+            value: joined(args, 2, name)?,
+        },
+        "HGET" => Command::HGet {
+            key: key(args, 0, name)?,
+            field: key(args, 1, name)?,
+        },
+        "HDEL" => Command::HDel {
+            key: key(args, 0, name)?,
+            fields: rest(args, 1, name)?,
+        },
+        "HGETALL" => Command::HGetAll {
+            key: key(args, 0, name)?,
+        },
+        "SADD" | "SREM" => Command::SetUpdate {
+            remove: name == "SREM",
+            key: key(args, 0, name)?,
+            members: rest(args, 1, name)?,
+        },
+        "SISMEMBER" => Command::SIsMember {
+            key: key(args, 0, name)?,
+            member: key(args, 1, name)?,
+        },
+        "SMEMBERS" => Command::SMembers {
+            key: key(args, 0, name)?,
+        },
+        "ZADD" => Command::ZAdd {
+            key: key(args, 0, name)?,
+            score: number(args, 1, name)?,
+            member: key(args, 2, name)?,
+        },
+        "ZREM" => Command::ZRem {
+            key: key(args, 0, name)?,
+            members: rest(args, 1, name)?,
+        },
+        "ZSCORE" => Command::ZScore {
+            key: key(args, 0, name)?,
+            member: key(args, 1, name)?,
+        },
+        "ZRANGEBYSCORE" => Command::ZRangeByScore {
+            key: key(args, 0, name)?,
+            min: number(args, 1, name)?,
+            max: number(args, 2, name)?,
+        },
+        "SETBIT" => Command::SetBit {
+            key: key(args, 0, name)?,
+            offset: number(args, 1, name)?,
+            bit: match args.get(2).map(String::as_str) {
+                Some("0") => false,
+                Some("1") => true,
+                _ => return Err(ParseError::Usage(name)),
+            },
+        },
+        "GETBIT" => Command::GetBit {
+            key: key(args, 0, name)?,
+            offset: number(args, 1, name)?,
+        },
+        "BITCOUNT" => Command::BitCount {
+            key: key(args, 0, name)?,
+        },
+        "WARMUP" => Command::Warmup(match args {
+            [flag, ..] if flag == "--keys" => Warmup::Keys(rest(args, 1, name)?),
+            [prefix] => Warmup::Prefix(prefix.as_bytes().to_vec()),
+            [] => Warmup::Prefix(Vec::new()),
+            _ => return Err(ParseError::Usage(name)),
+        }),
+        "EXPORT" => match args {
+            [prefix, path] => Command::Export {
+                prefix: prefix.as_bytes().to_vec(),
+                path: PathBuf::from(path),
+            },
+            _ => return Err(ParseError::Usage(name)),
+        },
+        "COMPACT" => Command::Compact(match args.first().map(String::as_str) {
+            None => CompactMode::Run,
+            Some("--dry-run") => CompactMode::DryRun,
+            Some("--progress") => CompactMode::Progress,
+            Some(_) => return Err(ParseError::Usage(name)),
+        }),
+        "SCAN" => {
+            let (prefix, options) = match args.split_first() {
+                Some((prefix, options)) if !prefix.starts_with("--") => {
+                    (prefix.as_bytes().to_vec(), options)
+                }
+                _ => (Vec::new(), args),
+            };
+            check_options(options, &["--format"], name)?;
+            Command::Scan {
+                prefix,
+                format: format(options, name)?,
+            }
+        }
+        "LOG" => {
+            check_options(args, &["--limit", "--format"], name)?;
+            Command::Log {
+                limit: option(args, "--limit")
+                    .map(|limit| limit.parse().map_err(|_| ParseError::Usage(name)))
+                    .transpose()?,
+                format: format(args, name)?,
+            }
+        }
+        "REINDEX" => Command::Reindex,
+        "STATS" => {
+            check_options(args, &["--format"], name)?;
+            Command::Stats {
+                format: format(args, name)?,
+            }
+        }
+        "HEALTH" => Command::Health,
+        "HELP" => Command::Help {
+            topic: args.first().map(|topic| topic.to_uppercase()),
+        },
+        "EXIT" => Command::Exit,
+        _ => return Err(ParseError::Unknown(name.to_string())),
+    };
+    Ok(Some(command))
+}
+
+/// Nombre maximal d'arguments des commandes à arguments fixes ; au-delà,
+/// la ligne est rejetée plutôt que tronquée en silence.
+fn max_args(name: &str) -> Option<usize> {
+    match name {
+        "REINDEX" | "HEALTH" | "EXIT" => Some(0),
+        "SIZE" | "DELETE" | "PURGE" | "LPOP" | "RPOP" | "HGETALL" | "SMEMBERS" | "BITCOUNT"
+        | "HELP" | "COMPACT" => Some(1),
+        "HGET" | "SISMEMBER" | "ZSCORE" | "GETBIT" | "STATS" => Some(2),
+        "GET" | "LRANGE" | "ZADD" | "ZRANGEBYSCORE" | "SETBIT" | "SCAN" => Some(3),
+        "LOG" => Some(4),
+        _ => None,
+    }
+}
+
+fn parse_set(args: &[String], name: &'static str) -> Result<Command, ParseError> {
+    let key = key(args, 0, name)?;
+    let value = match args.get(1) {
+        Some(flag) if flag == "--file" => {
+            let path = args.get(2).ok_or(ParseError::Usage(name))?;
+            SetValue::File(PathBuf::from(path))
+        }
+        Some(flag) if flag == "--stdin" => SetValue::Lines { delimiter: None },
+        Some(flag) if flag.len() > 2 && flag.starts_with("<<") => SetValue::Lines {
+            delimiter: Some(flag[2..].to_string()),
+        },
+        _ => SetValue::Inline(joined(args, 1, name)?),
+    };
+    Ok(Command::Set { key, value })
+}
+
+/// Argument `at`, en octets.
+fn key(args: &[String], at: usize, name: &'static str) -> Result<Vec<u8>, ParseError> {
+    args.get(at)
+        .map(|arg| arg.as_bytes().to_vec())
+        .ok_or(ParseError::Usage(name))
+}
+
+/// Argument `at`, converti en nombre.
+fn number<T: std::str::FromStr>(
+    args: &[String],
+    at: usize,
+    name: &'static str,
+) -> Result<T, ParseError> {
+    args.get(at)
+        .and_then(|arg| arg.parse().ok())
+        .ok_or(ParseError::Usage(name))
+}
+
+/// Valeur qui suit l'option `flag`, où qu'elle soit.
+fn option<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    let at = args.iter().position(|arg| arg == flag)?;
+    args.get(at + 1).map(String::as_str)
+}
+
+/// Vérifie que `args` n'est fait que de couples `--option valeur` pris dans
+/// `allowed`, chaque option au plus une fois.
+fn check_options(args: &[String], allowed: &[&str], name: &'static str) -> Result<(), ParseError> {
+    let mut seen: Vec<&str> = Vec::new();
+    for pair in args.chunks(2) {
+        match pair {
+            [flag, _] if allowed.contains(&flag.as_str()) && !seen.contains(&flag.as_str()) => {
+                seen.push(flag);
+            }
+            _ => return Err(ParseError::Usage(name)),
+        }
+    }
+    Ok(())
+}
+
+/// Valeur de `--format`, qui doit être `table`, `json` ou `csv`.
+fn format(args: &[String], name: &'static str) -> Result<Option<Format>, ParseError> {
+    if !args.iter().any(|arg| arg == "--format") {
+        return Ok(None);
+    }
+    option(args, "--format")
+        .and_then(|format| format.parse().ok())
+        .map(Some)
+        .ok_or(ParseError::Usage(name))
+}
+
+/// Arguments à partir de `from`, au moins un.
+fn rest(args: &[String], from: usize, name: &'static str) -> Result<Vec<Vec<u8>>, ParseError> {
+    match args.get(from..) {
+        Some(rest) if !rest.is_empty() => {
+            Ok(rest.iter().map(|arg| arg.as_bytes().to_vec()).collect())
+        }
+        _ => Err(ParseError::Usage(name)),
+    }
+}
+
+/// Arguments à partir de `from` joints par une espace, au moins un.
+fn joined(args: &[String], from: usize, name: &'static str) -> Result<Vec<u8>, ParseError> {
+    match args.get(from..) {
+        Some(rest) if !rest.is_empty() => Ok(rest.join(" ").into_bytes()),
+        _ => Err(ParseError::Usage(name)),
+    }
+}
+
+/// Découpe `line` en mots séparés par des blancs. Entre guillemets doubles,
+/// les blancs font partie du mot et `\"`, `\\` désignent un guillemet et une
+/// barre oblique inverse ; `""` donne un mot vide.
+pub fn tokenize(line: &str) -> Result<Vec<String>, ParseError> {
+    let mut words = Vec::new();
+    let mut chars = line.chars();
+    let mut word: Option<String> = None;
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(escaped @ ('"' | '\\')) => word.push(escaped),
+                            Some(other) => {
+                                word.push('\\');
+                                word.push(other);
+                            }
+                            None => return Err(ParseError::UnterminatedQuote),
+                        },
+                        Some(other) => word.push(other),
+                        None => return Err(ParseError::UnterminatedQuote),
+                    }
+                }
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Forme d'appel d'une commande.
+pub struct Form {
+    pub usage: &'static str,
+    pub summary: &'static str,
+}
+
+/// Définition d'une commande : source de l'aide (`HELP`) et des messages
+/// d'usage.
+pub struct CommandSpec {
+    pub name: &'static str,
+    /// Autres mots acceptés pour la commande.
+    pub aliases: &'static [&'static str],
+    pub forms: &'static [Form],
+    pub description: &'static str,
+    pub examples: &'static [&'static str],
+}
+
+const fn form(usage: &'static str, summary: &'static str) -> Form {
+    Form { usage, summary }
+}
+
+/// Commandes du REPL, dans l'ordre de l'aide.
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "SET",
+        aliases: &[],
+        forms: &[
+            form("SET <clé> <valeur>", "Ajoute/met à jour une clé"),
+            form(
+                "SET <clé> --file <chemin>",
+                "Stocke le contenu d'un fichier",
+            ),
+            form(
+                "SET <clé> --stdin",
+                "Lit la valeur jusqu'à la fin de l'entrée (Ctrl-D)",
+            ),
+            form(
+                "SET <clé> <<MOT",
+                "Lit la valeur jusqu'à une ligne égale à MOT",
+            ),
+        ],
+        description: "Les mots de la valeur sont joints par une espace ; entre \
+                      guillemets doubles, la valeur est prise telle quelle. \
+                      Avec --stdin ou <<MOT, les lignes suivantes sont \
+                      stockées telles quelles, sauts de ligne compris.",
+        examples: &[
+            "SET user:1 Alice",
+            "SET note \"deux  espaces\"",
+            "SET logo --file logo.png",
+            "SET poeme <<FIN",
+        ],
+    },
+    CommandSpec {
+        name: "GET",
+        aliases: &[],
+        forms: &[
+            form("GET <clé>", "Récupère une valeur"),
+            form(
+                "GET <clé> --file <chemin>",
+                "Écrit la valeur dans un fichier",
+            ),
+        ],
+        description: "Une valeur qui n'est pas de l'UTF-8 est affichée en hexadécimal.",
+        examples: &["GET user:1", "GET logo --file copie.png"],
+    },
+    CommandSpec {
+        name: "SIZE",
+        aliases: &[],
+        forms: &[form(
+            "SIZE <clé>",
+            "Taille de la valeur (décompressée et stockée)",
+        )],
+        description: "La valeur n'est pas décompressée pour être mesurée.",
+        examples: &["SIZE logo"],
+    },
+    CommandSpec {
+        name: "DELETE",
+        aliases: &[],
+        forms: &[form("DELETE <clé>", "Supprime une clé (Tombstone)")],
+        description: "Un tombstone masque la clé ; la compaction récupère l'espace.",
+        examples: &["DELETE user:1"],
+    },
+    CommandSpec {
+        name: "PURGE",
+        aliases: &[],
+        forms: &[form(
+            "PURGE <clé>",
+            "Efface toute trace d'une clé, tombstone compris",
+        )],
+        description: "La clé disparaît du journal à la prochaine compaction.",
+        examples: &["PURGE user:1"],
+    },
+    CommandSpec {
+        name: "LPUSH",
+        aliases: &[],
+        forms: &[form("LPUSH <clé> <valeur>...", "Ajoute en tête de liste")],
+        description: "Les valeurs sont ajoutées une à une : la dernière finit en tête.",
+        examples: &["LPUSH tâches a b c"],
+    },
+    CommandSpec {
+        name: "RPUSH",
+        aliases: &[],
+        forms: &[form("RPUSH <clé> <valeur>...", "Ajoute en fin de liste")],
+        description: "Les valeurs sont ajoutées dans l'ordre donné.",
+        examples: &["RPUSH tâches a b c"],
+    },
+    CommandSpec {
+        name: "LPOP",
+        aliases: &[],
+        forms: &[form("LPOP <clé>", "Retire le premier élément d'une liste")],
+        description: "Affiche l'élément retiré.",
+        examples: &["LPOP tâches"],
+    },
+    CommandSpec {
+        name: "RPOP",
+        aliases: &[],
+        forms: &[form("RPOP <clé>", "Retire le dernier élément d'une liste")],
+        description: "Affiche l'élément retiré.",
+        examples: &["RPOP tâches"],
+    },
+    CommandSpec {
+        name: "LRANGE",
+        aliases: &[],
+        forms: &[form(
+            "LRANGE <clé> <début> <fin>",
+            "Affiche une plage de la liste",
+        )],
+        description: "Bornes incluses ; un indice négatif part de la fin.",
+        examples: &["LRANGE tâches 0 -1"],
+    },
+    CommandSpec {
+        name: "HSET",
+        aliases: &[],
+        forms: &[form(
+            "HSET <clé> <champ> <valeur>",
+            "Définit un champ de table de hachage",
+        )],
+        description: "Les mots de la valeur sont joints par une espace.",
+        examples: &["HSET user:1 nom Alice"],
+    },
+    CommandSpec {
+        name: "HGET",
+        aliases: &[],
+        forms: &[form(
+            "HGET <clé> <champ>",
+            "Lit un champ de table de hachage",
+        )],
+        description: "",
+        examples: &["HGET user:1 nom"],
+    },
+    CommandSpec {
+        name: "HDEL",
+        aliases: &[],
+        forms: &[form("HDEL <clé> <champ>...", "Supprime des champs")],
+        description: "Affiche le nombre de champs supprimés.",
+        examples: &["HDEL user:1 nom âge"],
+    },
+    CommandSpec {
+        name: "HGETALL",
+        aliases: &[],
+        forms: &[form("HGETALL <clé>", "Affiche tous les champs")],
+        description: "",
+        examples: &["HGETALL user:1"],
+    },
+    CommandSpec {
+        name: "SADD",
+        aliases: &[],
+        forms: &[form(
+            "SADD <clé> <membre>...",
+            "Ajoute des membres à un ensemble",
+        )],
+        description: "Affiche le nombre de membres ajoutés.",
+        examples: &["SADD tags rust base"],
+    },
+    CommandSpec {
+        name: "SREM",
+        aliases: &[],
+        forms: &[form(
+            "SREM <clé> <membre>...",
+            "Retire des membres d'un ensemble",
+        )],
+        description: "Affiche le nombre de membres retirés.",
+        examples: &["SREM tags base"],
+    },
+    CommandSpec {
+        name: "SISMEMBER",
+        aliases: &[],
+        forms: &[form("SISMEMBER <clé> <membre>", "Teste l'appartenance")],
+        description: "",
+        examples: &["SISMEMBER tags rust"],
+    },
+    CommandSpec {
+        name: "SMEMBERS",
+        aliases: &[],
+        forms: &[form("SMEMBERS <clé>", "Affiche les membres d'un ensemble")],
+        description: "",
+        examples: &["SMEMBERS tags"],
+    },
+    CommandSpec {
+        name: "ZADD",
+        aliases: &[],
+        forms: &[form(
+            "ZADD <clé> <score> <membre>",
+            "Ajoute à un ensemble trié",
+        )],
+        description: "Un membre déjà présent prend le nouveau score.",
+        examples: &["ZADD scores 12.5 alice"],
+    },
+    CommandSpec {
+        name: "ZREM",
+        aliases: &[],
+        forms: &[form("ZREM <clé> <membre>...", "Retire d'un ensemble trié")],
+        description: "Affiche le nombre de membres retirés.",
+        examples: &["ZREM scores alice"],
+    },
+    CommandSpec {
+        name: "ZSCORE",
+        aliases: &[],
+        forms: &[form("ZSCORE <clé> <membre>", "Score d'un membre")],
+        description: "",
+        examples: &["ZSCORE scores alice"],
+    },
+    CommandSpec {
+        name: "ZRANGEBYSCORE",
+        aliases: &[],
+        forms: &[form(
+            "ZRANGEBYSCORE <clé> <min> <max>",
+            "Membres par plage de score",
+        )],
+        description: "Bornes incluses, membres triés par score.",
+        examples: &["ZRANGEBYSCORE scores 10 20"],
+    },
+    CommandSpec {
+        name: "SETBIT",
+        aliases: &[],
+        forms: &[form("SETBIT <clé> <offset> <0|1>", "Positionne un bit")],
+        description: "La valeur est agrandie si besoin ; affiche l'ancien bit.",
+        examples: &["SETBIT vus 42 1"],
+    },
+    CommandSpec {
+        name: "GETBIT",
+        aliases: &[],
+        forms: &[form("GETBIT <clé> <offset>", "Lit un bit")],
+        description: "",
+        examples: &["GETBIT vus 42"],
+    },
+    CommandSpec {
+        name: "BITCOUNT",
+        aliases: &[],
+        forms: &[form("BITCOUNT <clé>", "Compte les bits à 1")],
+        description: "",
+        examples: &["BITCOUNT vus"],
+    },
+    CommandSpec {
+        name: "WARMUP",
+        aliases: &[],
+        forms: &[
+            form(
+                "WARMUP [préfixe]",
+                "Précharge les clés d'un préfixe (toutes par défaut)",
+            ),
+            form("WARMUP --keys <clé>...", "Précharge des clés données"),
+        ],
+        description: "Lit les valeurs pour amener le journal dans le cache du système.",
+        examples: &["WARMUP user:", "WARMUP --keys user:1 user:2"],
+    },
+    CommandSpec {
+        name: "EXPORT",
+        aliases: &[],
+        forms: &[form(
+            "EXPORT <préfixe> <chemin>",
+            "Exporte les clés d'un préfixe dans une base autonome",
+        )],
+        description: "Le fichier cible ne doit pas exister.",
+        examples: &["EXPORT user: users.db"],
+    },
+    CommandSpec {
+        name: "COMPACT",
+        aliases: &[],
+        forms: &[
+            form("COMPACT", "Compacte le fichier de log"),
+            form("COMPACT --dry-run", "Estime le gain d'une compaction"),
+            form("COMPACT --progress", "Compacte en affichant l'avancement"),
+        ],
+        description: "Réécrit le journal avec les seules entrées vivantes.",
+        examples: &["COMPACT --dry-run"],
+    },
+    CommandSpec {
+        name: "SCAN",
+        aliases: &[],
+        forms: &[form(
+            "SCAN [préfixe] [--format table|json|csv]",
+            "Liste les clés vivantes d'un préfixe (toutes par défaut)",
+        )],
+        description: "Une ligne par clé : type (bytes ou collection), taille de la valeur \
+                      et taille dans le journal (valeurs simples), âge en octets écrits \
+                      au journal depuis sa dernière écriture.",
+        examples: &["SCAN user:", "SCAN --format json"],
+    },
+    CommandSpec {
+        name: "LOG",
+        aliases: &[],
+        forms: &[form(
+            "LOG [--limit N] [--format table|json|csv]",
+            "Affiche les entrées du journal",
+        )],
+        description: "Une ligne par enregistrement : position, taille, type, clé, \
+                      taille de la valeur, checksum.",
+        examples: &["LOG --limit 20", "LOG --format csv"],
+    },
+    CommandSpec {
+        name: "REINDEX",
+        aliases: &[],
+        forms: &[form("REINDEX", "Reconstruit l'index à partir du journal")],
+        description: "",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "STATS",
+        aliases: &[],
+        forms: &[form(
+            "STATS [--format table|json|csv]",
+            "Affiche les statistiques (dont la compression)",
+        )],
+        description: "Avec --format, une ligne par mesure (nom, valeur).",
+        examples: &["STATS --format json"],
+    },
+    CommandSpec {
+        name: "HEALTH",
+        aliases: &[],
+        forms: &[form("HEALTH", "Affiche l'état de santé de la base")],
+        description: "",
+        examples: &[],
+    },
+    CommandSpec {
+        name: "HELP",
+        aliases: &[],
+        forms: &[form(
+            "HELP [commande]",
+            "Liste les commandes, ou détaille l'une d'elles",
+        )],
+        description: "",
+        examples: &["HELP SET"],
+    },
+    CommandSpec {
+        name: "EXIT",
+        aliases: &["QUIT"],
+        forms: &[form("EXIT", "Quitte le programme")],
+        description: "",
+        examples: &[],
+    },
+];
+
+/// Définition de la commande `name` (en majuscules) ou d'un de ses alias.
+pub fn find(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS
+        .iter()
+        .find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+impl CommandSpec {
+    fn write_usage(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, form) in self.forms.iter().enumerate() {
+            let prefix = if idx == 0 { "Usage:" } else { "\n   ou:" };
+            write!(f, "{} {}", prefix, form.usage)?;
+        }
+        Ok(())
+    }
+}
+
+/// Liste des commandes : une ligne par forme, usage aligné et résumé.
+pub struct Overview;
+
+impl fmt::Display for Overview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let forms = COMMANDS.iter().flat_map(|spec| spec.forms);
+        let width = forms.clone().map(|form| form.usage.chars().count()).max();
+        for form in forms {
+            let pad = width.unwrap_or(0) - form.usage.chars().count();
+            writeln!(f, "  {}{}  - {}", form.usage, " ".repeat(pad), form.summary)?;
+        }
+        Ok(())
+    }
+}
+
+/// Aide détaillée d'une commande : formes, description et exemples.
+impl fmt::Display for CommandSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for form in self.forms {
+            writeln!(f, "  {}  - {}", form.usage, form.summary)?;
+        }
+        if !self.aliases.is_empty() {
+            writeln!(f, "Alias : {}", self.aliases.join(", "))?;
+        }
+        if !self.description.is_empty() {
+            writeln!(f, "{}", self.description)?;
+        }
+        if !self.examples.is_empty() {
+            writeln!(f, "Exemples :")?;
+            for example in self.examples {
+                writeln!(f, "  {}", example)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn tokenize_splits_on_whitespace() {
+        assert_eq!(words("  SET  a\tb \n"), ["SET", "a", "b"]);
+        assert!(words("   ").is_empty());
+    }
+
+    #[test]
+    fn tokenize_keeps_quoted_whitespace() {
+        assert_eq!(
+            words(r#"SET k "deux  espaces""#),
+            ["SET", "k", "deux  espaces"]
+        );
+        assert_eq!(words(r#"SET k """#), ["SET", "k", ""]);
+        assert_eq!(words(r#"a"b c"d"#), ["ab cd"]);
+    }
+
+    #[test]
+    fn tokenize_unescapes_quote_and_backslash() {
+        assert_eq!(words(r#""a\"b""#), [r#"a"b"#]);
+        assert_eq!(words(r#""a\\b""#), [r"a\b"]);
+        // Les autres séquences sont gardées telles quelles.
+        assert_eq!(words(r#""a\nb""#), [r"a\nb"]);
+    }
+
+    #[test]
+    fn tokenize_rejects_unterminated_quote() {
+        assert_eq!(
+            tokenize(r#"SET k "abc"#),
+            Err(ParseError::UnterminatedQuote)
+        );
+        assert_eq!(
+            tokenize(r#"SET k "abc\"#),
+            Err(ParseError::UnterminatedQuote)
+        );
+    }
+
+    #[test]
+    fn parse_blank_line() {
+        assert_eq!(parse("  \n"), Ok(None));
+    }
+
+    #[test]
+    fn parse_is_case_insensitive() {
+        let expected = Some(Command::Get {
+            key: b"Key".to_vec(),
+            file: None,
+        });
+        assert_eq!(parse("get Key"), Ok(expected.clone()));
+        assert_eq!(parse("GeT Key"), Ok(expected));
+        assert_eq!(parse("quit"), Ok(Some(Command::Exit)));
+        assert_eq!(
+            parse("help set"),
+            Ok(Some(Command::Help {
+                topic: Some("SET".to_string())
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_set_values() {
+        assert_eq!(
+            parse("SET k un  deux"),
+            Ok(Some(Command::Set {
+                key: b"k".to_vec(),
+                value: SetValue::Inline(b"un deux".to_vec()),
+            }))
+        );
+        assert_eq!(
+            parse(r#"SET k "un  deux""#),
+            Ok(Some(Command::Set {
+                key: b"k".to_vec(),
+                value: SetValue::Inline(b"un  deux".to_vec()),
+            }))
+        );
+        assert_eq!(
+            parse("SET k --file a.bin"),
+            Ok(Some(Command::Set {
+                key: b"k".to_vec(),
+                value: SetValue::File(PathBuf::from("a.bin")),
+            }))
+        );
+        assert_eq!(
+            parse("SET k <<FIN"),
+            Ok(Some(Command::Set {
+                key: b"k".to_vec(),
+                value: SetValue::Lines {
+                    delimiter: Some("FIN".to_string())
+                },
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_missing_arguments() {
+        assert_eq!(parse("SET"), Err(ParseError::Usage("SET")));
+        assert_eq!(parse("SET k"), Err(ParseError::Usage("SET")));
+        assert_eq!(parse("SET k --file"), Err(ParseError::Usage("SET")));
+        assert_eq!(parse("LPUSH l"), Err(ParseError::Usage("LPUSH")));
+        assert_eq!(parse("LRANGE l 0"), Err(ParseError::Usage("LRANGE")));
+        assert_eq!(parse("SETBIT b 3 2"), Err(ParseError::Usage("SETBIT")));
+        assert_eq!(parse("EXPORT p"), Err(ParseError::Usage("EXPORT")));
+    }
+
+    #[test]
+    fn parse_rejects_extra_arguments() {
+        assert_eq!(parse("SIZE a b"), Err(ParseError::Usage("SIZE")));
+        assert_eq!(parse("HGET h f g"), Err(ParseError::Usage("HGET")));
+        assert_eq!(parse("EXIT now"), Err(ParseError::Usage("EXIT")));
+        assert_eq!(
+            parse("EXPORT p a.db b.db"),
+            Err(ParseError::Usage("EXPORT"))
+        );
+        assert_eq!(
+            parse("COMPACT --dry-run now"),
+            Err(ParseError::Usage("COMPACT"))
+        );
+        assert_eq!(parse("STATS --format"), Err(ParseError::Usage("STATS")));
+        assert_eq!(parse("WARMUP a b"), Err(ParseError::Usage("WARMUP")));
+        assert_eq!(
+            parse("LOG --limit 1 --limit 2"),
+            Err(ParseError::Usage("LOG"))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_unknown_flags() {
+        assert_eq!(parse("COMPACT --dryrun"), Err(ParseError::Usage("COMPACT")));
+        assert_eq!(parse("LOG --max 3"), Err(ParseError::Usage("LOG")));
+        assert_eq!(parse("STATS json"), Err(ParseError::Usage("STATS")));
+        assert_eq!(
+            parse("COMPACT"),
+            Ok(Some(Command::Compact(CompactMode::Run)))
+        );
+        assert_eq!(
+            parse("WARMUP --keys a b"),
+            Ok(Some(Command::Warmup(Warmup::Keys(vec![
+                b"a".to_vec(),
+                b"b".to_vec()
+            ]))))
+        );
+    }
+
+    #[test]
+    fn parse_rejects_invalid_numbers_and_formats() {
+        assert_eq!(parse("LRANGE l a 1"), Err(ParseError::Usage("LRANGE")));
+        assert_eq!(parse("LOG --format xml"), Err(ParseError::Usage("LOG")));
+        assert_eq!(parse("LOG --limit abc"), Err(ParseError::Usage("LOG")));
+        assert_eq!(parse("LOG --limit -1"), Err(ParseError::Usage("LOG")));
+        assert_eq!(
+            parse("LOG --limit 2 --format CSV"),
+            Ok(Some(Command::Log {
+                limit: Some(2),
+                format: Some(Format::Csv),
+            }))
+        );
+    }
+
+    #[test]
+    fn parse_scan() {
+        assert_eq!(
+            parse("SCAN user: --format csv"),
+            Ok(Some(Command::Scan {
+                prefix: b"user:".to_vec(),
+                format: Some(Format::Csv),
+            }))
+        );
+        assert_eq!(
+            parse("SCAN"),
+            Ok(Some(Command::Scan {
+                prefix: Vec::new(),
+                format: None,
+            }))
+        );
+        assert_eq!(parse("SCAN a b"), Err(ParseError::Usage("SCAN")));
+        assert_eq!(parse("SCAN --limit 2"), Err(ParseError::Usage("SCAN")));
+    }
+
+    #[test]
+    fn parse_unknown_command() {
+        assert_eq!(
+            parse("frobnicate x"),
+            Err(ParseError::Unknown("FROBNICATE".to_string()))
+        );
+    }
+}
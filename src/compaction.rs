@@ -0,0 +1,98 @@
+use crate::db::MyDatabase;
+use crate::error::DatabaseError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::thread::{self, JoinHandle};
+
+/// Avancement d'une compaction en cours.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompactionProgress {
+    /// Entrées de l'index parcourues.
+    pub records_processed: usize,
+    /// Entrées de l'index au début de la compaction.
+    pub records_total: usize,
+    /// Octets écrits dans le nouveau journal.
+    pub bytes_written: u64,
+}
+
+impl CompactionProgress {
+    /// Pourcentage d'entrées parcourues (100 pour un index vide).
+    pub fn percent(&self) -> f64 {
+        if self.records_total == 0 {
+            return 100.0;
+        }
+        self.records_processed as f64 * 100.0 / self.records_total as f64
+    }
+}
+
+/// Compaction lancée par `compact_in_background`. Abandonner le handle laisse
+/// la compaction se terminer seule.
+pub struct CompactionHandle {
+    shared: Arc<HandleState>,
+    thread: JoinHandle<Result<(), DatabaseError>>,
+}
+
+#[derive(Default)]
+struct HandleState {
+    progress: Mutex<CompactionProgress>,
+    cancel: AtomicBool,
+}
+
+impl CompactionHandle {
+    /// Dernier avancement rapporté.
+    pub fn progress(&self) -> CompactionProgress {
+        *self
+            .shared
+            .progress
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Demande l'arrêt : la copie s'interrompt à l'entrée suivante, le
+    /// fichier temporaire est supprimé et le journal reste inchangé. Sans
+    /// effet une fois le remplacement du journal commencé.
+    pub fn cancel(&self) {
+        self.shared.cancel.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Attend la fin de la compaction ; `DatabaseError::CompactionCancelled`
+    /// si elle a été annulée.
+    pub fn wait(self) -> Result<(), DatabaseError> {
+        self.thread
+            .join()
+            .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+    }
+}
+
+impl MyDatabase {
+    /// Comme `compact`, en appelant `on_progress` au fil de la copie.
+    pub fn compact_with_progress(
+        &self,
+        mut on_progress: impl FnMut(&CompactionProgress),
+    ) -> Result<(), DatabaseError> {
+        self.compact_observed(&mut on_progress, &AtomicBool::new(false))
+    }
+
+    /// Lance une compaction sur un thread dédié.
+    pub fn compact_in_background(&self) -> CompactionHandle {
+        let shared = Arc::new(HandleState::default());
+        let db = self.clone();
+        let state = Arc::clone(&shared);
+        let thread = thread::spawn(move || {
+            db.compact_observed(
+                &mut |progress| {
+                    *state
+                        .progress
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner) = *progress;
+                },
+                &state.cancel,
+            )
+        });
+        CompactionHandle { shared, thread }
+    }
+}
@@ -1,16 +1,143 @@
-use crate::codec::{Compressor, DataEntry, EntryType, Lz77};
-use crate::error::DatabaseError;
-use std::collections::HashMap;
+use crate::clock::{Clock, SystemClock};
+use crate::codec::{self, DataEntry, Dictionary, EntryType, RawRecord};
+use crate::compaction::CompactionProgress;
+use crate::error::{DatabaseError, IoContext, IoOp, LockResource};
+use crate::eviction::{Eviction, LruTracker};
+use crate::failpoints;
+use crate::health::CompactionOutcome;
+use crate::merge::{self, MAX_MERGE_DEPTH, MergeOp, MergeRecord, Value};
+use crate::quarantine::Quarantine;
+use crate::schedule::{BackpressurePolicy, CompactionSchedule};
+use crate::stats::{
+    CompactionEstimate, CompressionCounters, DatabaseStats, LockWaits, ThrottleStats,
+};
+use crate::text::TextIndex;
+use crate::throttle::{RateLimiter, ThrottleState};
+use crate::token::SyncToken;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::fs::{File, OpenOptions};
 use std::io::{self, ErrorKind, Read, Seek, SeekFrom, Write};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex, RwLock};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Taille des écritures groupées de `bulk_load`.
+const BULK_CHUNK_SIZE: usize = 4 * 1024 * 1024;
+
+/// Âge au-delà duquel un fichier temporaire de compaction est abandonné.
+const STALE_COMPACTION_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// Intervalle de vérification d'une écriture en attente de compaction.
+const STALL_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Entrées traitées par fil de compaction entre deux écritures dans le
+/// nouveau journal.
+const COMPACTION_BATCH_PER_THREAD: usize = 64;
+
+/// Tentatives d'une lecture dont l'entrée et le journal ouvert ne sont pas de
+/// la même génération avant d'abandonner.
+const MAX_GENERATION_RETRIES: usize = 64;
+
+/// Lecture anticipée par défaut des parcours séquentiels.
+const DEFAULT_READ_AHEAD: usize = 256 * 1024;
+
+/// Valeurs lues par compaction pour entraîner le dictionnaire de compression.
+const DICTIONARY_SAMPLES: usize = 64;
+
+thread_local! {
+    /// Tampon de lecture des enregistrements bruts de `get_into`, réutilisé
+    /// d'un appel à l'autre sur un même fil.
+    static RAW_RECORD: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+}
 
 /// Configuration de la base de données
 #[derive(Debug, Clone)]
 pub struct DatabaseConfig {
     pub file_path: PathBuf,
     pub max_size: u64,
+    /// Débit maximal (octets/s) de l'écriture pendant la compaction. `None` = illimité.
+    pub compaction_rate_limit: Option<u64>,
+    /// Créneaux pendant lesquels la compaction automatique est permise.
+    pub compaction_schedule: CompactionSchedule,
+    /// Réaction des écritures lorsque le fichier dépasse sa taille cible.
+    /// Hors `Inline`, la compaction a lieu dans un fil d'arrière-plan lancé
+    /// par les écritures, dans les créneaux de `compaction_schedule`.
+    pub backpressure: BackpressurePolicy,
+    /// Si vrai, `max_size` devient aussi un plafond strict sur les données
+    /// vivantes : `set` échoue avec `DatabaseError::DatabaseFull` au-delà.
+    pub enforce_max_size: bool,
+    /// Éviction automatique des clés peu utilisées (mode cache).
+    pub eviction: Eviction,
+    /// Source de temps ; `SystemClock` par défaut.
+    pub clock: Arc<dyn Clock>,
+    /// Si vrai, la compaction recopie tels quels les enregistrements `Data`
+    /// dont le checksum est valide et le dictionnaire celui du nouveau
+    /// journal, sans décompresser ni recompresser.
+    pub compaction_raw_copy: bool,
+    /// Si vrai, un enregistrement invalide n'interrompt pas la compaction :
+    /// il est mis en quarantaine dans `<journal>.quarantine` (rapport dans
+    /// `<journal>.quarantine.txt`) et sa clé disparaît de la base compactée.
+    pub compaction_quarantine: bool,
+    /// Attente maximale des verrous lecteur/rédacteur et de compaction, par
+    /// exemple pendant une longue compaction. `None` = attente illimitée ;
+    /// sinon `DatabaseError::Timeout` au-delà.
+    pub lock_timeout: Option<Duration>,
+    /// Durée maximale de `compact`, `scan` et `warmup` : au-delà, l'opération
+    /// s'interrompt avec `DatabaseError::Timeout` (une compaction laisse le
+    /// journal inchangé). `None` = illimitée.
+    pub operation_timeout: Option<Duration>,
+    /// Fils décodant et réencodant les entrées pendant la compaction ; le
+    /// nouveau journal reste écrit par un seul fil, dans l'ordre. 1 = copie
+    /// séquentielle.
+    pub compaction_threads: usize,
+    /// Taille des lectures groupées des parcours dans l'ordre du journal
+    /// (`scan_iter`, `warmup`, copie, compaction) : les enregistrements
+    /// consécutifs sont servis depuis ce tampon. 0 = une lecture par
+    /// enregistrement.
+    pub read_ahead: usize,
+    /// Si vrai, l'ouverture compacte le journal dès qu'il contient des
+    /// octets morts (voir `recovery_summary`).
+    pub compact_on_open: bool,
+    /// Gain minimal de la compression, en pourcentage de la valeur : en
+    /// deçà, la valeur est stockée sans compression (données déjà
+    /// compressées, images...). `None` = toujours compresser. Les
+    /// enregistrements ainsi écrits ne sont pas lisibles par une version
+    /// antérieure de la base.
+    pub min_compression_savings: Option<u8>,
+    /// Si vrai, chaque compaction entraîne un dictionnaire sur un échantillon
+    /// des valeurs vivantes et l'écrit en tête du nouveau journal : les
+    /// valeurs recopiées puis écrites ensuite sont compressées avec lui, ce
+    /// qui réduit nettement de petites valeurs semblables (JSON…) que la
+    /// compression valeur par valeur ne réduit guère. Sans effet avant la
+    /// première compaction ; désactivé, la compaction suivante retire le
+    /// dictionnaire. Les journaux ainsi écrits ne sont pas lisibles par une
+    /// version antérieure de la base.
+    pub compression_dictionary: bool,
+    /// Si défini, une valeur d'au moins cette taille est stockée une seule
+    /// fois : dans un enregistrement `EntryType::Blob` repéré par son
+    /// empreinte, que référencent toutes les entrées de même valeur
+    /// (`BLOB_FLAG`). La lecture reconstitue la valeur ; la compaction ne
+    /// recopie que les valeurs encore référencées, jusque-là comptées comme
+    /// vivantes. Les journaux ainsi écrits ne sont pas lisibles par une
+    /// version antérieure de la base.
+    pub dedup_min_size: Option<usize>,
+    /// Facteur maximal entre la taille décodée d'une valeur et sa taille
+    /// stockée. Au-delà, la lecture échoue avec `DatabaseError::CorruptedData`
+    /// sans allouer la valeur : un enregistrement corrompu ou malveillant ne
+    /// peut pas épuiser la mémoire. Le défaut, `codec::MAX_EXPANSION`,
+    /// n'écarte aucune valeur valide ; un facteur plus bas borne davantage la
+    /// mémoire si les valeurs se compressent peu.
+    pub max_expansion: usize,
+    /// Taille maximale d'un enregistrement relu. Un en-tête annonçant plus
+    /// est traité comme corrompu avant toute allocation ; à régler au-dessus
+    /// du plus gros enregistrement écrit. Le défaut,
+    /// `codec::MAX_RECORD_SIZE`, n'écarte aucun enregistrement valide : la
+    /// mémoire allouée reste alors bornée par les octets réellement présents
+    /// dans le journal.
+    pub max_record_size: u32,
 }
 
 impl Default for DatabaseConfig {
@@ -18,6 +145,24 @@ impl Default for DatabaseConfig {
         Self {
             file_path: PathBuf::from("database.db"),
             max_size: 1024 * 1024,
+            compaction_rate_limit: None,
+            compaction_schedule: CompactionSchedule::Always,
+            backpressure: BackpressurePolicy::Inline,
+            enforce_max_size: false,
+            eviction: Eviction::None,
+            clock: Arc::new(SystemClock),
+            compaction_raw_copy: false,
+            compaction_quarantine: false,
+            lock_timeout: None,
+            operation_timeout: None,
+            compaction_threads: 1,
+            read_ahead: DEFAULT_READ_AHEAD,
+            compact_on_open: false,
+            min_compression_savings: None,
+            compression_dictionary: false,
+            dedup_min_size: None,
+            max_expansion: codec::MAX_EXPANSION,
+            max_record_size: codec::MAX_RECORD_SIZE,
         }
     }
 }
@@ -28,16 +173,193 @@ impl DatabaseConfig {
     }
 }
 
+/// Options d'une écriture (`set_with_options`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Si vrai, l'écriture est rendue durable (`fsync`) avant le retour ;
+    /// `set` se contente de vider le tampon vers le système.
+    pub sync: bool,
+}
+
+/// Conduite de la relecture du journal à l'ouverture (`open_with`). L'index
+/// des clés n'est jamais persisté : il est toujours reconstruit en relisant
+/// le journal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecoveryOptions {
+    pub mode: RecoveryMode,
+    /// Si faux, un enregistrement dont le checksum ne correspond pas est
+    /// indexé comme les autres.
+    pub verify_checksums: bool,
+    /// Si vrai, l'instantané de l'index plein texte (`.db.text`) est
+    /// supprimé : il sera reconstruit depuis le journal au prochain
+    /// `enable_text_index`.
+    pub rebuild_text_index: bool,
+}
+
+impl Default for RecoveryOptions {
+    /// Comportement de `new` : échec sur le premier enregistrement invalide.
+    fn default() -> Self {
+        Self {
+            mode: RecoveryMode::Strict,
+            verify_checksums: true,
+            rebuild_text_index: false,
+        }
+    }
+}
+
+/// État du journal constaté à l'ouverture (`recovery_summary`).
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RecoverySummary {
+    /// Enregistrements relus, ignorés compris.
+    pub records_scanned: u64,
+    /// Enregistrements invalides ignorés (`RecoveryMode::SkipCorrupt`).
+    pub records_skipped: u64,
+    /// Octets retirés en fin de journal (hors `RecoveryMode::Strict`).
+    pub truncated_bytes: u64,
+    /// Taille du journal après relecture, avant une éventuelle compaction.
+    pub file_size: u64,
+    /// Taille des entrées vivantes et du dictionnaire de compression.
+    pub live_bytes: u64,
+    /// Le journal a été compacté à l'ouverture (`compact_on_open`).
+    pub compacted: bool,
+}
+
+impl RecoverySummary {
+    /// Part du journal relu occupée par des données mortes (0.0 pour un
+    /// journal vide).
+    pub fn dead_ratio(&self) -> f64 {
+        if self.file_size == 0 {
+            return 0.0;
+        }
+        self.file_size.saturating_sub(self.live_bytes) as f64 / self.file_size as f64
+    }
+}
+
+/// Traitement d'un enregistrement invalide rencontré à l'ouverture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecoveryMode {
+    /// L'ouverture échoue avec `DatabaseError::CorruptedRecord`.
+    Strict,
+    /// Le journal est tronqué juste avant l'enregistrement invalide ; les
+    /// écritures suivantes sont perdues.
+    TruncateTail,
+    /// L'enregistrement est ignoré et la relecture continue. Un type
+    /// d'enregistrement inconnu, dont la taille n'est pas fiable, tronque le
+    /// journal comme `TruncateTail`.
+    SkipCorrupt,
+}
+
+/// Traitement d'une clé présente dans les deux bases lors de `merge_from`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    /// La valeur importée remplace la valeur existante (écriture la plus récente).
+    LastWriteWins,
+    /// La valeur existante est conservée.
+    KeepExisting,
+    /// La fusion échoue avant toute écriture avec `DatabaseError::KeyConflict`.
+    Error,
+}
+
+/// Clés à précharger avec `warmup`.
+#[derive(Debug, Clone, Copy)]
+pub enum WarmupTarget<'a> {
+    Keys(&'a [Vec<u8>]),
+    /// Toutes les clés commençant par ce préfixe (toutes pour un préfixe vide).
+    Prefix(&'a [u8]),
+}
+
+/// Tailles de la valeur d'une clé, retournées par `value_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueSize {
+    /// Taille de la valeur lue par `get`.
+    pub uncompressed: u64,
+    /// Taille de la valeur compressée dans le journal.
+    pub compressed: u64,
+}
+
+/// Description d'une clé vivante tirée de l'index, retournée par `scan_meta`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueMeta {
+    /// Taille de l'enregistrement courant dans le journal (valeur compressée ;
+    /// dernière opérande seulement pour une collection).
+    pub size: u32,
+    /// Position de l'enregistrement courant : plus elle est basse, plus la
+    /// dernière écriture de la clé est ancienne.
+    pub offset: u64,
+    /// Génération du journal contenant l'enregistrement.
+    pub generation: u64,
+}
+
+/// Valeur et métadonnées retournées par `get_with_meta`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueWithMeta {
+    pub value: Vec<u8>,
+    /// Métadonnées passées à `set_with_meta`, vides pour une valeur écrite
+    /// par `set`.
+    pub meta: Vec<u8>,
+}
+
+/// Couple clé/valeur décodé.
+pub(crate) type KeyValue = (Vec<u8>, Vec<u8>);
+
 #[derive(Clone, Copy)]
 pub struct IndexEntry {
     pub offset: u64,
     pub size: u32,
+    pub tombstone: bool,
+    /// Génération du journal contenant l'enregistrement ; change à chaque
+    /// remplacement par une compaction.
+    pub generation: u64,
 }
 
 pub struct SharedState {
     pub file: Mutex<File>,
     pub access: RwLock<()>,
     pub index: RwLock<HashMap<Vec<u8>, IndexEntry>>,
+    pub compaction: Mutex<()>,
+    pub throttle: ThrottleState,
+    /// Horodatage (ms UNIX) de la dernière écriture, 0 si aucune.
+    pub last_write: AtomicU64,
+    /// Taille cumulée des entrées vivantes (hors tombstones) référencées par l'index.
+    pub live_bytes: AtomicU64,
+    /// Taille cumulée des enregistrements `Blob` du journal
+    /// (`dedup_min_size`), comptés comme vivants jusqu'à la compaction.
+    pub(crate) blob_bytes: AtomicU64,
+    pub(crate) recency: Mutex<LruTracker>,
+    /// Index plein texte, `None` tant qu'il n'est pas activé.
+    pub(crate) text: Mutex<Option<TextIndex>>,
+    /// Fin du dernier enregistrement du journal reflété par l'index.
+    pub(crate) indexed_end: AtomicU64,
+    /// Nombre de reprises après un verrou empoisonné par un panic.
+    pub(crate) poison_recoveries: AtomicU64,
+    /// Horodatage (ms UNIX) du dernier `fsync` du journal, 0 si aucun.
+    pub(crate) last_sync: AtomicU64,
+    pub(crate) last_compaction: Mutex<Option<CompactionOutcome>>,
+    /// Une corruption du journal a été rencontrée depuis l'ouverture.
+    pub(crate) corruption_detected: AtomicBool,
+    /// Une écriture a échoué faute d'espace (voir `write_log`).
+    pub(crate) disk_full: AtomicBool,
+    /// Fixé une fois l'ouverture terminée.
+    pub(crate) recovery: OnceLock<RecoverySummary>,
+    /// Base ouverte par `open_follower` : toute écriture est refusée.
+    pub(crate) read_only: bool,
+    /// Nombre d'enregistrements intégrés à l'index depuis l'ouverture ; base
+    /// des `SyncToken`.
+    pub(crate) applied_writes: AtomicU64,
+    pub(crate) lock_waits: LockWaits,
+    /// Itérateurs `scan_iter` en cours.
+    pub(crate) live_snapshots: AtomicUsize,
+    /// Une compaction lancée par `spawn_compaction` est en cours.
+    pub(crate) background_compaction: AtomicBool,
+    /// Compteur des remplacements du journal : impair pendant un remplacement,
+    /// sa moitié est la génération courante.
+    pub(crate) file_epoch: AtomicU64,
+    pub(crate) compression: CompressionCounters,
+    /// Dictionnaire en tête du journal courant (`compression_dictionary`).
+    pub(crate) dictionary: Mutex<Option<Arc<Dictionary>>>,
+    /// Enregistrements `Blob` du journal courant, par empreinte de leur
+    /// valeur (`dedup_min_size`).
+    pub(crate) blobs: Mutex<HashMap<u64, IndexEntry>>,
 }
 
 /// Moteur principal de la base clé/valeur.
@@ -55,17 +377,44 @@ pub struct LogRecord {
     pub checksum_ok: bool,
 }
 
-/// Itérateur public sur le journal.
+/// Itérateur public sur les entrées du journal.
 pub struct LogIter {
     reader: LogReader,
+    /// Taille au-delà de laquelle un enregistrement est rejeté
+    /// (`DatabaseConfig::max_record_size`).
+    max_record_size: u32,
 }
 
-struct LogReader {
+/// Lecteur du journal ; conserve le chemin pour situer les erreurs.
+pub(crate) struct LogReader {
     file: File,
+    path: PathBuf,
     offset: u64,
+    /// Génération du fichier ouvert, relevée par `MyDatabase::open_reader`.
+    generation: u64,
+    read_ahead: Option<ReadAhead>,
+    /// Expansion maximale admise au décodage des valeurs
+    /// (`DatabaseConfig::max_expansion`).
+    max_expansion: usize,
+    /// Dictionnaire en tête du fichier ouvert, lu à la première valeur qui
+    /// en a besoin (`None` tant qu'il n'est pas lu).
+    dictionary: Option<Option<Arc<Dictionary>>>,
+}
+
+/// Tampon de lecture anticipée d'un `LogReader` : une fenêtre du journal
+/// commençant à `start`. Le journal n'étant jamais réécrit sur place, une
+/// fenêtre reste valide tant que le descripteur est ouvert.
+struct ReadAhead {
+    buffer: Vec<u8>,
+    start: u64,
+    capacity: usize,
 }
 
 /// Ajoute une entrée à la fin du fichier (Append-only)
+///
+/// Contourne les verrous et l'index de toute `MyDatabase` ouverte sur le même
+/// fichier : celle-ci ne voit l'entrée qu'après `refresh`.
+#[deprecated(note = "utiliser `MyDatabase::ingest`, qui maintient l'index et les verrous")]
 pub fn append_entry(config: &DatabaseConfig, entry: &DataEntry) -> io::Result<()> {
     let mut file = OpenOptions::new()
         .append(true)
@@ -81,345 +430,2844 @@ pub fn append_entry(config: &DatabaseConfig, entry: &DataEntry) -> io::Result<()
 impl MyDatabase {
     /// Ouvre la base et reconstruit l'index au démarrage.
     pub fn new(config: DatabaseConfig) -> Result<Self, DatabaseError> {
-        let file = OpenOptions::new()
-            .read(true)
-            .append(true)
-            .create(true)
-            .open(&config.file_path)?;
+        Self::open_with(config, RecoveryOptions::default())
+    }
+
+    /// Comme `new`, en choisissant le traitement des enregistrements invalides.
+    /// Hors `Strict`, un enregistrement incomplet en fin de journal est retiré.
+    pub fn open_with(
+        config: DatabaseConfig,
+        options: RecoveryOptions,
+    ) -> Result<Self, DatabaseError> {
+        Self::open_mode(config, options, false)
+    }
 
-        let index = Self::recover_index(&config.file_path)?;
+    /// Ouverture commune à `open_with` et `open_follower`. En lecture seule,
+    /// le journal doit exister et n'est jamais modifié : ni troncature, ni
+    /// nettoyage des fichiers de compaction de l'écrivain, ni compaction.
+    pub(crate) fn open_mode(
+        config: DatabaseConfig,
+        options: RecoveryOptions,
+        read_only: bool,
+    ) -> Result<Self, DatabaseError> {
+        let file = match read_only {
+            true => OpenOptions::new().read(true).open(&config.file_path),
+            false => OpenOptions::new()
+                .read(true)
+                .append(true)
+                .create(true)
+                .open(&config.file_path),
+        }
+        .context(IoOp::LogOpen, &config.file_path)?;
+        if !read_only {
+            remove_stale_compaction_files(&config.file_path)?;
+        }
+
+        let replay = Self::replay_log(&config.file_path, &options, config.max_record_size)?;
+        let (index, indexed_end) = (replay.index, replay.end);
+        let dictionary = LogReader::new(&config.file_path)?.dictionary()?;
+        let len = file
+            .metadata()
+            .context(IoOp::LogOpen, &config.file_path)?
+            .len();
+        let mut truncated_bytes = 0;
+        if !read_only && options.mode != RecoveryMode::Strict && len > indexed_end {
+            file.set_len(indexed_end)
+                .context(IoOp::LogTruncate, &config.file_path)?;
+            truncated_bytes = len - indexed_end;
+        }
+        let live_bytes = live_size(&index);
+        let blob_bytes = live_size(&replay.blobs);
+        let dictionary_bytes = dictionary.as_ref().map_or(0, |d| d.record_len() as u64);
+        let mut summary = RecoverySummary {
+            records_scanned: replay.records,
+            records_skipped: replay.skipped,
+            truncated_bytes,
+            file_size: len - truncated_bytes,
+            live_bytes: live_bytes + blob_bytes + dictionary_bytes,
+            compacted: false,
+        };
+        let recency = Self::initial_recency(&config, &index);
         let shared = Arc::new(SharedState {
             file: Mutex::new(file),
             access: RwLock::new(()),
             index: RwLock::new(index),
+            compaction: Mutex::new(()),
+            throttle: ThrottleState::default(),
+            last_write: AtomicU64::new(0),
+            live_bytes: AtomicU64::new(live_bytes),
+            blob_bytes: AtomicU64::new(blob_bytes),
+            recency: Mutex::new(recency),
+            text: Mutex::new(None),
+            indexed_end: AtomicU64::new(indexed_end),
+            poison_recoveries: AtomicU64::new(0),
+            last_sync: AtomicU64::new(0),
+            last_compaction: Mutex::new(None),
+            corruption_detected: AtomicBool::new(replay.damaged),
+            disk_full: AtomicBool::new(false),
+            read_only,
+            applied_writes: AtomicU64::new(0),
+            lock_waits: LockWaits::default(),
+            live_snapshots: AtomicUsize::new(0),
+            background_compaction: AtomicBool::new(false),
+            file_epoch: AtomicU64::new(0),
+            compression: CompressionCounters::default(),
+            dictionary: Mutex::new(dictionary),
+            blobs: Mutex::new(replay.blobs),
+            recovery: OnceLock::new(),
         });
 
-        Ok(Self { config, shared })
+        let db = Self { config, shared };
+        if options.rebuild_text_index {
+            match std::fs::remove_file(db.text_snapshot_path()) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(DatabaseError::io(
+                        IoOp::TextIndexRemove,
+                        db.text_snapshot_path(),
+                        err,
+                    ));
+                }
+            }
+        }
+        if !read_only && db.config.compact_on_open && summary.file_size > summary.live_bytes {
+            db.compact()?;
+            summary.compacted = true;
+        }
+        let _ = db.shared.recovery.set(summary);
+        Ok(db)
+    }
+
+    /// Ce que la relecture du journal a constaté à l'ouverture : de quoi
+    /// journaliser l'état dans lequel la base a démarré.
+    pub fn recovery_summary(&self) -> RecoverySummary {
+        self.shared.recovery.get().copied().unwrap_or_default()
     }
 
-    /// Ajoute ou met à jour une valeur.
-    pub fn set(&self, key: Vec<u8>, value: Vec<u8>) -> Result<(), DatabaseError> {
+    /// Ajoute ou met à jour une valeur. Le jeton retourné permet d'exiger
+    /// plus tard une lecture qui voit cette écriture (`wait_for`).
+    pub fn set(&self, key: Vec<u8>, value: Vec<u8>) -> Result<SyncToken, DatabaseError> {
+        self.admit_write()?;
+        self.store(key, value)
+    }
+
+    /// `set` une fois l'écriture admise par `admit_write`.
+    fn store(&self, key: Vec<u8>, value: Vec<u8>) -> Result<SyncToken, DatabaseError> {
         let entry = DataEntry {
             entry_type: EntryType::Data,
-            key: key.clone(),
+            key,
             value,
         };
-        let bytes = entry.to_bytes();
-        let size = bytes.len() as u32;
-
-        {
-            let _access_guard = self
-                .shared
-                .access
-                .write()
-                .map_err(|_| DatabaseError::LockPoisoned("lecteur/rédacteur"))?;
+        self.append(&entry)?;
+        let token = self.sync_token();
+        self.evict(&entry.key)?;
+        self.maybe_compact()?;
+        Ok(token)
+    }
 
-            let offset = {
-                let mut file = self
-                    .shared
-                    .file
-                    .lock()
-                    .map_err(|_| DatabaseError::LockPoisoned("fichier"))?;
-                let offset = file.seek(SeekFrom::End(0))?;
-                file.write_all(&bytes)?;
-                file.flush()?;
-                offset
-            };
+    /// Comme `set`, selon `options`.
+    pub fn set_with_options(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        options: WriteOptions,
+    ) -> Result<SyncToken, DatabaseError> {
+        self.admit_write()?;
 
-            let mut index = self
-                .shared
-                .index
-                .write()
-                .map_err(|_| DatabaseError::LockPoisoned("index"))?;
-            index.insert(key.clone(), IndexEntry { offset, size });
+        let entry = DataEntry {
+            entry_type: EntryType::Data,
+            key,
+            value,
+        };
+        {
+            let _access_guard = self.write_access()?;
+            self.append_locked(&entry)?;
+            if options.sync {
+                self.lock_file()?
+                    .sync_all()
+                    .context(IoOp::LogSync, &self.config.file_path)?;
+                self.shared
+                    .last_sync
+                    .store(self.config.clock.now_millis(), Ordering::Relaxed);
+            }
         }
-
+        let token = self.sync_token();
+        self.evict(&entry.key)?;
         self.maybe_compact()?;
-        Ok(())
+        Ok(token)
     }
 
-    /// Récupère une valeur si elle existe.
-    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
-        let index_info = {
-            let index = self
-                .shared
-                .index
-                .read()
-                .map_err(|_| DatabaseError::LockPoisoned("index"))?;
-            match index.get(key) {
-                Some(entry) => *entry,
-                None => return Ok(None),
-            }
-        };
-
-        let _access_guard = self
-            .shared
-            .access
-            .read()
-            .map_err(|_| DatabaseError::LockPoisoned("lecteur/rédacteur"))?;
-
-        let mut file = File::open(&self.config.file_path)?;
-        Self::read_entry_value(&mut file, &index_info, key)
-    }
+    /// Comme `set`, en joignant à la valeur des métadonnées utilisateur (type
+    /// de contenu, version de schéma…) d'au plus `MAX_META_LEN` octets,
+    /// stockées dans l'en-tête de l'enregistrement. Un `set` ultérieur les
+    /// efface.
+    pub fn set_with_meta(
+        &self,
+        key: Vec<u8>,
+        value: Vec<u8>,
+        meta: &[u8],
+    ) -> Result<SyncToken, DatabaseError> {
+        if meta.len() > codec::MAX_META_LEN {
+            return Err(DatabaseError::MetadataTooLarge);
+        }
+        self.admit_write()?;
 
-    /// Supprime une clé via tombstone.
-    pub fn delete(&self, key: Vec<u8>) -> Result<(), DatabaseError> {
         let entry = DataEntry {
-            entry_type: EntryType::Tombstone,
-            key: key.clone(),
-            value: Vec::new(),
+            entry_type: EntryType::Data,
+            key,
+            value,
         };
-        let bytes = entry.to_bytes();
-        let size = bytes.len() as u32;
-
         {
-            let _access_guard = self
-                .shared
-                .access
-                .write()
-                .map_err(|_| DatabaseError::LockPoisoned("lecteur/rédacteur"))?;
-
-            let offset = {
-                let mut file = self
-                    .shared
-                    .file
-                    .lock()
-                    .map_err(|_| DatabaseError::LockPoisoned("fichier"))?;
-                let offset = file.seek(SeekFrom::End(0))?;
-                file.write_all(&bytes)?;
-                file.flush()?;
-                offset
-            };
-
-            let mut index = self
-                .shared
-                .index
-                .write()
-                .map_err(|_| DatabaseError::LockPoisoned("index"))?;
-            index.insert(key.clone(), IndexEntry { offset, size });
+            let _access_guard = self.write_access()?;
+            self.append_encoded(&entry, self.encode(&entry, meta)?)?;
         }
-
+        let token = self.sync_token();
+        self.evict(&entry.key)?;
         self.maybe_compact()?;
-        Ok(())
+        Ok(token)
     }
 
-    fn decode_buffer(buffer: &[u8], key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
-        if buffer.len() < 9 {
-            return Err(DatabaseError::InvalidFormat);
+    /// Comme `set`, mais si `key` contient déjà une valeur simple proche, seul
+    /// l'écart avec elle est journalisé (enregistrement delta reconstitué à la
+    /// lecture et fusionné par la compaction). Les métadonnées de la valeur
+    /// précédente sont conservées. La valeur précédente est lue à chaque
+    /// appel : à réserver aux grosses valeurs peu modifiées.
+    pub fn set_delta(&self, key: Vec<u8>, value: Vec<u8>) -> Result<SyncToken, DatabaseError> {
+        self.admit_write()?;
+
+        let ((), delta) = self.update_admitted(key.clone(), |current| {
+            let Some(Value::Bytes(old)) = current else {
+                return Ok((None, ()));
+            };
+            let op = merge::splice_diff(old, &value);
+            let worthwhile =
+                matches!(&op, MergeOp::Splice { bytes, .. } if bytes.len() < value.len() / 2);
+            Ok((worthwhile.then_some(op), ()))
+        })?;
+        if !delta {
+            return self.store(key, value);
         }
+        let token = self.sync_token();
+        self.evict(&key)?;
+        Ok(token)
+    }
 
-        if buffer[0] == 1 {
+    /// Valeur et métadonnées de `key`.
+    pub fn get_with_meta(&self, key: &[u8]) -> Result<Option<ValueWithMeta>, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let Some((entry, mut reader)) = self.locate(key)? else {
+            return Ok(None);
+        };
+        if entry.tombstone {
             return Ok(None);
         }
+        let raw = reader.read_raw(&entry)?;
+        let decoded = codec::verify_record(&raw).and_then(|record| {
+            if record.key != key {
+                return Err(index_mismatch(key, entry.offset));
+            }
+            let value = match record.entry_type {
+                EntryType::Data => Some(reader.decode_value(&record)?),
+                EntryType::Tombstone | EntryType::Merge => None,
+                EntryType::Dictionary | EntryType::Blob => {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+            };
+            Ok((record.entry_type, value, record.meta.to_vec()))
+        });
+        let (entry_type, value, meta) = decoded
+            .map_err(|err| err.at_record(&self.config.file_path, entry.offset))
+            .inspect_err(|err| self.note_corruption(err))?;
+        // Un delta garde les métadonnées de la valeur qu'il modifie.
+        let value = match entry_type {
+            EntryType::Merge => match Self::resolve(&mut reader, &entry, key)
+                .inspect_err(|err| self.note_corruption(err))?
+                .0
+            {
+                None => None,
+                Some(Value::Bytes(bytes)) => Some(bytes),
+                Some(_) => return Err(DatabaseError::WrongType),
+            },
+            _ => value,
+        };
+        let value = value.map(|value| ValueWithMeta { value, meta });
+        if value.is_some() && self.config.eviction != Eviction::None {
+            self.shared
+                .recency
+                .lock()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))?
+                .touch(key);
+        }
+        Ok(value)
+    }
 
-        let key_len = u32::from_be_bytes(
-            buffer[1..5]
-                .try_into()
-                .map_err(|_| DatabaseError::InvalidFormat)?,
-        ) as usize;
-        let value_len = u32::from_be_bytes(
-            buffer[5..9]
-                .try_into()
-                .map_err(|_| DatabaseError::InvalidFormat)?,
-        ) as usize;
-
-        let total_len = 9 + key_len + value_len + 4;
-        if total_len > buffer.len() {
-            return Err(DatabaseError::CorruptedData);
+    /// Récupère une valeur si elle existe.
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let Some((index_info, mut file)) = self.locate(key)? else {
+            return Ok(None);
+        };
+        let (value, _) = Self::resolve(&mut file, &index_info, key)
+            .inspect_err(|err| self.note_corruption(err))?;
+        if value.is_some() && self.config.eviction != Eviction::None {
+            self.shared
+                .recency
+                .lock()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))?
+                .touch(key);
         }
+        match value {
+            None => Ok(None),
+            Some(Value::Bytes(bytes)) => Ok(Some(bytes)),
+            Some(_) => Err(DatabaseError::WrongType),
+        }
+    }
 
-        let key_start = 9;
-        let key_end = key_start + key_len;
-        let value_start = key_end;
-        let value_end = value_start + value_len;
-        let checksum_start = value_end;
+    /// Comme `get`, en écrivant la valeur dans `out` (remplacé) plutôt que
+    /// dans un nouveau `Vec` : un appelant qui réutilise `out` n'alloue plus à
+    /// chaque lecture. Retourne `false` si la clé est absente (`out` est
+    /// alors inchangé).
+    pub fn get_into(&self, key: &[u8], out: &mut Vec<u8>) -> Result<bool, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let Some((entry, mut reader)) = self.locate(key)? else {
+            return Ok(false);
+        };
+        if entry.tombstone {
+            return Ok(false);
+        }
+        let decoded =
+            RAW_RECORD.with_borrow_mut(|raw| {
+                reader.read_raw_into(&entry, raw)?;
+                let record = codec::verify_record(raw)
+                    .map_err(|err| err.at_record(&self.config.file_path, entry.offset))?;
+                match record.entry_type {
+                    _ if record.key != key => Err(index_mismatch(key, entry.offset)),
+                    EntryType::Data => reader
+                        .decode_value_into(&record, out)
+                        .map(|_| Some(true))
+                        .map_err(|err| err.at_record(&self.config.file_path, entry.offset)),
+                    EntryType::Tombstone => Ok(Some(false)),
+                    EntryType::Merge => Ok(None),
+                    EntryType::Dictionary | EntryType::Blob => Err(DatabaseError::InvalidFormat
+                        .at_record(&self.config.file_path, entry.offset)),
+                }
+            });
+        let found = match decoded.inspect_err(|err| self.note_corruption(err))? {
+            Some(found) => found,
+            None => match Self::resolve(&mut reader, &entry, key)
+                .inspect_err(|err| self.note_corruption(err))?
+                .0
+            {
+                None => false,
+                Some(Value::Bytes(bytes)) => {
+                    *out = bytes;
+                    true
+                }
+                Some(_) => return Err(DatabaseError::WrongType),
+            },
+        };
+        if found && self.config.eviction != Eviction::None {
+            self.shared
+                .recency
+                .lock()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))?
+                .touch(key);
+        }
+        Ok(found)
+    }
 
-        if &buffer[key_start..key_end] != key {
+    /// Lit la valeur matérialisée d'une clé, quel que soit son type.
+    pub(crate) fn read_value(&self, key: &[u8]) -> Result<Option<Value>, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let Some((index_info, mut file)) = self.locate(key)? else {
             return Ok(None);
-        }
+        };
+        Ok(Self::resolve(&mut file, &index_info, key)
+            .inspect_err(|err| self.note_corruption(err))?
+            .0)
+    }
 
-        let mut somme: u32 = 0;
-        for byte in &buffer[0..value_end] {
-            somme = somme.wrapping_add(*byte as u32);
+    /// Entrée de `key` et journal ouvert dans la même génération. Si une
+    /// compaction remplace le journal entre la lecture de l'index et
+    /// l'ouverture, l'index est relu.
+    fn locate(&self, key: &[u8]) -> Result<Option<(IndexEntry, LogReader)>, DatabaseError> {
+        for _ in 0..MAX_GENERATION_RETRIES {
+            let Some(entry) = self.index_read()?.get(key).copied() else {
+                return Ok(None);
+            };
+            if let Some(reader) = self.open_reader()?
+                && reader.generation == entry.generation
+            {
+                return Ok(Some((entry, reader)));
+            }
+            thread::yield_now();
         }
+        Err(DatabaseError::Timeout)
+    }
 
-        let stored_checksum = u32::from_be_bytes(
-            buffer[checksum_start..checksum_start + 4]
-                .try_into()
-                .map_err(|_| DatabaseError::CorruptedData)?,
-        );
-        if somme != stored_checksum {
-            return Err(DatabaseError::CorruptedData);
+    /// Ouvre le journal en relevant sa génération et son dictionnaire ;
+    /// `None` si un remplacement a eu lieu pendant l'ouverture.
+    pub(crate) fn open_reader(&self) -> Result<Option<LogReader>, DatabaseError> {
+        let before = self.shared.file_epoch.load(Ordering::Acquire);
+        if before % 2 == 1 {
+            return Ok(None);
         }
+        let mut reader = self.log_reader()?;
+        reader.dictionary = Some(self.lock_dictionary()?.clone());
+        if self.shared.file_epoch.load(Ordering::Acquire) != before {
+            return Ok(None);
+        }
+        reader.generation = before / 2;
+        Ok(Some(reader))
+    }
 
-        let decoded = Lz77::decode(&buffer[value_start..value_end])?;
-        Ok(Some(decoded))
+    /// Lecteur du journal appliquant `max_expansion`.
+    pub(crate) fn log_reader(&self) -> Result<LogReader, DatabaseError> {
+        Ok(LogReader::new(&self.config.file_path)?.with_max_expansion(self.config.max_expansion))
     }
 
-    fn read_entry_value(
-        reader: &mut File,
-        entry: &IndexEntry,
-        key: &[u8],
-    ) -> Result<Option<Vec<u8>>, DatabaseError> {
-        let mut buffer = vec![0; entry.size as usize];
-        reader.seek(SeekFrom::Start(entry.offset))?;
-        reader.read_exact(&mut buffer)?;
-        Self::decode_buffer(&buffer, key)
+    /// Génération courante du journal.
+    pub(crate) fn generation(&self) -> u64 {
+        self.shared.file_epoch.load(Ordering::Acquire) / 2
     }
 
-    /// Compacte le journal pour ne garder que les entrées valides.
-    pub fn compact(&self) -> Result<(), DatabaseError> {
-        let _access_guard = self
-            .shared
-            .access
-            .write()
-            .map_err(|_| DatabaseError::LockPoisoned("lecteur/rédacteur"))?;
+    /// Lecture-modification-écriture atomique d'une clé via une opérande de
+    /// fusion. `f` reçoit la valeur courante et retourne l'opérande à
+    /// journaliser (ou `None` pour ne rien écrire) et le résultat de l'appel.
+    /// L'écriture est soumise à `backpressure` et à l'éviction comme `set`.
+    pub(crate) fn update<R>(
+        &self,
+        key: Vec<u8>,
+        f: impl FnOnce(Option<&Value>) -> Result<(Option<MergeOp>, R), DatabaseError>,
+    ) -> Result<R, DatabaseError> {
+        self.admit_write()?;
+        let (result, written) = self.update_admitted(key.clone(), f)?;
+        if written {
+            self.evict(&key)?;
+        }
+        Ok(result)
+    }
 
-        let index_snapshot = {
-            let index = self
-                .shared
-                .index
-                .read()
-                .map_err(|_| DatabaseError::LockPoisoned("index"))?;
-            let mut snapshot: Vec<(Vec<u8>, IndexEntry)> =
-                index.iter().map(|(k, entry)| (k.clone(), *entry)).collect();
-            snapshot.sort_by_key(|(_, entry)| entry.offset);
-            snapshot
-        };
+    /// `update` une fois l'écriture admise par `admit_write`, sans éviction.
+    /// Indique aussi si un enregistrement a été écrit.
+    fn update_admitted<R>(
+        &self,
+        key: Vec<u8>,
+        f: impl FnOnce(Option<&Value>) -> Result<(Option<MergeOp>, R), DatabaseError>,
+    ) -> Result<(R, bool), DatabaseError> {
+        let (result, written) = {
+            let _access_guard = self.write_access()?;
+
+            let current_entry = self
+                .index_read()?
+                .get(&key)
+                .copied()
+                .filter(|entry| !entry.tombstone);
+            let (current, depth, meta) = match &current_entry {
+                Some(entry) => {
+                    let mut file = self.log_reader()?;
+                    let meta = Self::read_meta(&mut file, entry)
+                        .inspect_err(|err| self.note_corruption(err))?;
+                    let (current, depth) = Self::resolve(&mut file, entry, &key)
+                        .inspect_err(|err| self.note_corruption(err))?;
+                    (current, depth, meta)
+                }
+                None => (None, 0, Vec::new()),
+            };
 
-        let live_entries = {
-            let mut reader = File::open(&self.config.file_path)?;
-            let mut entries = Vec::new();
-            for (key, entry) in index_snapshot {
-                if let Some(value) = Self::read_entry_value(&mut reader, &entry, &key)? {
-                    entries.push((key, value));
+            let (op, result) = f(current.as_ref())?;
+            match op {
+                None => (result, false),
+                Some(op) => {
+                    let entry = if depth >= MAX_MERGE_DEPTH {
+                        match merge::apply(current, op)? {
+                            Some(value) => base_entry(key, value),
+                            None => tombstone_entry(key),
+                        }
+                    } else {
+                        let record = MergeRecord {
+                            prev: current_entry.map(|entry| (entry.offset, entry.size)),
+                            op,
+                        };
+                        match merge::apply(current, record.op.clone())? {
+                            Some(_) => DataEntry {
+                                entry_type: EntryType::Merge,
+                                key,
+                                value: record.to_bytes(),
+                            },
+                            None => tombstone_entry(key),
+                        }
+                    };
+                    // Les métadonnées de la valeur sont reportées sur le
+                    // nouvel enregistrement.
+                    let meta = match entry.entry_type {
+                        EntryType::Tombstone => &[][..],
+                        _ => &meta,
+                    };
+                    self.append_encoded(&entry, self.encode(&entry, meta)?)?;
+                    (result, true)
                 }
             }
-            entries
         };
 
-        let temp_path = self.config.file_path.with_extension("db.compacted");
-        let _ = std::fs::remove_file(&temp_path);
+        if written {
+            self.maybe_compact()?;
+        }
+        Ok((result, written))
+    }
 
-        let mut new_index = HashMap::new();
-        {
-            let mut temp_file = OpenOptions::new()
-                .write(true)
-                .create(true)
-                .truncate(true)
-                .open(&temp_path)?;
-            for (key, value) in &live_entries {
-                let entry = DataEntry {
-                    entry_type: EntryType::Data,
-                    key: key.clone(),
-                    value: value.clone(),
-                };
-                let bytes = entry.to_bytes();
-                let offset = temp_file.seek(SeekFrom::End(0))?;
-                temp_file.write_all(&bytes)?;
-                new_index.insert(
-                    key.clone(),
-                    IndexEntry {
-                        offset,
-                        size: bytes.len() as u32,
-                    },
+    /// Comme `get`, mais une clé absente est une erreur `KeyNotFound`.
+    pub fn get_required(&self, key: &[u8]) -> Result<Vec<u8>, DatabaseError> {
+        self.get(key)?
+            .ok_or_else(|| DatabaseError::KeyNotFound(key.to_vec()))
+    }
+
+    /// Tailles de la valeur de `key`, `None` si la clé est absente. Seul
+    /// l'enregistrement désigné par l'index est lu, sans décompression, sauf
+    /// pour une valeur écrite par delta (`set_delta`, `json_set`), reconstituée
+    /// comme par `get` ; `DatabaseError::WrongType` pour une collection.
+    pub fn value_size(&self, key: &[u8]) -> Result<Option<ValueSize>, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let Some((entry, mut reader)) = self.locate(key)? else {
+            return Ok(None);
+        };
+        if entry.tombstone {
+            return Ok(None);
+        }
+        let raw = reader.read_raw(&entry)?;
+        let record = codec::verify_record(&raw)
+            .and_then(|record| match record.key == key {
+                true => Ok(record),
+                false => Err(index_mismatch(key, entry.offset)),
+            })
+            .map_err(|err| err.at_record(&self.config.file_path, entry.offset))
+            .inspect_err(|err| self.note_corruption(err))?;
+        match record.entry_type {
+            EntryType::Data => {}
+            EntryType::Tombstone => return Ok(None),
+            EntryType::Merge => return self.merged_size(&mut reader, &entry, key),
+            EntryType::Dictionary | EntryType::Blob => {
+                return Err(
+                    DatabaseError::InvalidFormat.at_record(&self.config.file_path, entry.offset)
                 );
             }
-            temp_file.flush()?;
         }
+        let (uncompressed, compressed) = reader
+            .decoded_len(&record)
+            .and_then(|len| Ok((len, reader.stored_len(&record)?)))
+            .map_err(|err| err.at_record(&self.config.file_path, entry.offset))?;
+        Ok(Some(ValueSize {
+            uncompressed: uncompressed as u64,
+            compressed: compressed as u64,
+        }))
+    }
 
+    /// `value_size` d'une valeur reconstituée à partir d'enregistrements
+    /// delta : `compressed` cumule les valeurs stockées de toute la chaîne.
+    fn merged_size(
+        &self,
+        reader: &mut LogReader,
+        entry: &IndexEntry,
+        key: &[u8],
+    ) -> Result<Option<ValueSize>, DatabaseError> {
+        let value = match Self::resolve(reader, entry, key)
+            .inspect_err(|err| self.note_corruption(err))?
+            .0
         {
-            let _guard = self
-                .shared
-                .file
-                .lock()
-                .map_err(|_| DatabaseError::LockPoisoned("fichier"))?;
-            drop(_guard);
-        }
+            None => return Ok(None),
+            Some(Value::Bytes(bytes)) => bytes,
+            Some(_) => return Err(DatabaseError::WrongType),
+        };
 
-        match std::fs::rename(&temp_path, &self.config.file_path) {
-            Ok(_) => {}
-            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
-                std::fs::remove_file(&self.config.file_path)?;
-                std::fs::rename(&temp_path, &self.config.file_path)?;
+        let mut compressed = 0u64;
+        let mut next = Some((entry.offset, entry.size));
+        while let Some((offset, size)) = next.take() {
+            let raw = reader.read_raw(&IndexEntry {
+                offset,
+                size,
+                tombstone: false,
+                generation: entry.generation,
+            })?;
+            let record = codec::verify_record(&raw)
+                .map_err(|err| err.at_record(&self.config.file_path, offset))?;
+            compressed += reader
+                .stored_len(&record)
+                .map_err(|err| err.at_record(&self.config.file_path, offset))?
+                as u64;
+            if record.entry_type == EntryType::Merge {
+                let bytes = reader
+                    .decode_value(&record)
+                    .and_then(|bytes| MergeRecord::from_bytes(&bytes))
+                    .map_err(|err| err.at_record(&self.config.file_path, offset))?;
+                next = bytes.prev;
             }
-            Err(err) => return Err(err.into()),
-        }
-
-        let new_file = OpenOptions::new()
-            .read(true)
-            .append(true)
-            .create(true)
-            .open(&self.config.file_path)?;
-        {
-            let mut guard = self
-                .shared
-                .file
-                .lock()
-                .map_err(|_| DatabaseError::LockPoisoned("fichier"))?;
-            *guard = new_file;
         }
+        Ok(Some(ValueSize {
+            uncompressed: value.len() as u64,
+            compressed,
+        }))
+    }
 
-        let mut index_guard = self
-            .shared
-            .index
-            .write()
-            .map_err(|_| DatabaseError::LockPoisoned("index"))?;
-        *index_guard = new_index;
+    /// Indique si la clé existe, d'après l'index seul : aucune lecture du
+    /// journal. Une collection vidée étant remplacée par un tombstone, toute
+    /// entrée vivante de l'index désigne une valeur présente.
+    pub fn contains_key(&self, key: &[u8]) -> Result<bool, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        Ok(self
+            .index_read()?
+            .get(key)
+            .is_some_and(|entry| !entry.tombstone))
+    }
 
+    /// Supprime une clé via tombstone.
+    pub fn delete(&self, key: Vec<u8>) -> Result<(), DatabaseError> {
+        let entry = DataEntry {
+            entry_type: EntryType::Tombstone,
+            key,
+            value: Vec::new(),
+        };
+        self.append(&entry)?;
+        self.maybe_compact()?;
         Ok(())
     }
 
-    fn file_size(&self) -> Result<u64, DatabaseError> {
-        Ok(std::fs::metadata(&self.config.file_path)?.len())
+    /// Efface toute trace de `key`, contrairement à `delete` dont le
+    /// tombstone reste dans l'index. Au retour, la suppression est durable
+    /// (`fsync`) et la clé est absente de l'index ; la compaction suivante
+    /// retire du journal toutes ses versions et le tombstone lui-même. Une
+    /// compaction en cours est attendue, pour que le tombstone ne soit pas
+    /// recopié dans le journal qu'elle produit.
+    pub fn purge(&self, key: Vec<u8>) -> Result<(), DatabaseError> {
+        {
+            let _compaction_guard = self.lock_compaction()?;
+            let _access_guard = self.write_access()?;
+            let entry = tombstone_entry(key);
+            self.append_locked(&entry)?;
+            self.lock_file()?
+                .sync_all()
+                .context(IoOp::LogSync, &self.config.file_path)?;
+            self.shared
+                .last_sync
+                .store(self.config.clock.now_millis(), Ordering::Relaxed);
+            self.index_write()?.remove(&entry.key);
+        }
+        self.maybe_compact()
     }
 
-    fn maybe_compact(&self) -> Result<(), DatabaseError> {
-        if self.config.max_size == 0 {
+    /// Supprime plusieurs clés : les tombstones sont écrits d'un bloc sous un
+    /// seul verrou d'écriture, suivi d'un unique `fsync`. Le lot est soumis à
+    /// `backpressure` comme `set`.
+    pub fn delete_many(&self, keys: &[Vec<u8>]) -> Result<(), DatabaseError> {
+        if keys.is_empty() {
             return Ok(());
         }
-
-        loop {
-            let len = self.file_size()?;
-            if len < self.config.max_size {
-                break;
-            }
-            let before = len;
-            self.compact()?;
-            let after = self.file_size()?;
-            if after >= before {
-                break;
+        self.admit_write()?;
+        {
+            let _access_guard = self.write_access()?;
+            let mut file = self.lock_file()?;
+            let start = file
+                .seek(SeekFrom::End(0))
+                .context(IoOp::LogWrite, &self.config.file_path)?;
+            // Le verrou du fichier exclut un remplacement du journal.
+            let generation = self.generation();
+            let mut batch = Vec::new();
+            let mut pending = Vec::with_capacity(keys.len());
+            for key in keys {
+                let entry = DataEntry {
+                    entry_type: EntryType::Tombstone,
+                    key: key.clone(),
+                    value: Vec::new(),
+                };
+                let offset = start + batch.len() as u64;
+                let location = IndexEntry {
+                    offset,
+                    size: entry.write_to(&mut batch) as u32,
+                    tombstone: true,
+                    generation,
+                };
+                pending.push((entry, location));
             }
+            self.write_bulk_chunk(&mut file, start, &mut batch, &mut pending)?;
+            file.sync_all()
+                .context(IoOp::LogSync, &self.config.file_path)?;
+            let now = self.config.clock.now_millis();
+            self.shared.last_sync.store(now, Ordering::Relaxed);
+            self.shared.last_write.store(now, Ordering::Relaxed);
         }
-
-        Ok(())
+        self.maybe_compact()
     }
 
-    fn recover_index(path: &PathBuf) -> Result<HashMap<Vec<u8>, IndexEntry>, DatabaseError> {
-        let mut index = HashMap::new();
-        let mut iter = LogIter::new(path)?;
+    /// Ajoute un lot d'entrées (`Data` ou `Tombstone`) sous un seul verrou
+    /// d'écriture, en maintenant l'index, puis applique éviction et compaction
+    /// une seule fois. Retourne le nombre d'entrées écrites.
+    ///
+    /// Les opérandes de fusion sont refusées : elles référencent des positions
+    /// d'un autre journal. Les dictionnaires et valeurs partagées aussi : ce
+    /// ne sont pas des entrées.
+    pub fn ingest(
+        &self,
+        entries: impl IntoIterator<Item = DataEntry>,
+    ) -> Result<usize, DatabaseError> {
+        let entries: Vec<DataEntry> = entries.into_iter().collect();
+        if entries.iter().any(|entry| {
+            matches!(
+                entry.entry_type,
+                EntryType::Merge | EntryType::Dictionary | EntryType::Blob
+            )
+        }) {
+            return Err(DatabaseError::InvalidFormat);
+        }
+        self.admit_write()?;
 
-        for record in &mut iter {
-            let record = record?;
-            if !record.checksum_ok {
-                return Err(DatabaseError::CorruptedData);
+        {
+            let _access_guard = self.write_access()?;
+            for entry in &entries {
+                self.append_locked(entry)?;
             }
-            index.insert(
-                record.key,
-                IndexEntry {
-                    offset: record.offset,
-                    size: record.size,
-                },
-            );
         }
 
-        Ok(index)
+        if let Some(last) = entries.last() {
+            self.evict(&last.key)?;
+        }
+        self.maybe_compact()?;
+        Ok(entries.len())
+    }
+
+    /// Importe les entrées vivantes (valeurs simples et collections) d'un
+    /// autre fichier de base, lu sans être ouvert en écriture. Retourne le
+    /// nombre d'entrées écrites.
+    pub fn merge_from(
+        &self,
+        other_path: impl AsRef<Path>,
+        conflict: ConflictPolicy,
+    ) -> Result<usize, DatabaseError> {
+        let other_path = other_path.as_ref().to_path_buf();
+        let (other_index, _) = Self::recover_index(&other_path, self.config.max_record_size)?;
+        let mut incoming: Vec<(Vec<u8>, IndexEntry)> = other_index
+            .into_iter()
+            .filter(|(_, entry)| !entry.tombstone)
+            .collect();
+        incoming.sort_by_key(|(_, entry)| entry.offset);
+
+        self.admit_write()?;
+        let mut written = 0;
+        let mut last_key = None;
+        {
+            let _access_guard = self.write_access()?;
+
+            let existing: HashSet<Vec<u8>> = {
+                let index = self.index_read()?;
+                incoming
+                    .iter()
+                    .filter(|(key, _)| index.get(key).is_some_and(|entry| !entry.tombstone))
+                    .map(|(key, _)| key.clone())
+                    .collect()
+            };
+            if conflict == ConflictPolicy::Error
+                && let Some((key, _)) = incoming.iter().find(|(key, _)| existing.contains(key))
+            {
+                return Err(DatabaseError::KeyConflict(key.clone()));
+            }
+
+            let mut reader =
+                LogReader::new(&other_path)?.with_max_expansion(self.config.max_expansion);
+            for (key, entry) in incoming {
+                if conflict == ConflictPolicy::KeepExisting && existing.contains(&key) {
+                    continue;
+                }
+                let (Some(value), _) = Self::resolve(&mut reader, &entry, &key)? else {
+                    continue;
+                };
+                let entry = base_entry(key, value);
+                self.append_locked(&entry)?;
+                written += 1;
+                last_key = Some(entry.key);
+            }
+        }
+
+        if let Some(key) = last_key {
+            self.evict(&key)?;
+        }
+        self.maybe_compact()?;
+        Ok(written)
+    }
+
+    /// Écrit dans `path` (qui ne doit pas exister) une copie compactée des
+    /// entrées vivantes dont la clé satisfait `filter`, sans que `path`
+    /// existe jamais à moitié écrit. Retourne le nombre d'entrées copiées.
+    pub fn clone_to(
+        &self,
+        path: impl AsRef<Path>,
+        filter: impl Fn(&[u8]) -> bool,
+    ) -> Result<usize, DatabaseError> {
+        let path = path.as_ref();
+        let _access_guard = self.read_access()?;
+        let mut selected: Vec<(Vec<u8>, IndexEntry)> = self
+            .index_read()?
+            .iter()
+            .filter(|(key, entry)| !entry.tombstone && filter(key))
+            .map(|(key, entry)| (key.clone(), *entry))
+            .collect();
+        selected.sort_by_key(|(_, entry)| entry.offset);
+
+        let mut reader = self.log_reader()?.with_read_ahead(self.config.read_ahead);
+        self.write_copy(&selected, &mut reader, path)
+    }
+
+    /// Recopie les enregistrements compactés de `entries` (triées par
+    /// position) dans un fichier `<path>.partial`, synchronisé puis lié sous
+    /// `path`. Échoue si `path` existe déjà, y compris s'il apparaît pendant
+    /// la copie : un fichier existant n'est jamais écrasé. Retourne le nombre
+    /// d'entrées copiées.
+    pub(crate) fn write_copy(
+        &self,
+        entries: &[(Vec<u8>, IndexEntry)],
+        reader: &mut LogReader,
+        path: &Path,
+    ) -> Result<usize, DatabaseError> {
+        // Évite une copie inutile ; seul `hard_link` fait foi.
+        if path.exists() {
+            return Err(DatabaseError::io(
+                IoOp::CopyCreate,
+                path,
+                ErrorKind::AlreadyExists.into(),
+            ));
+        }
+        let mut temp_name = path.file_name().unwrap_or_default().to_os_string();
+        temp_name.push(".partial");
+        let temp_path = path.with_file_name(temp_name);
+        let mut target = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&temp_path)
+            .context(IoOp::CopyCreate, &temp_path)?;
+        let copied = (|| {
+            let raw_copy = self.config.compaction_raw_copy;
+            let min_savings = self.config.min_compression_savings;
+            // La copie garde le dictionnaire du journal, en tête comme lui.
+            let dictionary = reader.dictionary()?;
+            if let Some(dictionary) = &dictionary {
+                target
+                    .write_all(&dictionary.to_record())
+                    .context(IoOp::CopyWrite, &temp_path)?;
+            }
+            let mut blobs = BlobCopy::new(raw_copy, min_savings, dictionary.clone(), 0);
+            let mut prefix = Vec::new();
+            let mut copied = 0;
+            for (key, entry) in entries {
+                let record = Self::compaction_copy(
+                    reader,
+                    entry,
+                    key,
+                    raw_copy,
+                    min_savings,
+                    dictionary.as_deref(),
+                    false,
+                )
+                .inspect_err(|err| self.note_corruption(err))?;
+                if let Some(mut record) = record {
+                    let position = target
+                        .stream_position()
+                        .context(IoOp::CopyWrite, &temp_path)?;
+                    prefix.clear();
+                    blobs
+                        .place(reader, &mut record, position, &mut prefix)
+                        .inspect_err(|err| self.note_corruption(err))?;
+                    target
+                        .write_all(&prefix)
+                        .and_then(|_| target.write_all(&record.bytes))
+                        .context(IoOp::CopyWrite, &temp_path)?;
+                    copied += 1;
+                }
+            }
+            target.sync_all().context(IoOp::CopySync, &temp_path)?;
+            // Contrairement à `rename`, `hard_link` échoue si `path` existe.
+            std::fs::hard_link(&temp_path, path).context(IoOp::CopyPublish, path)?;
+            sync_parent_dir(path)?;
+            Ok(copied)
+        })();
+        let _ = std::fs::remove_file(&temp_path);
+        copied
+    }
+
+    /// Charge un grand volume de paires clé/valeur : écritures groupées par
+    /// blocs, un seul `fsync` final et ni éviction par entrée ni compaction
+    /// automatique. Retourne le nombre d'entrées écrites.
+    ///
+    /// L'index ne référence un bloc qu'une fois celui-ci écrit : en cas
+    /// d'erreur, les entrées déjà indexées restent lisibles.
+    pub fn bulk_load(
+        &self,
+        entries: impl Iterator<Item = (Vec<u8>, Vec<u8>)>,
+    ) -> Result<usize, DatabaseError> {
+        self.admit_write()?;
+        let _access_guard = self.write_access()?;
+        let mut file = self.lock_file()?;
+
+        // Avec un plafond strict, chaque entrée est vérifiée contre l'index à jour.
+        let chunk_limit = if self.config.enforce_max_size {
+            0
+        } else {
+            BULK_CHUNK_SIZE
+        };
+        let mut chunk_start = file
+            .seek(SeekFrom::End(0))
+            .context(IoOp::LogWrite, &self.config.file_path)?;
+        let mut chunk = Vec::with_capacity(chunk_limit);
+        let mut pending: Vec<(DataEntry, IndexEntry)> = Vec::new();
+        let mut count = 0;
+        let mut last_key = None;
+        let dictionary = self.lock_dictionary()?.clone();
+
+        for (key, value) in entries {
+            let entry = DataEntry {
+                entry_type: EntryType::Data,
+                key,
+                value,
+            };
+            // Un nouvel enregistrement `Blob` est écrit après le bloc en cours.
+            let blob = match self.find_blob(&entry)? {
+                BlobLookup::Inline => None,
+                BlobLookup::Shared(offset) => Some(offset),
+                BlobLookup::Missing(hash) => {
+                    if let Some((entry, _)) = pending.last() {
+                        last_key = Some(entry.key.clone());
+                    }
+                    self.write_bulk_chunk(&mut file, chunk_start, &mut chunk, &mut pending)?;
+                    let blob =
+                        self.append_blob(&mut file, hash, &entry.value, dictionary.as_deref())?;
+                    chunk_start = blob.offset + blob.size as u64;
+                    Some(blob.offset)
+                }
+            };
+            let offset = chunk_start + chunk.len() as u64;
+            let size = match blob {
+                Some(blob) => codec::encode_reference_into(&entry.key, &[], blob, &mut chunk),
+                None => codec::encode_record_into(
+                    &entry,
+                    &[],
+                    self.config.min_compression_savings,
+                    dictionary.as_deref(),
+                    &mut chunk,
+                ),
+            } as u32;
+            self.check_capacity(&entry.key, size)?;
+            let location = IndexEntry {
+                offset,
+                size,
+                tombstone: false,
+                generation: self.generation(),
+            };
+            pending.push((entry, location));
+            count += 1;
+
+            if chunk.len() >= chunk_limit {
+                last_key = pending.last().map(|(entry, _)| entry.key.clone());
+                chunk_start =
+                    self.write_bulk_chunk(&mut file, chunk_start, &mut chunk, &mut pending)?;
+            }
+        }
+        if let Some((entry, _)) = pending.last() {
+            last_key = Some(entry.key.clone());
+        }
+        self.write_bulk_chunk(&mut file, chunk_start, &mut chunk, &mut pending)?;
+        file.sync_all()
+            .context(IoOp::LogSync, &self.config.file_path)?;
+        self.shared
+            .last_sync
+            .store(self.config.clock.now_millis(), Ordering::Relaxed);
+        drop(file);
+        drop(_access_guard);
+
+        if count > 0 {
+            self.shared
+                .last_write
+                .store(self.config.clock.now_millis(), Ordering::Relaxed);
+        }
+        if let Some(key) = last_key {
+            self.evict(&key)?;
+        }
+        Ok(count)
+    }
+
+    /// Écrit un bloc de `bulk_load` ou `delete_many` puis indexe ses entrées.
+    /// Retourne la position du bloc suivant.
+    fn write_bulk_chunk(
+        &self,
+        file: &mut File,
+        chunk_start: u64,
+        chunk: &mut Vec<u8>,
+        pending: &mut Vec<(DataEntry, IndexEntry)>,
+    ) -> Result<u64, DatabaseError> {
+        self.write_log(file, chunk_start, |file| file.write_all(chunk))?;
+        let chunk_end = chunk_start + chunk.len() as u64;
+        for (entry, location) in pending.drain(..) {
+            let start = (location.offset - chunk_start) as usize;
+            self.count_compression(&entry, &chunk[start..]);
+            self.track_record(&entry.key, location)?;
+            self.sync_text_entry(&entry, &location)?;
+        }
+        let _ = self.shared.indexed_end.compare_exchange(
+            chunk_start,
+            chunk_end,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        chunk.clear();
+        Ok(chunk_end)
+    }
+
+    /// Ajoute des octets au journal dont la fin est à `end`. Si l'écriture
+    /// échoue, le journal est ramené à `end` : aucun enregistrement partiel
+    /// ne reste à la suite. Un disque plein donne `DatabaseError::DiskFull`
+    /// et refuse les écritures suivantes jusqu'à une compaction réussie ou
+    /// une réouverture ; les lectures ne sont pas affectées.
+    fn write_log(
+        &self,
+        file: &mut File,
+        end: u64,
+        write: impl FnOnce(&mut File) -> io::Result<()>,
+    ) -> Result<(), DatabaseError> {
+        if self.shared.read_only {
+            return Err(DatabaseError::ReadOnly);
+        }
+        if self.shared.disk_full.load(Ordering::Relaxed) {
+            return Err(DatabaseError::DiskFull);
+        }
+        let Err(err) = write(file) else {
+            return Ok(());
+        };
+        let _ = file.set_len(end);
+        if is_disk_full(&err) {
+            self.shared.disk_full.store(true, Ordering::Relaxed);
+            return Err(DatabaseError::DiskFull);
+        }
+        Err(DatabaseError::io(
+            IoOp::LogWrite,
+            &self.config.file_path,
+            err,
+        ))
+    }
+
+    /// Écrit une entrée en fin de journal et met à jour l'index.
+    fn append(&self, entry: &DataEntry) -> Result<(), DatabaseError> {
+        let _access_guard = self.write_access()?;
+        self.append_locked(entry)
+    }
+
+    /// Comme `append`, l'appelant détenant déjà le verrou d'écriture.
+    fn append_locked(&self, entry: &DataEntry) -> Result<(), DatabaseError> {
+        self.append_encoded(entry, self.encode(entry, &[])?)
+    }
+
+    /// Encodage de `entry` avec les métadonnées `meta`, selon
+    /// `min_compression_savings` et avec le dictionnaire du journal.
+    /// L'appelant doit détenir le verrou d'écriture, qui exclut un
+    /// changement de dictionnaire avant l'ajout.
+    /// Une valeur à dédupliquer (`dedup_min_size`) est encodée en référence
+    /// à son enregistrement `Blob`, ajouté au journal s'il n'y est pas.
+    fn encode(&self, entry: &DataEntry, meta: &[u8]) -> Result<Vec<u8>, DatabaseError> {
+        let dictionary = self.lock_dictionary()?.clone();
+        let blob = match self.find_blob(entry)? {
+            BlobLookup::Shared(offset) => offset,
+            BlobLookup::Missing(hash) => {
+                let mut file = self.lock_file()?;
+                self.append_blob(&mut file, hash, &entry.value, dictionary.as_deref())?
+                    .offset
+            }
+            BlobLookup::Inline => return Ok(self.encode_with(entry, meta, dictionary.as_deref())),
+        };
+        let mut bytes = Vec::new();
+        codec::encode_reference_into(&entry.key, meta, blob, &mut bytes);
+        Ok(bytes)
+    }
+
+    /// Enregistrement `Blob` à référencer pour stocker la valeur de `entry` :
+    /// `Inline` si elle n'est pas à dédupliquer ou si l'enregistrement de
+    /// même empreinte porte une autre valeur. L'appelant doit détenir le
+    /// verrou d'écriture.
+    fn find_blob(&self, entry: &DataEntry) -> Result<BlobLookup, DatabaseError> {
+        let shared = entry.entry_type == EntryType::Data
+            && self
+                .config
+                .dedup_min_size
+                .is_some_and(|min| entry.value.len() >= min);
+        if !shared {
+            return Ok(BlobLookup::Inline);
+        }
+        let hash = codec::content_hash(&entry.value);
+        let Some(blob) = self.lock_blobs()?.get(&hash).copied() else {
+            return Ok(BlobLookup::Missing(hash));
+        };
+        let mut reader = self.log_reader()?;
+        let raw = reader.read_blob(blob.offset)?;
+        let stored = codec::verify_record(&raw)
+            .and_then(|record| reader.decode_value(&record))
+            .map_err(|err| err.at_record(&self.config.file_path, blob.offset))?;
+        match stored == entry.value {
+            true => Ok(BlobLookup::Shared(blob.offset)),
+            false => Ok(BlobLookup::Inline),
+        }
+    }
+
+    /// Ajoute en fin de journal l'enregistrement `Blob` d'empreinte `hash`
+    /// portant `value` et retourne sa position. L'appelant doit détenir le
+    /// verrou d'écriture.
+    fn append_blob(
+        &self,
+        file: &mut File,
+        hash: u64,
+        value: &[u8],
+        dictionary: Option<&Dictionary>,
+    ) -> Result<IndexEntry, DatabaseError> {
+        let entry = DataEntry {
+            entry_type: EntryType::Blob,
+            key: hash.to_be_bytes().to_vec(),
+            value: value.to_vec(),
+        };
+        let bytes = self.encode_with(&entry, &[], dictionary);
+        if self.config.enforce_max_size
+            && self.config.max_size > 0
+            && self.stored_bytes() + bytes.len() as u64 > self.config.max_size
+        {
+            return Err(DatabaseError::DatabaseFull);
+        }
+        let offset = file
+            .seek(SeekFrom::End(0))
+            .context(IoOp::LogWrite, &self.config.file_path)?;
+        self.write_log(file, offset, |file| {
+            file.write_all(&bytes)?;
+            file.flush()
+        })?;
+        let location = IndexEntry {
+            offset,
+            size: bytes.len() as u32,
+            tombstone: false,
+            generation: self.generation(),
+        };
+        self.track_blob(&entry.key, location)?;
+        let _ = self.shared.indexed_end.compare_exchange(
+            offset,
+            offset + bytes.len() as u64,
+            Ordering::Relaxed,
+            Ordering::Relaxed,
+        );
+        Ok(location)
+    }
+
+    /// Comme `encode`, avec `dictionary`.
+    fn encode_with(
+        &self,
+        entry: &DataEntry,
+        meta: &[u8],
+        dictionary: Option<&Dictionary>,
+    ) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let min_savings = self.config.min_compression_savings;
+        codec::encode_record_into(entry, meta, min_savings, dictionary, &mut bytes);
+        bytes
+    }
+
+    /// Comme `append_locked`, `bytes` étant l'encodage de `entry`.
+    fn append_encoded(&self, entry: &DataEntry, bytes: Vec<u8>) -> Result<(), DatabaseError> {
+        let size = bytes.len() as u32;
+        let tombstone = matches!(entry.entry_type, EntryType::Tombstone);
+
+        {
+            if !tombstone {
+                self.check_capacity(&entry.key, size)?;
+            }
+
+            let offset = {
+                let mut file = self.lock_file()?;
+                let offset = file
+                    .seek(SeekFrom::End(0))
+                    .context(IoOp::LogWrite, &self.config.file_path)?;
+                failpoints::hit("set.before_write")
+                    .context(IoOp::LogWrite, &self.config.file_path)?;
+                self.write_log(&mut file, offset, |file| {
+                    failpoints::write_all("set.write", file, &bytes)?;
+                    file.flush()
+                })?;
+                offset
+            };
+            failpoints::hit("set.after_write").context(IoOp::LogWrite, &self.config.file_path)?;
+
+            let location = IndexEntry {
+                offset,
+                size,
+                tombstone,
+                generation: self.generation(),
+            };
+            self.count_compression(entry, &bytes);
+            self.track_record(&entry.key, location)?;
+            // Des écritures externes (ex. `append_entry`) intercalées avant
+            // celle-ci restent à rattraper par `refresh`.
+            let _ = self.shared.indexed_end.compare_exchange(
+                offset,
+                offset + size as u64,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            );
+            self.sync_text_entry(entry, &location)?;
+        }
+
+        self.shared
+            .last_write
+            .store(self.config.clock.now_millis(), Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Compte la valeur de `entry`, écrite encodée dans `encoded`, dans les
+    /// statistiques de compression.
+    fn count_compression(&self, entry: &DataEntry, encoded: &[u8]) {
+        if entry.entry_type != EntryType::Tombstone {
+            self.shared.compression.record(
+                entry.value.len(),
+                codec::stored_value_len(encoded),
+                encoded[0] & codec::RAW_FLAG != 0,
+            );
+        }
+    }
+
+    /// Enregistre dans l'index l'enregistrement situé en `location` et met à
+    /// jour les compteurs dérivés. L'appelant doit détenir le verrou d'écriture.
+    fn track_record(&self, key: &[u8], location: IndexEntry) -> Result<(), DatabaseError> {
+        let mut index = self.index_write()?;
+        let previous = index.insert(key.to_vec(), location);
+        if let Some(previous) = previous.filter(|previous| !previous.tombstone) {
+            self.shared
+                .live_bytes
+                .fetch_sub(previous.size as u64, Ordering::Relaxed);
+        }
+        if !location.tombstone {
+            self.shared
+                .live_bytes
+                .fetch_add(location.size as u64, Ordering::Relaxed);
+        }
+
+        self.shared.applied_writes.fetch_add(1, Ordering::Release);
+
+        if self.config.eviction != Eviction::None {
+            let mut recency = self
+                .shared
+                .recency
+                .lock()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))?;
+            if location.tombstone {
+                recency.remove(key);
+            } else {
+                recency.touch(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Enregistre l'enregistrement `Blob` de clé `key` situé en `location`.
+    /// L'appelant doit détenir le verrou d'écriture.
+    fn track_blob(&self, key: &[u8], location: IndexEntry) -> Result<(), DatabaseError> {
+        if let Some(hash) = blob_hash(key) {
+            self.lock_blobs()?.insert(hash, location);
+            self.shared
+                .blob_bytes
+                .fetch_add(location.size as u64, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Reconstruit l'index en relisant tout le journal, pour se remettre d'un
+    /// `DatabaseError::IndexMismatch` sans rouvrir la base. Retourne le
+    /// nombre de clés vivantes.
+    pub fn reindex(&self) -> Result<usize, DatabaseError> {
+        let _access_guard = self.write_access()?;
+        self.reindex_locked()
+    }
+
+    /// Comme `reindex`, l'appelant détenant déjà le verrou d'écriture.
+    pub(crate) fn reindex_locked(&self) -> Result<usize, DatabaseError> {
+        let replay = Self::recover_log(&self.config.file_path, self.config.max_record_size)?;
+        let (mut index, mut blobs, end) = (replay.index, replay.blobs, replay.end);
+        let generation = self.generation();
+        for entry in index.values_mut().chain(blobs.values_mut()) {
+            entry.generation = generation;
+        }
+        let live_keys = index.values().filter(|entry| !entry.tombstone).count();
+
+        self.shared
+            .live_bytes
+            .store(live_size(&index), Ordering::Relaxed);
+        self.shared
+            .blob_bytes
+            .store(live_size(&blobs), Ordering::Relaxed);
+        *self.lock_blobs()? = blobs;
+        self.shared.indexed_end.store(end, Ordering::Relaxed);
+        *self
+            .shared
+            .recency
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))? =
+            Self::initial_recency(&self.config, &index);
+        *self.index_write()? = index;
+
+        let mut text = self
+            .shared
+            .text
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))?;
+        if let Some(config) = text.as_ref().map(|text| text.config()) {
+            *text = Some(self.build_text_index(config)?);
+        }
+        Ok(live_keys)
+    }
+
+    /// Intègre à l'index les enregistrements ajoutés au journal depuis la
+    /// dernière position connue, par un autre processus ou par `append_entry`.
+    /// Retourne le nombre d'enregistrements intégrés. Sur une base
+    /// `open_follower` dont l'écrivain a remplacé le journal, l'index est
+    /// reconstruit et le nombre de clés vivantes est retourné.
+    pub fn refresh(&self) -> Result<usize, DatabaseError> {
+        let _access_guard = self.write_access()?;
+
+        let start = self.shared.indexed_end.load(Ordering::Relaxed);
+        if self.shared.read_only && self.log_replaced(start)? {
+            return self.follow_replaced_log();
+        }
+        if self.file_size()? < start {
+            // Le journal a été réécrit (compaction d'un autre processus).
+            return Err(DatabaseError::InvalidFormat);
+        }
+
+        let mut end = start;
+        let mut count = 0;
+        let mut keys = HashSet::new();
+        for record in self.log_iter_from(start)? {
+            let record = record?;
+            if !record.checksum_ok {
+                self.shared
+                    .corruption_detected
+                    .store(true, Ordering::Relaxed);
+                return Err(DatabaseError::CorruptedRecord {
+                    path: self.config.file_path.clone(),
+                    offset: record.offset,
+                });
+            }
+            let location = IndexEntry {
+                offset: record.offset,
+                size: record.size,
+                tombstone: matches!(record.entry_type, EntryType::Tombstone),
+                generation: self.generation(),
+            };
+            end = record.offset + record.size as u64;
+            if record.entry_type == EntryType::Blob {
+                self.track_blob(&record.key, location)?;
+                continue;
+            }
+            self.track_record(&record.key, location)?;
+            count += 1;
+            keys.insert(record.key);
+        }
+        self.shared.indexed_end.store(end, Ordering::Relaxed);
+
+        self.refresh_text_keys(keys)?;
+        Ok(count)
+    }
+
+    /// Applique la politique `eviction` en supprimant les clés les moins
+    /// récemment utilisées, sans jamais évincer `protected`.
+    fn evict(&self, protected: &[u8]) -> Result<(), DatabaseError> {
+        let Eviction::Lru {
+            max_keys,
+            max_bytes,
+        } = self.config.eviction
+        else {
+            return Ok(());
+        };
+
+        loop {
+            let victim = {
+                let recency = self
+                    .shared
+                    .recency
+                    .lock()
+                    .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))?;
+                let live_bytes = self.shared.live_bytes.load(Ordering::Relaxed);
+                let over_keys = max_keys.is_some_and(|max| recency.len() > max);
+                let over_bytes = max_bytes.is_some_and(|max| live_bytes > max);
+                if !over_keys && !over_bytes {
+                    return Ok(());
+                }
+                match recency.oldest() {
+                    Some(key) if key.as_slice() != protected => key.clone(),
+                    _ => return Ok(()),
+                }
+            };
+
+            self.append(&DataEntry {
+                entry_type: EntryType::Tombstone,
+                key: victim,
+                value: Vec::new(),
+            })?;
+        }
+    }
+
+    /// Ordre de récence initial : ordre d'écriture dans le journal.
+    pub(crate) fn initial_recency(
+        config: &DatabaseConfig,
+        index: &HashMap<Vec<u8>, IndexEntry>,
+    ) -> LruTracker {
+        let mut recency = LruTracker::default();
+        if config.eviction == Eviction::None {
+            return recency;
+        }
+
+        let mut live: Vec<(&Vec<u8>, &IndexEntry)> =
+            index.iter().filter(|(_, entry)| !entry.tombstone).collect();
+        live.sort_by_key(|(_, entry)| entry.offset);
+        for (key, _) in live {
+            recency.touch(key);
+        }
+        recency
+    }
+
+    /// Vérifie le plafond `max_size` sur les données vivantes quand
+    /// `enforce_max_size` est actif. L'appelant doit détenir le verrou d'écriture.
+    fn check_capacity(&self, key: &[u8], size: u32) -> Result<(), DatabaseError> {
+        if !self.config.enforce_max_size || self.config.max_size == 0 {
+            return Ok(());
+        }
+
+        let replaced = self
+            .index_read()?
+            .get(key)
+            .filter(|entry| !entry.tombstone)
+            .map_or(0, |entry| entry.size as u64);
+        if self.stored_bytes() - replaced + size as u64 > self.config.max_size {
+            return Err(DatabaseError::DatabaseFull);
+        }
+        Ok(())
+    }
+
+    /// Taille des entrées vivantes et des valeurs partagées.
+    fn stored_bytes(&self) -> u64 {
+        self.shared.live_bytes.load(Ordering::Relaxed)
+            + self.shared.blob_bytes.load(Ordering::Relaxed)
+    }
+
+    fn decode_buffer(
+        reader: &mut LogReader,
+        buffer: &[u8],
+        key: &[u8],
+        offset: u64,
+    ) -> Result<Option<(EntryType, Vec<u8>)>, DatabaseError> {
+        let record = codec::verify_record(buffer)?;
+        if record.key != key {
+            return Err(index_mismatch(key, offset));
+        }
+        match record.entry_type {
+            EntryType::Tombstone => Ok(None),
+            EntryType::Dictionary | EntryType::Blob => Err(DatabaseError::InvalidFormat),
+            entry_type => Ok(Some((entry_type, reader.decode_value(&record)?))),
+        }
+    }
+
+    /// Métadonnées de l'enregistrement désigné par `entry`.
+    fn read_meta(reader: &mut LogReader, entry: &IndexEntry) -> Result<Vec<u8>, DatabaseError> {
+        let raw = reader.read_raw(entry)?;
+        codec::verify_record(&raw)
+            .map(|record| record.meta.to_vec())
+            .map_err(|err| err.at_record(&reader.path, entry.offset))
+    }
+
+    fn read_entry(
+        reader: &mut LogReader,
+        entry: &IndexEntry,
+        key: &[u8],
+    ) -> Result<Option<(EntryType, Vec<u8>)>, DatabaseError> {
+        let buffer = reader.read_raw(entry)?;
+        Self::decode_buffer(reader, &buffer, key, entry.offset)
+            .map_err(|err| err.at_record(&reader.path, entry.offset))
+    }
+
+    /// Reconstitue la valeur d'une clé en remontant sa chaîne d'opérandes de
+    /// fusion. Retourne aussi le nombre d'opérandes parcourues.
+    pub(crate) fn resolve(
+        reader: &mut LogReader,
+        entry: &IndexEntry,
+        key: &[u8],
+    ) -> Result<(Option<Value>, usize), DatabaseError> {
+        let mut ops = Vec::new();
+        let mut next = Some((entry.offset, entry.size));
+        let mut value = None;
+
+        while let Some((offset, size)) = next.take() {
+            if ops.len() > MAX_MERGE_DEPTH * 2 {
+                return Err(DatabaseError::CorruptedData);
+            }
+            let entry = IndexEntry {
+                offset,
+                size,
+                tombstone: false,
+                generation: entry.generation,
+            };
+            match Self::read_entry(reader, &entry, key)? {
+                None => {}
+                Some((EntryType::Merge, bytes)) => {
+                    let record = MergeRecord::from_bytes(&bytes)
+                        .map_err(|err| err.at_record(&reader.path, offset))?;
+                    next = record.prev;
+                    ops.push(record.op);
+                }
+                Some((_, bytes)) => value = Some(Value::Bytes(bytes)),
+            }
+        }
+
+        let depth = ops.len();
+        for op in ops.into_iter().rev() {
+            value = merge::apply(value, op)?;
+        }
+        Ok((value, depth))
+    }
+
+    /// Lit toutes les valeurs simples vivantes dans l'ordre du journal
+    /// (les collections sont ignorées). L'appelant doit détenir le verrou `access`.
+    #[cfg(feature = "sqlite")]
+    pub(crate) fn read_live_entries(&self) -> Result<Vec<KeyValue>, DatabaseError> {
+        let index_snapshot = {
+            let index = self.index_read()?;
+            let mut snapshot: Vec<(Vec<u8>, IndexEntry)> =
+                index.iter().map(|(k, entry)| (k.clone(), *entry)).collect();
+            snapshot.sort_by_key(|(_, entry)| entry.offset);
+            snapshot
+        };
+
+        let mut reader = self.log_reader()?.with_read_ahead(self.config.read_ahead);
+        let mut entries = Vec::new();
+        for (key, entry) in index_snapshot {
+            if let (Some(Value::Bytes(value)), _) = Self::resolve(&mut reader, &entry, &key)? {
+                entries.push((key, value));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Retourne les valeurs simples vivantes dont la clé commence par
+    /// `prefix`, triées par clé (les collections sont ignorées).
+    pub fn scan(&self, prefix: &[u8]) -> Result<Vec<KeyValue>, DatabaseError> {
+        let deadline = self.operation_deadline();
+        let mut entries = Vec::new();
+        for entry in self.scan_iter(prefix)? {
+            check_deadline(deadline)?;
+            entries.push(entry?);
+        }
+        Ok(entries)
+    }
+
+    /// Relit dans l'ordre du journal les enregistrements des clés visées,
+    /// pour les amener dans le cache de pages du système (et en tête de
+    /// l'ordre LRU si l'éviction est active) après un redémarrage. Retourne
+    /// le nombre de clés préchargées.
+    pub fn warmup(&self, target: WarmupTarget<'_>) -> Result<usize, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let mut selected: Vec<(Vec<u8>, IndexEntry)> = {
+            let index = self.index_read()?;
+            let live = |entry: &&IndexEntry| !entry.tombstone;
+            match target {
+                WarmupTarget::Keys(keys) => keys
+                    .iter()
+                    .filter_map(|key| {
+                        index
+                            .get(key)
+                            .filter(live)
+                            .map(|entry| (key.clone(), *entry))
+                    })
+                    .collect(),
+                WarmupTarget::Prefix(prefix) => index
+                    .iter()
+                    .filter(|(key, entry)| !entry.tombstone && key.starts_with(prefix))
+                    .map(|(key, entry)| (key.clone(), *entry))
+                    .collect(),
+            }
+        };
+        selected.sort_by_key(|(_, entry)| entry.offset);
+
+        let deadline = self.operation_deadline();
+        let mut reader = self.log_reader()?.with_read_ahead(self.config.read_ahead);
+        for (key, entry) in &selected {
+            check_deadline(deadline)?;
+            Self::resolve(&mut reader, entry, key).inspect_err(|err| self.note_corruption(err))?;
+        }
+        if self.config.eviction != Eviction::None {
+            let mut recency = self
+                .shared
+                .recency
+                .lock()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Recency))?;
+            for (key, _) in &selected {
+                recency.touch(key);
+            }
+        }
+        Ok(selected.len())
+    }
+
+    /// Compacte le journal pour ne garder que les entrées valides.
+    ///
+    /// La copie des entrées vivantes se fait sans bloquer les lecteurs ni les
+    /// rédacteurs ; seul le rattrapage des écritures concurrentes et le
+    /// remplacement du fichier ont lieu sous le verrou d'écriture.
+    pub fn compact(&self) -> Result<(), DatabaseError> {
+        self.compact_observed(&mut |_| {}, &AtomicBool::new(false))
+    }
+
+    /// Comme `compact`, en écrivant le journal compacté dans `dir`.
+    /// Si `dir` est sur un autre système de fichiers, le journal compacté
+    /// est ensuite recopié à côté de l'ancien, puis renommé à sa place :
+    /// le volume de la base doit alors accueillir les données vivantes en
+    /// plus de l'ancien journal, comme pour `compact`. Seuls la copie, son
+    /// rattrapage et la préallocation sont reportés sur `dir` ; cela ne
+    /// libère aucune place sur un volume presque plein.
+    ///
+    /// Avec `preallocate`, l'espace estimé des données vivantes est réservé
+    /// dans `dir` avant la copie, pour échouer d'emblée s'il manque.
+    pub fn compact_into(
+        &self,
+        dir: impl AsRef<Path>,
+        preallocate: bool,
+    ) -> Result<(), DatabaseError> {
+        let target = CompactionTarget {
+            dir: dir.as_ref(),
+            preallocate,
+        };
+        self.compact_to(&target, &mut |_| {}, &AtomicBool::new(false))
+    }
+
+    /// Comme `compact`, en rapportant l'avancement à `on_progress` et en
+    /// abandonnant avec `DatabaseError::CompactionCancelled` dès que `cancel`
+    /// passe à vrai pendant la copie.
+    pub(crate) fn compact_observed(
+        &self,
+        on_progress: &mut dyn FnMut(&CompactionProgress),
+        cancel: &AtomicBool,
+    ) -> Result<(), DatabaseError> {
+        let target = CompactionTarget {
+            dir: parent_dir(&self.config.file_path),
+            preallocate: false,
+        };
+        self.compact_to(&target, on_progress, cancel)
+    }
+
+    fn compact_to(
+        &self,
+        target: &CompactionTarget,
+        on_progress: &mut dyn FnMut(&CompactionProgress),
+        cancel: &AtomicBool,
+    ) -> Result<(), DatabaseError> {
+        if self.shared.read_only {
+            return Err(DatabaseError::ReadOnly);
+        }
+        let temp_path = compaction_temp_path(
+            &self.config.file_path,
+            target.dir,
+            self.config.clock.now_millis(),
+        );
+        let deadline = self.operation_deadline();
+        let result = self.compact_log(&temp_path, target, deadline, on_progress, cancel);
+        if result.is_err() {
+            // Absent si l'échec suit le remplacement du journal.
+            let _ = std::fs::remove_file(&temp_path);
+        }
+        self.record_compaction(&result);
+        result.map(|_| ())
+    }
+
+    /// Encodage à recopier pour la clé `key` lors d'une compaction, `None` si
+    /// elle n'a plus de valeur. Avec `raw_copy`, un enregistrement `Data` est
+    /// repris tel quel après vérification de son checksum, s'il est compressé
+    /// avec `dictionary`, celui du fichier de destination (ou sans
+    /// dictionnaire si elle n'en a pas). Les métadonnées de l'enregistrement
+    /// le plus récent sont conservées. Les valeurs réencodées le sont selon
+    /// `min_savings` (`min_compression_savings`) et avec `dictionary`. La
+    /// valeur n'est retournée que si `with_value` (index plein texte). Une
+    /// référence (`BLOB_FLAG`) est reprise telle quelle, à faire pointer
+    /// vers la copie de son enregistrement `Blob` (`CopiedRecord::blob`).
+    pub(crate) fn compaction_copy(
+        reader: &mut LogReader,
+        entry: &IndexEntry,
+        key: &[u8],
+        raw_copy: bool,
+        min_savings: Option<u8>,
+        dictionary: Option<&Dictionary>,
+        with_value: bool,
+    ) -> Result<Option<CopiedRecord>, DatabaseError> {
+        let raw = reader.read_raw(entry)?;
+        let record =
+            codec::verify_record(&raw).map_err(|err| err.at_record(&reader.path, entry.offset))?;
+        if let Some(blob) = record.blob.filter(|_| record.key == key) {
+            let value = match with_value {
+                true => Some(Value::Bytes(
+                    reader
+                        .decode_value(&record)
+                        .map_err(|err| err.at_record(&reader.path, entry.offset))?,
+                )),
+                false => None,
+            };
+            return Ok(Some(CopiedRecord {
+                bytes: raw,
+                value,
+                blob: Some(blob),
+            }));
+        }
+        if record.entry_type == EntryType::Data && record.key == key {
+            let raw_copy = raw_copy && record.dictionary == dictionary.map(|d| d.id);
+            if raw_copy && !with_value {
+                return Ok(Some(CopiedRecord {
+                    bytes: raw,
+                    value: None,
+                    blob: None,
+                }));
+            }
+            let value = reader
+                .decode_value(&record)
+                .map_err(|err| err.at_record(&reader.path, entry.offset))?;
+            let meta = record.meta.to_vec();
+            let entry = DataEntry {
+                entry_type: EntryType::Data,
+                key: key.to_vec(),
+                value,
+            };
+            let bytes = match raw_copy {
+                true => raw,
+                false => {
+                    let mut bytes = Vec::new();
+                    codec::encode_record_into(&entry, &meta, min_savings, dictionary, &mut bytes);
+                    bytes
+                }
+            };
+            return Ok(Some(CopiedRecord {
+                bytes,
+                value: with_value.then_some(Value::Bytes(entry.value)),
+                blob: None,
+            }));
+        }
+
+        let meta = record.meta.to_vec();
+        let (Some(value), _) = Self::resolve(reader, entry, key)? else {
+            return Ok(None);
+        };
+        let value_copy = with_value.then(|| value.clone());
+        let mut bytes = Vec::new();
+        codec::encode_record_into(
+            &base_entry(key.to_vec(), value),
+            &meta,
+            min_savings,
+            dictionary,
+            &mut bytes,
+        );
+        Ok(Some(CopiedRecord {
+            value: value_copy,
+            bytes,
+            blob: None,
+        }))
+    }
+
+    /// `compaction_copy` de chaque entrée vivante de `batch`, réparti en
+    /// tranches contiguës sur les lecteurs de `readers` (un fil chacun). Les
+    /// résultats suivent l'ordre de `batch`.
+    fn copy_batch(
+        batch: &[(Vec<u8>, IndexEntry)],
+        readers: &mut [LogReader],
+        raw_copy: bool,
+        min_savings: Option<u8>,
+        dictionary: Option<&Dictionary>,
+        with_value: bool,
+    ) -> Vec<Result<Option<CopiedRecord>, DatabaseError>> {
+        let copy = |reader: &mut LogReader, (key, entry): &(Vec<u8>, IndexEntry)| {
+            if entry.tombstone {
+                return Ok(None);
+            }
+            Self::compaction_copy(
+                reader,
+                entry,
+                key,
+                raw_copy,
+                min_savings,
+                dictionary,
+                with_value,
+            )
+        };
+        if readers.len() == 1 || batch.len() < 2 {
+            return batch
+                .iter()
+                .map(|item| copy(&mut readers[0], item))
+                .collect();
+        }
+
+        let slice_len = batch.len().div_ceil(readers.len());
+        thread::scope(|scope| {
+            let workers: Vec<_> = batch
+                .chunks(slice_len)
+                .zip(readers.iter_mut())
+                .map(|(slice, reader)| {
+                    scope.spawn(move || {
+                        slice
+                            .iter()
+                            .map(|item| copy(reader, item))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect()
+        })
+    }
+
+    fn compact_log(
+        &self,
+        temp_path: &Path,
+        target: &CompactionTarget,
+        deadline: Option<Instant>,
+        on_progress: &mut dyn FnMut(&CompactionProgress),
+        cancel: &AtomicBool,
+    ) -> Result<usize, DatabaseError> {
+        let _compaction_guard = self.lock_compaction()?;
+        let generation = self.generation() + 1;
+
+        let (index_snapshot, snapshot_end) = {
+            let _access_guard = self.read_access()?;
+            let index = self.index_read()?;
+            let mut snapshot: Vec<(Vec<u8>, IndexEntry)> =
+                index.iter().map(|(k, entry)| (k.clone(), *entry)).collect();
+            snapshot.sort_by_key(|(_, entry)| entry.offset);
+            (snapshot, self.file_size()?)
+        };
+        let mut progress = CompactionProgress {
+            records_total: index_snapshot.len(),
+            ..CompactionProgress::default()
+        };
+        on_progress(&progress);
+        let dictionary = match self.config.compression_dictionary {
+            true => self.train_dictionary(&index_snapshot)?.map(Arc::new),
+            false => None,
+        };
+
+        // L'index plein texte est reconstruit à partir des valeurs recopiées.
+        let mut new_text = self
+            .shared
+            .text
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))?
+            .as_ref()
+            .map(|text| TextIndex::new(text.config()));
+        let mut new_index = HashMap::new();
+        let mut quarantine = Quarantine::new(&self.config.file_path);
+        let mut temp_file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(temp_path)
+            .context(IoOp::CompactionCreate, temp_path)?;
+        if target.preallocate {
+            preallocate_file(&mut temp_file, self.stored_bytes())
+                .context(IoOp::CompactionPreallocate, temp_path)?;
+        }
+        if let Some(dictionary) = &dictionary {
+            let record = dictionary.to_record();
+            temp_file
+                .write_all(&record)
+                .context(IoOp::CompactionCopy, temp_path)?;
+            progress.bytes_written += record.len() as u64;
+        }
+        let mut blobs = BlobCopy::new(
+            self.config.compaction_raw_copy,
+            self.config.min_compression_savings,
+            dictionary.clone(),
+            generation,
+        );
+        {
+            let _throttle = self.shared.throttle.begin();
+            let mut limiter =
+                RateLimiter::new(self.config.compaction_rate_limit, &self.shared.throttle);
+            let threads = self.config.compaction_threads.max(1);
+            let mut readers = (0..threads)
+                .map(|_| {
+                    self.log_reader()
+                        .map(|reader| reader.with_read_ahead(self.config.read_ahead))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let with_value = new_text.is_some();
+            let mut prefix = Vec::new();
+            let mut remaining = index_snapshot.into_iter();
+            loop {
+                let batch: Vec<_> = remaining
+                    .by_ref()
+                    .take(COMPACTION_BATCH_PER_THREAD * threads)
+                    .collect();
+                if batch.is_empty() {
+                    break;
+                }
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(DatabaseError::CompactionCancelled);
+                }
+                check_deadline(deadline)?;
+                let copies = Self::copy_batch(
+                    &batch,
+                    &mut readers,
+                    self.config.compaction_raw_copy,
+                    self.config.min_compression_savings,
+                    dictionary.as_deref(),
+                    with_value,
+                );
+                for ((key, entry), copied) in batch.into_iter().zip(copies) {
+                    if cancel.load(Ordering::Relaxed) {
+                        return Err(DatabaseError::CompactionCancelled);
+                    }
+                    check_deadline(deadline)?;
+                    progress.records_processed += 1;
+                    // L'enregistrement `Blob` d'une référence la précède.
+                    let position = temp_file
+                        .stream_position()
+                        .context(IoOp::CompactionCopy, temp_path)?;
+                    prefix.clear();
+                    let copied = copied.and_then(|copied| {
+                        copied
+                            .map(|mut copied| {
+                                blobs
+                                    .place(&mut readers[0], &mut copied, position, &mut prefix)
+                                    .map(|_| copied)
+                            })
+                            .transpose()
+                    });
+                    let copied = match copied {
+                        Ok(Some(copied)) => copied,
+                        Ok(None) => continue,
+                        Err(err) if self.config.compaction_quarantine && err.is_corruption() => {
+                            let raw = readers[0].read_raw(&entry)?;
+                            let now = self.config.clock.now_millis();
+                            quarantine.add(&key, &raw, entry.offset, &err, now)?;
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    };
+                    if let (Some(text), Some(value)) = (new_text.as_mut(), &copied.value) {
+                        text.update(&key, Some(value));
+                    }
+                    let bytes = copied.bytes;
+                    temp_file
+                        .write_all(&prefix)
+                        .context(IoOp::CompactionCopy, temp_path)?;
+                    let offset = position + prefix.len() as u64;
+                    failpoints::write_all("compact.copy", &mut temp_file, &bytes)
+                        .context(IoOp::CompactionCopy, temp_path)?;
+                    new_index.insert(
+                        key,
+                        IndexEntry {
+                            offset,
+                            size: bytes.len() as u32,
+                            tombstone: false,
+                            generation,
+                        },
+                    );
+                    limiter.consume(prefix.len() + bytes.len());
+                    progress.bytes_written += (prefix.len() + bytes.len()) as u64;
+                    on_progress(&progress);
+                }
+            }
+        }
+
+        let _access_guard = self.write_access()?;
+
+        // Rattrapage : recopie brute des enregistrements ajoutés pendant la copie.
+        let tail_start = temp_file
+            .stream_position()
+            .context(IoOp::CompactionCatchUp, temp_path)?;
+        let mut tail = Vec::new();
+        {
+            let mut reader = File::open(&self.config.file_path)
+                .context(IoOp::CompactionCatchUp, &self.config.file_path)?;
+            reader
+                .seek(SeekFrom::Start(snapshot_end))
+                .context(IoOp::CompactionCatchUp, &self.config.file_path)?;
+            reader
+                .read_to_end(&mut tail)
+                .context(IoOp::CompactionCatchUp, &self.config.file_path)?;
+        }
+        // Les enregistrements compressés avec l'ancien dictionnaire sont
+        // réencodés avec le nouveau ; les références pointent vers la copie
+        // de leur enregistrement `Blob`, recopié s'il ne l'est pas déjà.
+        let mut copied_tail = Vec::new();
+        let mut old_reader = self.log_reader()?;
+        let mut merge_tails = HashMap::new();
+        for record in self.log_iter_from(snapshot_end)? {
+            let record = record?;
+            if !record.checksum_ok {
+                return Err(DatabaseError::CorruptedRecord {
+                    path: self.config.file_path.clone(),
+                    offset: record.offset,
+                });
+            }
+            let start = (record.offset - snapshot_end) as usize;
+            let raw = &tail[start..start + record.size as usize];
+            if record.entry_type == EntryType::Blob {
+                blobs.locate(&mut old_reader, record.offset, tail_start, &mut copied_tail)?;
+                continue;
+            }
+            let stored = codec::verify_record(raw);
+            let moved = match stored.as_ref().map(|stored| stored.blob) {
+                Ok(Some(blob)) => {
+                    Some(blobs.locate(&mut old_reader, blob, tail_start, &mut copied_tail)?)
+                }
+                _ => None,
+            };
+            let offset = tail_start + copied_tail.len() as u64;
+            match stored {
+                Ok(stored)
+                    if stored.dictionary.is_some()
+                        && stored.dictionary != dictionary.as_ref().map(|d| d.id) =>
+                {
+                    let entry = DataEntry {
+                        entry_type: stored.entry_type,
+                        key: stored.key.to_vec(),
+                        value: old_reader
+                            .decode_value(&stored)
+                            .map_err(|err| err.at_record(&self.config.file_path, record.offset))?,
+                    };
+                    let bytes = self.encode_with(&entry, stored.meta, dictionary.as_deref());
+                    copied_tail.extend_from_slice(&bytes);
+                }
+                _ => copied_tail.extend_from_slice(raw),
+            }
+            if let Some(moved) = moved {
+                let copied = &mut copied_tail[(offset - tail_start) as usize..];
+                codec::relocate_reference(copied, moved);
+            }
+            merge_tails.insert(
+                record.key.clone(),
+                matches!(record.entry_type, EntryType::Merge),
+            );
+            new_index.insert(
+                record.key,
+                IndexEntry {
+                    offset,
+                    size: (tail_start + copied_tail.len() as u64 - offset) as u32,
+                    tombstone: matches!(record.entry_type, EntryType::Tombstone),
+                    generation,
+                },
+            );
+        }
+        temp_file
+            .write_all(&copied_tail)
+            .context(IoOp::CompactionCatchUp, temp_path)?;
+
+        // Les opérandes recopiées pointent vers l'ancien fichier : on réécrit
+        // la valeur matérialisée des clés concernées.
+        {
+            let index = self.index_read()?;
+            let mut reader = self.log_reader()?;
+            for (key, is_merge) in merge_tails {
+                if !is_merge && new_text.is_none() {
+                    continue;
+                }
+                let live = index.get(&key).filter(|entry| !entry.tombstone);
+                let value = match live {
+                    Some(entry) => Self::resolve(&mut reader, entry, &key)?.0,
+                    None => None,
+                };
+                if let Some(text) = new_text.as_mut() {
+                    text.update(&key, value.as_ref());
+                }
+                let (Some(value), Some(live)) = (value.filter(|_| is_merge), live) else {
+                    continue;
+                };
+                let meta = Self::read_meta(&mut reader, live)?;
+                let entry = base_entry(key, value);
+                let bytes = self.encode_with(&entry, &meta, dictionary.as_deref());
+                let offset = temp_file
+                    .stream_position()
+                    .context(IoOp::CompactionCopy, temp_path)?;
+                temp_file
+                    .write_all(&bytes)
+                    .context(IoOp::CompactionCopy, temp_path)?;
+                new_index.insert(
+                    entry.key,
+                    IndexEntry {
+                        offset,
+                        size: bytes.len() as u32,
+                        tombstone: false,
+                        generation,
+                    },
+                );
+            }
+        }
+        // Retire la part préallouée non utilisée.
+        let written = temp_file
+            .stream_position()
+            .context(IoOp::CompactionCopy, temp_path)?;
+        temp_file
+            .set_len(written)
+            .context(IoOp::CompactionCopy, temp_path)?;
+        temp_file.flush().context(IoOp::CompactionCopy, temp_path)?;
+        temp_file
+            .sync_all()
+            .context(IoOp::CompactionSync, temp_path)?;
+        drop(temp_file);
+        quarantine.sync()?;
+
+        {
+            let _guard = self.lock_file()?;
+            drop(_guard);
+        }
+
+        // Hors Unix, un fichier ouvert par un itérateur ne peut pas être
+        // remplacé : la compaction est abandonnée plutôt que de le forcer.
+        if !cfg!(unix) && self.shared.live_snapshots.load(Ordering::Acquire) > 0 {
+            return Err(DatabaseError::SnapshotInUse);
+        }
+        failpoints::hit("compact.before_rename")
+            .context(IoOp::CompactionReplace, &self.config.file_path)?;
+
+        // Tout ce qui peut échouer a lieu avant le remplacement : une fois le
+        // journal remplacé, le descripteur et l'index sont installés sans
+        // autre point d'échec, faute de quoi les lectures verraient une
+        // génération périmée et les écritures iraient à l'ancien fichier.
+        let compacted = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(temp_path)
+            .context(IoOp::CompactionOpen, temp_path)?;
+        let mut file_guard = self.lock_file()?;
+        let mut index_guard = self.index_write()?;
+        let mut dictionary_guard = self.lock_dictionary()?;
+        let mut blobs_guard = self.lock_blobs()?;
+        let swap = FileSwap::begin(&self.shared);
+        *file_guard = self.replace_log(temp_path, compacted)?;
+        self.shared
+            .live_bytes
+            .store(live_size(&new_index), Ordering::Relaxed);
+        self.shared
+            .blob_bytes
+            .store(live_size(&blobs.blobs), Ordering::Relaxed);
+        *index_guard = new_index;
+        *dictionary_guard = dictionary;
+        *blobs_guard = blobs.blobs;
+        self.shared.indexed_end.store(written, Ordering::Relaxed);
+        swap.commit();
+        drop(blobs_guard);
+        drop(dictionary_guard);
+        drop(index_guard);
+        drop(file_guard);
+
+        // Sans synchronisation du répertoire, un arrêt brutal peut rétablir
+        // l'ancien nom sur certains systèmes de fichiers. Le journal étant
+        // déjà remplacé, un échec n'annule pas la compaction : `last_sync`
+        // n'est simplement pas avancé.
+        if sync_parent_dir(&self.config.file_path).is_ok() {
+            self.shared
+                .last_sync
+                .store(self.config.clock.now_millis(), Ordering::Relaxed);
+        }
+        let _ = failpoints::hit("compact.after_rename");
+
+        // L'instantané de l'index plein texte référence des positions de
+        // l'ancien journal : il est réécrit, ou supprimé si l'index est inactif.
+        match new_text {
+            Some(text) => {
+                self.persist_text_index(&text, self.file_size()?)?;
+                *self
+                    .shared
+                    .text
+                    .lock()
+                    .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))? =
+                    Some(text);
+            }
+            None => match std::fs::remove_file(self.text_snapshot_path()) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(DatabaseError::io(
+                        IoOp::CompactionTextIndexRemove,
+                        self.text_snapshot_path(),
+                        err,
+                    ));
+                }
+            },
+        }
+
+        on_progress(&progress);
+        Ok(quarantine.count())
+    }
+
+    /// Met le journal compacté `temp_path` à la place du journal et retourne
+    /// le descripteur à utiliser ensuite (`compacted`, ouvert sur
+    /// `temp_path`, sauf s'il a fallu le recopier). L'ancien journal reste en
+    /// place tant que le nouveau n'est pas complet.
+    fn replace_log(&self, temp_path: &Path, compacted: File) -> Result<File, DatabaseError> {
+        let path = &self.config.file_path;
+        match std::fs::rename(temp_path, path) {
+            Ok(_) => Ok(compacted),
+            Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+                // Recopie à côté du journal puis renommage : le journal en
+                // place reste intact jusqu'au remplacement atomique.
+                drop(compacted);
+                let local_path =
+                    compaction_temp_path(path, parent_dir(path), self.config.clock.now_millis());
+                let copied = std::fs::copy(temp_path, &local_path).and_then(|_| {
+                    let file = OpenOptions::new()
+                        .read(true)
+                        .append(true)
+                        .open(&local_path)?;
+                    file.sync_all()?;
+                    std::fs::rename(&local_path, path)?;
+                    Ok(file)
+                });
+                match copied {
+                    Ok(file) => {
+                        // Le journal est remplacé : la copie restante n'est
+                        // plus qu'un fichier temporaire.
+                        let _ = std::fs::remove_file(temp_path);
+                        Ok(file)
+                    }
+                    Err(err) => {
+                        let _ = std::fs::remove_file(&local_path);
+                        Err(DatabaseError::io(IoOp::CompactionCopyBack, path, err))
+                    }
+                }
+            }
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+                std::fs::remove_file(path).context(IoOp::CompactionReplace, path)?;
+                std::fs::rename(temp_path, path).context(IoOp::CompactionReplace, path)?;
+                Ok(compacted)
+            }
+            Err(err) => Err(DatabaseError::io(IoOp::CompactionReplace, path, err)),
+        }
+    }
+
+    /// Dictionnaire entraîné sur les valeurs simples d'au plus
+    /// `DICTIONARY_SAMPLES` entrées réparties sur `entries`, identifié par le
+    /// successeur de l'identifiant courant. Une valeur illisible est écartée
+    /// de l'échantillon ; la copie la signalera.
+    fn train_dictionary(
+        &self,
+        entries: &[(Vec<u8>, IndexEntry)],
+    ) -> Result<Option<Dictionary>, DatabaseError> {
+        let live: Vec<_> = entries
+            .iter()
+            .filter(|(_, entry)| !entry.tombstone)
+            .collect();
+        let step = live.len().div_ceil(DICTIONARY_SAMPLES).max(1);
+        let mut reader = self.log_reader()?;
+        let samples: Vec<Vec<u8>> = live
+            .into_iter()
+            .step_by(step)
+            .filter_map(
+                |(key, entry)| match Self::resolve(&mut reader, entry, key) {
+                    Ok((Some(Value::Bytes(value)), _)) => Some(value),
+                    _ => None,
+                },
+            )
+            .collect();
+        let id = self
+            .lock_dictionary()?
+            .as_ref()
+            .map_or(1, |dictionary| dictionary.id.wrapping_add(1));
+        Ok(Dictionary::train(id, &samples))
+    }
+
+    /// Retourne un instantané des statistiques de la base.
+    pub fn stats(&self) -> Result<DatabaseStats, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let index_len = self.index_read()?.len();
+
+        Ok(DatabaseStats {
+            file_size: self.file_size()?,
+            index_len,
+            compaction_throttle: ThrottleStats {
+                bytes_per_sec: self.config.compaction_rate_limit,
+                active: self.shared.throttle.is_active(),
+                total_wait: self.shared.throttle.total_wait(),
+            },
+            poison_recoveries: self.shared.poison_recoveries.load(Ordering::Relaxed),
+            lock_contention: self.shared.lock_waits.snapshot(),
+            compression: self.shared.compression.snapshot(),
+        })
+    }
+
+    /// Estime l'effet d'une compaction sans rien écrire : nombre d'entrées
+    /// vivantes à réécrire et octets récupérables.
+    pub fn compaction_estimate(&self) -> Result<CompactionEstimate, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let index = self.index_read()?;
+
+        let mut live_records = 0usize;
+        let mut live_bytes = 0u64;
+        for entry in index.values().filter(|entry| !entry.tombstone) {
+            live_records += 1;
+            live_bytes += entry.size as u64;
+        }
+        live_bytes += self.shared.blob_bytes.load(Ordering::Relaxed);
+
+        let file_size = self.file_size()?;
+        Ok(CompactionEstimate {
+            file_size,
+            live_records,
+            live_bytes,
+            reclaimable_bytes: file_size.saturating_sub(live_bytes),
+        })
+    }
+
+    /// Échéance d'une opération commencée maintenant (`operation_timeout`).
+    fn operation_deadline(&self) -> Option<Instant> {
+        self.config
+            .operation_timeout
+            .map(|timeout| Instant::now() + timeout)
+    }
+
+    pub(crate) fn file_size(&self) -> Result<u64, DatabaseError> {
+        Ok(std::fs::metadata(&self.config.file_path)
+            .context(IoOp::LogSize, &self.config.file_path)?
+            .len())
+    }
+
+    /// Compacte si le seuil `max_size` est atteint et que la politique
+    /// `compaction_schedule` l'autorise. À appeler périodiquement (ex. depuis un
+    /// thread de maintenance) pour les politiques que le chemin d'écriture ne
+    /// peut pas satisfaire, comme `Idle`. Retourne `true` si une compaction a eu lieu.
+    pub fn run_scheduled_compaction(&self) -> Result<bool, DatabaseError> {
+        if self.config.max_size == 0 || self.file_size()? < self.config.max_size {
+            return Ok(false);
+        }
+        if !self.compaction_allowed() {
+            return Ok(false);
+        }
+        self.compact_below_max_size()?;
+        Ok(true)
+    }
+
+    /// Applique la politique `backpressure` avant une écriture.
+    fn admit_write(&self) -> Result<(), DatabaseError> {
+        let (hard_limit, max_stall) = match self.config.backpressure {
+            BackpressurePolicy::Inline => return Ok(()),
+            BackpressurePolicy::Stall {
+                hard_limit,
+                max_stall,
+            } => (hard_limit, max_stall),
+            BackpressurePolicy::Reject { hard_limit } => (hard_limit, Duration::ZERO),
+        };
+
+        let started = Instant::now();
+        if self.file_size()? >= hard_limit {
+            self.spawn_compaction();
+        }
+        while self.file_size()? >= hard_limit {
+            if started.elapsed() >= max_stall {
+                return Err(DatabaseError::Backpressure);
+            }
+            thread::sleep(STALL_POLL_INTERVAL.min(max_stall.saturating_sub(started.elapsed())));
+        }
+        Ok(())
+    }
+
+    fn compaction_allowed(&self) -> bool {
+        let last_write = self.shared.last_write.load(Ordering::Relaxed);
+        self.config
+            .compaction_schedule
+            .allows(self.config.clock.now_millis(), last_write)
+    }
+
+    fn maybe_compact(&self) -> Result<(), DatabaseError> {
+        if self.config.backpressure != BackpressurePolicy::Inline {
+            if self.config.max_size > 0 && self.file_size()? >= self.config.max_size {
+                self.spawn_compaction();
+            }
+            return Ok(());
+        }
+        self.compact_below_max_size()
+    }
+
+    /// Lance une compaction dans un fil d'arrière-plan, sauf si une autre
+    /// lancée ainsi est en cours ou si `compaction_schedule` l'interdit.
+    /// Son résultat est visible dans `health` (`last_compaction`).
+    fn spawn_compaction(&self) {
+        if self.shared.read_only || !self.compaction_allowed() {
+            return;
+        }
+        if self
+            .shared
+            .background_compaction
+            .swap(true, Ordering::AcqRel)
+        {
+            return;
+        }
+        let db = self.clone();
+        thread::spawn(move || {
+            let _ = db.compact();
+            db.shared
+                .background_compaction
+                .store(false, Ordering::Release);
+        });
+    }
+
+    fn compact_below_max_size(&self) -> Result<(), DatabaseError> {
+        if self.config.max_size == 0 || !self.compaction_allowed() {
+            return Ok(());
+        }
+
+        loop {
+            let len = self.file_size()?;
+            if len < self.config.max_size {
+                break;
+            }
+            let before = len;
+            self.compact()?;
+            let after = self.file_size()?;
+            if after >= before {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reconstruit l'index depuis le journal ; retourne aussi la position de
+    /// fin du dernier enregistrement complet.
+    pub(crate) fn recover_index(
+        path: &Path,
+        max_record_size: u32,
+    ) -> Result<(HashMap<Vec<u8>, IndexEntry>, u64), DatabaseError> {
+        let replay = Self::recover_log(path, max_record_size)?;
+        Ok((replay.index, replay.end))
+    }
+
+    /// Comme `recover_index`, avec les enregistrements `Blob` du journal.
+    pub(crate) fn recover_log(path: &Path, max_record_size: u32) -> Result<Replay, DatabaseError> {
+        Self::replay_log(path, &RecoveryOptions::default(), max_record_size)
+    }
+
+    /// Relit le journal selon `options`. `end` est la fin du dernier
+    /// enregistrement conservé.
+    fn replay_log(
+        path: &Path,
+        options: &RecoveryOptions,
+        max_record_size: u32,
+    ) -> Result<Replay, DatabaseError> {
+        let mut index = HashMap::new();
+        let mut blobs = HashMap::new();
+        let mut iter = LogIter::new(path)?.with_max_record_size(max_record_size);
+        let mut damaged = false;
+        let (mut records, mut skipped) = (0, 0);
+
+        for record in &mut iter {
+            failpoints::hit("recovery.read").context(IoOp::LogReplay, path)?;
+            records += 1;
+            let record = match record {
+                Err(DatabaseError::CorruptedRecord { offset, .. })
+                    if options.mode != RecoveryMode::Strict =>
+                {
+                    return Ok(Replay {
+                        index,
+                        blobs,
+                        end: offset,
+                        damaged: true,
+                        records,
+                        skipped,
+                    });
+                }
+                record => record?,
+            };
+            if !record.checksum_ok && options.verify_checksums {
+                match options.mode {
+                    RecoveryMode::Strict => {
+                        return Err(DatabaseError::CorruptedRecord {
+                            path: path.to_path_buf(),
+                            offset: record.offset,
+                        });
+                    }
+                    RecoveryMode::TruncateTail => {
+                        return Ok(Replay {
+                            index,
+                            blobs,
+                            end: record.offset,
+                            damaged: true,
+                            records,
+                            skipped,
+                        });
+                    }
+                    RecoveryMode::SkipCorrupt => {
+                        damaged = true;
+                        skipped += 1;
+                        continue;
+                    }
+                }
+            }
+            let location = IndexEntry {
+                offset: record.offset,
+                size: record.size,
+                tombstone: matches!(record.entry_type, EntryType::Tombstone),
+                generation: 0,
+            };
+            match record.entry_type {
+                EntryType::Blob => {
+                    if let Some(hash) = blob_hash(&record.key) {
+                        blobs.insert(hash, location);
+                    }
+                }
+                _ => {
+                    index.insert(record.key, location);
+                }
+            }
+        }
+
+        Ok(Replay {
+            index,
+            blobs,
+            end: iter.reader.offset,
+            damaged,
+            records,
+            skipped,
+        })
+    }
+
+    /// Retourne un itérateur sur le journal (lecture seule).
+    pub fn log_iter(&self) -> Result<LogIter, DatabaseError> {
+        Ok(LogIter::new(&self.config.file_path)?.with_max_record_size(self.config.max_record_size))
+    }
+
+    /// Comme `log_iter`, à partir de `offset`.
+    pub(crate) fn log_iter_from(&self, offset: u64) -> Result<LogIter, DatabaseError> {
+        Ok(LogIter::from_offset(&self.config.file_path, offset)?
+            .with_max_record_size(self.config.max_record_size))
+    }
+}
+
+/// Enregistrement autonome (sans opérande antérieure) représentant `value`.
+fn base_entry(key: Vec<u8>, value: Value) -> DataEntry {
+    match value {
+        Value::Bytes(value) => DataEntry {
+            entry_type: EntryType::Data,
+            key,
+            value,
+        },
+        value => DataEntry {
+            entry_type: EntryType::Merge,
+            key,
+            value: MergeRecord {
+                prev: None,
+                op: MergeOp::Replace(value),
+            }
+            .to_bytes(),
+        },
     }
+}
 
-    /// Retourne un itérateur sur le journal (lecture seule).
-    pub fn log_iter(&self) -> Result<LogIter, DatabaseError> {
-        LogIter::new(&self.config.file_path)
+/// Vrai pour une erreur d'écriture due au manque d'espace : disque ou quota
+/// plein, ou écriture tronquée sans autre erreur.
+fn is_disk_full(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        ErrorKind::StorageFull | ErrorKind::QuotaExceeded | ErrorKind::WriteZero
+    )
+}
+
+fn index_mismatch(key: &[u8], offset: u64) -> DatabaseError {
+    DatabaseError::IndexMismatch {
+        key: key.to_vec(),
+        offset,
+    }
+}
+
+fn tombstone_entry(key: Vec<u8>) -> DataEntry {
+    DataEntry {
+        entry_type: EntryType::Tombstone,
+        key,
+        value: Vec::new(),
+    }
+}
+
+fn check_deadline(deadline: Option<Instant>) -> Result<(), DatabaseError> {
+    match deadline {
+        Some(deadline) if Instant::now() >= deadline => Err(DatabaseError::Timeout),
+        _ => Ok(()),
+    }
+}
+
+/// Emplacement du journal compacté.
+struct CompactionTarget<'a> {
+    dir: &'a Path,
+    preallocate: bool,
+}
+
+/// Fichier temporaire d'une compaction dans `dir`, propre au processus et à
+/// l'instant pour que deux processus compactant le même journal ne se gênent
+/// pas : `<journal>.<pid>.<ms>.compacted`.
+fn compaction_temp_path(path: &Path, dir: &Path, now_millis: u64) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".{}.{}.compacted", std::process::id(), now_millis));
+    dir.join(name)
+}
+
+/// Réserve `len` octets en les écrivant (un fichier creux ne garantirait pas
+/// l'espace), puis revient au début du fichier.
+fn preallocate_file(file: &mut File, len: u64) -> io::Result<()> {
+    let zeros = vec![0u8; 64 * 1024];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = remaining.min(zeros.len() as u64) as usize;
+        file.write_all(&zeros[..chunk])?;
+        remaining -= chunk as u64;
+    }
+    file.seek(SeekFrom::Start(0))?;
+    Ok(())
+}
+
+/// Répertoire de `path`, `.` pour un nom relatif sans répertoire.
+fn parent_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    }
+}
+
+/// Enregistrement produit par `MyDatabase::compaction_copy`.
+pub(crate) struct CopiedRecord {
+    pub(crate) bytes: Vec<u8>,
+    /// Valeur matérialisée, si demandée.
+    value: Option<Value>,
+    /// `bytes` est une référence vers l'enregistrement `Blob` situé à cette
+    /// position du journal lu (voir `BlobCopy::place`).
+    blob: Option<u64>,
+}
+
+/// Enregistrements `Blob` recopiés dans un nouveau fichier (compaction,
+/// `clone_to`) : chacun y est écrit une seule fois, avant la première
+/// référence recopiée, et seulement s'il est encore référencé.
+struct BlobCopy {
+    /// Position dans la copie, par position dans le journal lu.
+    moved: HashMap<u64, u64>,
+    /// Enregistrements de la copie, par empreinte.
+    blobs: HashMap<u64, IndexEntry>,
+    raw_copy: bool,
+    min_savings: Option<u8>,
+    /// Dictionnaire de la copie.
+    dictionary: Option<Arc<Dictionary>>,
+    generation: u64,
+}
+
+impl BlobCopy {
+    fn new(
+        raw_copy: bool,
+        min_savings: Option<u8>,
+        dictionary: Option<Arc<Dictionary>>,
+        generation: u64,
+    ) -> Self {
+        Self {
+            moved: HashMap::new(),
+            blobs: HashMap::new(),
+            raw_copy,
+            min_savings,
+            dictionary,
+            generation,
+        }
+    }
+
+    /// Fait pointer la référence `copied` vers la copie de son
+    /// enregistrement `Blob` ; voir `locate`.
+    fn place(
+        &mut self,
+        reader: &mut LogReader,
+        copied: &mut CopiedRecord,
+        position: u64,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DatabaseError> {
+        if let Some(blob) = copied.blob {
+            let moved = self.locate(reader, blob, position, out)?;
+            codec::relocate_reference(&mut copied.bytes, moved);
+        }
+        Ok(())
+    }
+
+    /// Position dans la copie de l'enregistrement `Blob` situé en `offset`
+    /// dans le journal lu par `reader`. S'il n'y est pas encore, son encodage
+    /// (brut comme `compaction_copy`, ou avec le dictionnaire de la copie)
+    /// est ajouté à `out`, dont le début sera écrit en `position`.
+    fn locate(
+        &mut self,
+        reader: &mut LogReader,
+        offset: u64,
+        position: u64,
+        out: &mut Vec<u8>,
+    ) -> Result<u64, DatabaseError> {
+        if let Some(&moved) = self.moved.get(&offset) {
+            return Ok(moved);
+        }
+        let raw = reader.read_blob(offset)?;
+        let record =
+            codec::verify_record(&raw).map_err(|err| err.at_record(&reader.path, offset))?;
+        let hash = blob_hash(record.key)
+            .ok_or_else(|| DatabaseError::InvalidFormat.at_record(&reader.path, offset))?;
+        let start = out.len();
+        if self.raw_copy && record.dictionary == self.dictionary.as_ref().map(|d| d.id) {
+            out.extend_from_slice(&raw);
+        } else {
+            let entry = DataEntry {
+                entry_type: EntryType::Blob,
+                key: record.key.to_vec(),
+                value: reader
+                    .decode_value(&record)
+                    .map_err(|err| err.at_record(&reader.path, offset))?,
+            };
+            let dictionary = self.dictionary.as_deref();
+            codec::encode_record_into(&entry, &[], self.min_savings, dictionary, out);
+        }
+        let moved = position + start as u64;
+        self.moved.insert(offset, moved);
+        self.blobs.insert(
+            hash,
+            IndexEntry {
+                offset: moved,
+                size: (out.len() - start) as u32,
+                tombstone: false,
+                generation: self.generation,
+            },
+        );
+        Ok(moved)
+    }
+}
+
+/// Stockage d'une valeur selon `dedup_min_size` (`MyDatabase::find_blob`).
+enum BlobLookup {
+    /// Valeur stockée dans l'entrée elle-même.
+    Inline,
+    /// Valeur portée par l'enregistrement `Blob` situé à cette position.
+    Shared(u64),
+    /// Aucun enregistrement `Blob` n'a cette empreinte.
+    Missing(u64),
+}
+
+/// Index reconstruit par `MyDatabase::replay_log`.
+pub(crate) struct Replay {
+    pub(crate) index: HashMap<Vec<u8>, IndexEntry>,
+    /// Enregistrements `Blob`, par empreinte.
+    pub(crate) blobs: HashMap<u64, IndexEntry>,
+    /// Fin du dernier enregistrement conservé.
+    pub(crate) end: u64,
+    /// Des enregistrements invalides ont été ignorés ou retirés.
+    damaged: bool,
+    records: u64,
+    skipped: u64,
+}
+
+/// Remplacement du journal en cours : les lecteurs ouverts pendant ce temps
+/// n'ont pas de génération sûre. Abandonné sans `commit`, la génération
+/// précédente est rétablie.
+pub(crate) struct FileSwap<'a> {
+    shared: &'a SharedState,
+    committed: bool,
+}
+
+impl<'a> FileSwap<'a> {
+    pub(crate) fn begin(shared: &'a SharedState) -> Self {
+        shared.file_epoch.fetch_add(1, Ordering::AcqRel);
+        Self {
+            shared,
+            committed: false,
+        }
+    }
+
+    /// Le nouveau journal est en place : passe à la génération suivante.
+    pub(crate) fn commit(mut self) {
+        self.shared.file_epoch.fetch_add(1, Ordering::AcqRel);
+        self.committed = true;
+    }
+}
+
+impl Drop for FileSwap<'_> {
+    fn drop(&mut self) {
+        if !self.committed {
+            self.shared.file_epoch.fetch_sub(1, Ordering::AcqRel);
+        }
+    }
+}
+
+/// Supprime les fichiers temporaires laissés par une compaction interrompue :
+/// l'ancien nom fixe `<journal>.compacted` et ceux qui n'ont pas été modifiés
+/// depuis `STALE_COMPACTION_AGE` (une compaction en cours écrit sans cesse le sien).
+fn remove_stale_compaction_files(path: &Path) -> Result<(), DatabaseError> {
+    let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(());
+    };
+    let dir = parent_dir(path);
+    let legacy = format!("{}.compacted", file_name);
+    let prefix = format!("{}.", file_name);
+
+    for dir_entry in std::fs::read_dir(dir).context(IoOp::StaleCompactionScan, dir)? {
+        let dir_entry = dir_entry.context(IoOp::StaleCompactionScan, dir)?;
+        let name = dir_entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        let stale = if name == legacy {
+            true
+        } else if name.starts_with(&prefix) && name.ends_with(".compacted") {
+            dir_entry
+                .metadata()
+                .and_then(|metadata| metadata.modified())
+                .ok()
+                .and_then(|modified| modified.elapsed().ok())
+                .is_some_and(|age| age >= STALE_COMPACTION_AGE)
+        } else {
+            false
+        };
+        if stale {
+            let stale_path = dir_entry.path();
+            match std::fs::remove_file(&stale_path) {
+                Ok(()) => {}
+                Err(err) if err.kind() == ErrorKind::NotFound => {}
+                Err(err) => {
+                    return Err(DatabaseError::io(
+                        IoOp::StaleCompactionRemove,
+                        stale_path,
+                        err,
+                    ));
+                }
+            }
+        }
     }
+    Ok(())
+}
+
+/// Rend durable un renommage dans le répertoire de `path`. Les répertoires ne
+/// peuvent pas être ouverts ainsi hors Unix, où le renommage suffit.
+pub(crate) fn sync_parent_dir(path: &Path) -> Result<(), DatabaseError> {
+    #[cfg(unix)]
+    {
+        let dir = parent_dir(path);
+        File::open(dir)
+            .and_then(|dir| dir.sync_all())
+            .context(IoOp::DirSync, dir)?;
+    }
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Empreinte portée par la clé d'un enregistrement `Blob`.
+pub(crate) fn blob_hash(key: &[u8]) -> Option<u64> {
+    key.try_into().ok().map(u64::from_be_bytes)
+}
+
+/// Somme des tailles des entrées vivantes d'un index.
+pub(crate) fn live_size<K>(index: &HashMap<K, IndexEntry>) -> u64 {
+    index
+        .values()
+        .filter(|entry| !entry.tombstone)
+        .map(|entry| entry.size as u64)
+        .sum()
 }
 
 impl Clone for MyDatabase {
@@ -432,20 +3280,265 @@ impl Clone for MyDatabase {
 }
 
 impl LogReader {
-    fn new(path: &PathBuf) -> Result<Self, DatabaseError> {
-        Ok(Self {
-            file: File::open(path)?,
+    pub(crate) fn new(path: &Path) -> Result<Self, DatabaseError> {
+        let file = File::open(path).context(IoOp::LogOpen, path)?;
+        Ok(Self::from_file(file, path))
+    }
+
+    /// Lecteur de `file`, déjà ouvert sur le journal `path`.
+    pub(crate) fn from_file(file: File, path: &Path) -> Self {
+        Self {
+            file,
+            path: path.to_path_buf(),
             offset: 0,
+            generation: 0,
+            read_ahead: None,
+            max_expansion: codec::MAX_EXPANSION,
+            dictionary: None,
+        }
+    }
+
+    /// Rejette à la lecture les valeurs décodées de plus de `max_expansion`
+    /// fois leur taille stockée.
+    pub(crate) fn with_max_expansion(mut self, max_expansion: usize) -> Self {
+        self.max_expansion = max_expansion;
+        self
+    }
+
+    /// Lit par blocs de `capacity` octets (0 = désactivé) : adapté aux
+    /// lectures par offsets croissants.
+    pub(crate) fn with_read_ahead(mut self, capacity: usize) -> Self {
+        self.read_ahead = (capacity > 0).then(|| ReadAhead {
+            buffer: Vec::new(),
+            start: 0,
+            capacity,
+        });
+        self
+    }
+}
+
+impl LogReader {
+    /// Octets bruts de l'enregistrement désigné par `entry`.
+    pub(crate) fn read_raw(&mut self, entry: &IndexEntry) -> Result<Vec<u8>, DatabaseError> {
+        let mut buffer = Vec::new();
+        self.read_raw_into(entry, &mut buffer)?;
+        Ok(buffer)
+    }
+
+    /// Comme `read_raw`, en remplaçant le contenu de `buffer`.
+    pub(crate) fn read_raw_into(
+        &mut self,
+        entry: &IndexEntry,
+        buffer: &mut Vec<u8>,
+    ) -> Result<(), DatabaseError> {
+        if let Some(ahead) = &mut self.read_ahead
+            && entry.size as usize <= ahead.capacity
+        {
+            return ahead
+                .read_into(&mut self.file, entry, buffer)
+                .context(IoOp::RecordRead, &self.path);
+        }
+        buffer.clear();
+        buffer.resize(entry.size as usize, 0);
+        self.file
+            .seek(SeekFrom::Start(entry.offset))
+            .and_then(|_| self.file.read_exact(buffer))
+            .context(IoOp::RecordRead, &self.path)
+    }
+
+    /// Dictionnaire de compression du fichier ouvert : l'enregistrement
+    /// `EntryType::Dictionary` qui le commence, s'il y en a un.
+    pub(crate) fn dictionary(&mut self) -> Result<Option<Arc<Dictionary>>, DatabaseError> {
+        if let Some(dictionary) = &self.dictionary {
+            return Ok(dictionary.clone());
+        }
+        let dictionary = match self.header_at(0)? {
+            Some(header) if header[0] & !codec::FLAG_BITS == EntryType::Dictionary.to_byte() => {
+                let size = codec::record_len(&header);
+                if size > codec::MAX_DICTIONARY_RECORD_LEN as u64 {
+                    return Err(DatabaseError::InvalidFormat.at_record(&self.path, 0));
+                }
+                let raw = self.read_raw(&IndexEntry {
+                    offset: 0,
+                    size: size as u32,
+                    tombstone: false,
+                    generation: self.generation,
+                })?;
+                let dictionary = codec::verify_record(&raw)
+                    .and_then(|record| Dictionary::from_record(&record))
+                    .map_err(|err| err.at_record(&self.path, 0))?;
+                Some(Arc::new(dictionary))
+            }
+            _ => None,
+        };
+        self.dictionary = Some(dictionary.clone());
+        Ok(dictionary)
+    }
+
+    /// En-tête de l'enregistrement situé en `offset` ; `None` au-delà de la
+    /// fin du fichier.
+    fn header_at(
+        &mut self,
+        offset: u64,
+    ) -> Result<Option<[u8; codec::RECORD_HEADER_LEN]>, DatabaseError> {
+        let mut header = [0u8; codec::RECORD_HEADER_LEN];
+        let read = self
+            .file
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.file.read_exact(&mut header));
+        match read {
+            Ok(()) => Ok(Some(header)),
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(err) => Err(DatabaseError::io(IoOp::RecordRead, &self.path, err)),
+        }
+    }
+
+    /// Octets bruts de l'enregistrement `EntryType::Blob` situé en `offset`,
+    /// dont la taille est lue dans son en-tête ; `InvalidFormat` s'il n'y en
+    /// a pas à cette position.
+    pub(crate) fn read_blob(&mut self, offset: u64) -> Result<Vec<u8>, DatabaseError> {
+        let header = self
+            .header_at(offset)?
+            .filter(|header| header[0] & !codec::FLAG_BITS == EntryType::Blob.to_byte());
+        let file_len = self
+            .file
+            .metadata()
+            .context(IoOp::RecordRead, &self.path)?
+            .len();
+        let size = header
+            .and_then(|header| u32::try_from(codec::record_len(&header)).ok())
+            .filter(|size| u64::from(*size) <= file_len.saturating_sub(offset));
+        let Some(size) = size else {
+            return Err(DatabaseError::InvalidFormat.at_record(&self.path, offset));
+        };
+        self.read_raw(&IndexEntry {
+            offset,
+            size,
+            tombstone: false,
+            generation: self.generation,
         })
     }
+
+    /// Valeur de `record`, lu dans ce fichier, décodée avec `max_expansion`
+    /// et le dictionnaire du fichier ; celle d'une référence (`BLOB_FLAG`)
+    /// est lue dans l'enregistrement `Blob` référencé.
+    pub(crate) fn decode_value(
+        &mut self,
+        record: &RawRecord<'_>,
+    ) -> Result<Vec<u8>, DatabaseError> {
+        let mut out = Vec::new();
+        self.decode_value_into(record, &mut out)?;
+        Ok(out)
+    }
+
+    /// Comme `decode_value`, en remplaçant le contenu de `out`.
+    pub(crate) fn decode_value_into(
+        &mut self,
+        record: &RawRecord<'_>,
+        out: &mut Vec<u8>,
+    ) -> Result<(), DatabaseError> {
+        if let Some(offset) = record.blob {
+            let raw = self.read_blob(offset)?;
+            return codec::verify_record(&raw)
+                .and_then(|blob| self.decode_value_into(&blob, out))
+                .map_err(|err| err.at_record(&self.path, offset));
+        }
+        let dictionary = self.dictionary_of(record)?;
+        record.decode_value_into(out, self.max_expansion, dictionary.as_deref())
+    }
+
+    /// Taille de la valeur décodée de `record` (voir `RawRecord::decoded_len`).
+    pub(crate) fn decoded_len(&mut self, record: &RawRecord<'_>) -> Result<usize, DatabaseError> {
+        if let Some(offset) = record.blob {
+            let raw = self.read_blob(offset)?;
+            return codec::verify_record(&raw)
+                .and_then(|blob| self.decoded_len(&blob))
+                .map_err(|err| err.at_record(&self.path, offset));
+        }
+        let dictionary = self.dictionary_of(record)?;
+        record.decoded_len(self.max_expansion, dictionary.as_deref())
+    }
+
+    /// Taille stockée de la valeur de `record` : celle de l'enregistrement
+    /// `Blob` référencé pour une référence (`BLOB_FLAG`).
+    pub(crate) fn stored_len(&mut self, record: &RawRecord<'_>) -> Result<usize, DatabaseError> {
+        let Some(offset) = record.blob else {
+            return Ok(record.value.len());
+        };
+        let raw = self.read_blob(offset)?;
+        codec::verify_record(&raw)
+            .map(|blob| blob.value.len())
+            .map_err(|err| err.at_record(&self.path, offset))
+    }
+
+    /// Dictionnaire nécessaire à `record`, lu seulement s'il en a un.
+    fn dictionary_of(
+        &mut self,
+        record: &RawRecord<'_>,
+    ) -> Result<Option<Arc<Dictionary>>, DatabaseError> {
+        match record.dictionary {
+            Some(_) => self.dictionary(),
+            None => Ok(None),
+        }
+    }
+}
+
+impl ReadAhead {
+    /// Octets de `entry`, en rechargeant la fenêtre depuis son offset s'ils
+    /// n'y sont pas entièrement.
+    fn read_into(
+        &mut self,
+        file: &mut File,
+        entry: &IndexEntry,
+        out: &mut Vec<u8>,
+    ) -> io::Result<()> {
+        let size = entry.size as usize;
+        let end = entry.offset + entry.size as u64;
+        if entry.offset < self.start || end > self.start + self.buffer.len() as u64 {
+            self.buffer.clear();
+            self.start = entry.offset;
+            file.seek(SeekFrom::Start(entry.offset))?;
+            (&mut *file)
+                .take(self.capacity as u64)
+                .read_to_end(&mut self.buffer)?;
+            if self.buffer.len() < size {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+        }
+        let from = (entry.offset - self.start) as usize;
+        out.clear();
+        out.extend_from_slice(&self.buffer[from..from + size]);
+        Ok(())
+    }
 }
 
 impl LogIter {
-    fn new(path: &PathBuf) -> Result<Self, DatabaseError> {
+    fn new(path: &Path) -> Result<Self, DatabaseError> {
         Ok(Self {
             reader: LogReader::new(path)?,
+            max_record_size: codec::MAX_RECORD_SIZE,
+        })
+    }
+
+    fn from_offset(path: &Path, offset: u64) -> Result<Self, DatabaseError> {
+        let mut reader = LogReader::new(path)?;
+        reader
+            .file
+            .seek(SeekFrom::Start(offset))
+            .context(IoOp::LogRead, path)?;
+        reader.offset = offset;
+        Ok(Self {
+            reader,
+            max_record_size: codec::MAX_RECORD_SIZE,
         })
     }
+
+    /// Rejette comme corrompu tout enregistrement de plus de
+    /// `max_record_size` octets, avant d'allouer son contenu.
+    pub(crate) fn with_max_record_size(mut self, max_record_size: u32) -> Self {
+        self.max_record_size = max_record_size;
+        self
+    }
 }
 
 impl Iterator for LogIter {
@@ -456,61 +3549,595 @@ impl Iterator for LogIter {
         match self.reader.file.read_exact(&mut header) {
             Ok(_) => {}
             Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return None,
-            Err(err) => return Some(Err(err.into())),
+            Err(err) => {
+                return Some(Err(DatabaseError::io(
+                    IoOp::LogRead,
+                    &self.reader.path,
+                    err,
+                )));
+            }
         }
 
-        let entry_type = match header[0] {
-            0 => EntryType::Data,
-            1 => EntryType::Tombstone,
-            _ => return Some(Err(DatabaseError::InvalidFormat)),
+        let Some(entry_type) = EntryType::from_byte(header[0] & !codec::FLAG_BITS) else {
+            return Some(Err(DatabaseError::CorruptedRecord {
+                path: self.reader.path.clone(),
+                offset: self.reader.offset,
+            }));
         };
 
-        let key_len = u32::from_be_bytes(
-            header[1..5]
-                .try_into()
-                .map_err(|_| DatabaseError::InvalidFormat)?,
-        ) as usize;
-        let value_len = u32::from_be_bytes(
-            header[5..9]
-                .try_into()
-                .map_err(|_| DatabaseError::InvalidFormat)?,
-        ) as usize;
-        let total_size = 9usize + key_len + value_len + 4usize;
+        let key_len = u32::from_be_bytes([header[1], header[2], header[3], header[4]]) as usize;
+        let value_len = u32::from_be_bytes([header[5], header[6], header[7], header[8]]) as usize;
 
-        let mut body = vec![0u8; key_len + value_len + 4];
-        if let Err(err) = self.reader.file.read_exact(&mut body) {
-            if err.kind() == io::ErrorKind::UnexpectedEof {
-                return None;
+        // Métadonnées éventuelles : leur taille (1 octet) puis leurs octets.
+        let mut meta = Vec::new();
+        if header[0] & codec::META_FLAG != 0 {
+            meta.push(0);
+            let read = self.reader.file.read_exact(&mut meta).and_then(|_| {
+                meta.resize(1 + meta[0] as usize, 0);
+                self.reader.file.read_exact(&mut meta[1..])
+            });
+            if let Err(err) = read {
+                if err.kind() == io::ErrorKind::UnexpectedEof {
+                    return None;
+                }
+                return Some(Err(DatabaseError::io(
+                    IoOp::LogRead,
+                    &self.reader.path,
+                    err,
+                )));
             }
-            return Some(Err(err.into()));
         }
+        // Tailles lues dans l'en-tête : un débordement (cibles 32 bits) ou une
+        // taille au-delà de `max_record_size` trahit un en-tête invalide.
+        let checksum_start = key_len.checked_add(value_len);
+        let total_size = checksum_start
+            .and_then(|len| len.checked_add(codec::RECORD_HEADER_LEN + meta.len() + 4))
+            .and_then(|len| u32::try_from(len).ok())
+            .filter(|size| *size <= self.max_record_size);
+        let (Some(checksum_start), Some(total_size)) = (checksum_start, total_size) else {
+            return Some(Err(
+                DatabaseError::InvalidFormat.at_record(&self.reader.path, self.reader.offset)
+            ));
+        };
 
-        let checksum_start = key_len + value_len;
-        let stored_checksum = u32::from_be_bytes(
-            body[checksum_start..checksum_start + 4]
-                .try_into()
-                .map_err(|_| DatabaseError::CorruptedData)?,
-        );
-
-        let mut sum: u32 = 0;
-        for byte in &header {
-            sum = sum.wrapping_add(*byte as u32);
-        }
-        for byte in &body[..checksum_start] {
-            sum = sum.wrapping_add(*byte as u32);
+        // Lu au fil des octets présents : un en-tête corrompu en fin de
+        // journal n'alloue pas la taille qu'il annonce.
+        let body_len = checksum_start + 4;
+        let mut body = Vec::new();
+        match (&mut self.reader.file)
+            .take(body_len as u64)
+            .read_to_end(&mut body)
+        {
+            Ok(read) if read < body_len => return None,
+            Ok(_) => {}
+            Err(err) => {
+                return Some(Err(DatabaseError::io(
+                    IoOp::LogRead,
+                    &self.reader.path,
+                    err,
+                )));
+            }
         }
 
+        let stored_checksum = u32::from_be_bytes([
+            body[checksum_start],
+            body[checksum_start + 1],
+            body[checksum_start + 2],
+            body[checksum_start + 3],
+        ]);
+
+        let sum = codec::checksum(&header)
+            .wrapping_add(codec::checksum(&meta))
+            .wrapping_add(codec::checksum(&body[..checksum_start]));
         let checksum_ok = sum == stored_checksum;
         let key = body[..key_len].to_vec();
         let record = LogRecord {
             offset: self.reader.offset,
-            size: total_size as u32,
+            size: total_size,
             entry_type,
             key,
             value_len,
             checksum_ok,
         };
-        self.reader.offset += total_size as u64;
+        self.reader.offset += u64::from(total_size);
+        // Le dictionnaire de compression n'est pas une entrée ; invalide, il
+        // est signalé comme tout enregistrement corrompu.
+        if entry_type == EntryType::Dictionary && checksum_ok {
+            return self.next();
+        }
         Some(Ok(record))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    /// Journal temporaire contenant `bytes`, propre au test `name`.
+    fn log_with(name: &str, bytes: &[u8]) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("rdb-logiter-{}-{}.db", std::process::id(), name));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    /// Premier résultat de `LogIter` sur `bytes`.
+    fn first_record(
+        name: &str,
+        bytes: &[u8],
+        max_record_size: u32,
+    ) -> Option<Result<LogRecord, DatabaseError>> {
+        let path = log_with(name, bytes);
+        let first = LogIter::new(&path)
+            .unwrap()
+            .with_max_record_size(max_record_size)
+            .next();
+        std::fs::remove_file(&path).unwrap();
+        first
+    }
+
+    #[test]
+    fn oversized_lengths_are_rejected() {
+        // Entrées minimisées issues du fuzzing : tailles d'en-tête dont la
+        // somme déborde sur 32 bits ou dépasse `u32`.
+        let inputs: &[(&str, &[u8])] = &[
+            ("max-key", &[0, 0xff, 0xff, 0xff, 0xff, 0, 0, 0, 0]),
+            (
+                "max-both",
+                &[0, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff],
+            ),
+        ];
+        for (name, input) in inputs {
+            let first = first_record(name, input, codec::MAX_RECORD_SIZE);
+            assert!(
+                matches!(first, Some(Err(DatabaseError::CorruptedRecord { .. }))),
+                "{}: {:?}",
+                name,
+                first.map(|r| r.map(|record| record.size))
+            );
+        }
+    }
+
+    #[test]
+    fn lengths_overflowing_with_meta_are_rejected() {
+        // Clé et valeur tiennent dans `u32`, mais pas avec l'en-tête et
+        // 255 octets de métadonnées.
+        let mut input = vec![0x80, 0xff, 0xff, 0xfe, 0x00, 0, 0, 0, 0xff, 0xff];
+        input.extend_from_slice(&[0; 255]);
+        let first = first_record("max-meta", &input, codec::MAX_RECORD_SIZE);
+        assert!(matches!(
+            first,
+            Some(Err(DatabaseError::CorruptedRecord { .. }))
+        ));
+    }
+
+    #[test]
+    fn unknown_type_is_rejected() {
+        let first = first_record(
+            "type",
+            &[0x07, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            u32::MAX,
+        );
+        assert!(matches!(
+            first,
+            Some(Err(DatabaseError::CorruptedRecord { .. }))
+        ));
+    }
+
+    #[test]
+    fn record_over_max_size_is_rejected() {
+        // Annonce 1 Go sans les octets correspondants : rejeté avant
+        // allocation.
+        let first = first_record("cap", &[0, 0, 0, 0, 1, 0x40, 0, 0, 0], 1 << 20);
+        assert!(matches!(
+            first,
+            Some(Err(DatabaseError::CorruptedRecord { .. }))
+        ));
+    }
+
+    #[test]
+    fn truncated_tail_ends_iteration() {
+        let first = first_record("tail", &[0, 0, 0, 0, 1, 0, 0, 0, 8, b'k'], u32::MAX);
+        assert!(first.is_none());
+    }
+
+    fn kv(key: &str, value: &str) -> (Vec<u8>, Vec<u8>) {
+        (key.as_bytes().to_vec(), value.as_bytes().to_vec())
+    }
+
+    /// Base `other.db` de `dir` contenant `entries`, puis fermée.
+    fn other_database(dir: &TempDir, entries: &[(&str, &str)]) -> PathBuf {
+        let path = dir.path().join("other.db");
+        let other = MyDatabase::new(DatabaseConfig {
+            file_path: path.clone(),
+            ..DatabaseConfig::default()
+        })
+        .unwrap();
+        for (key, value) in entries {
+            let (key, value) = kv(key, value);
+            other.set(key, value).unwrap();
+        }
+        other.delete(b"gone".to_vec()).unwrap();
+        path
+    }
+
+    #[test]
+    fn merge_from_applies_the_conflict_policy() {
+        let dir = TempDir::new("merge-from");
+        let other = other_database(&dir, &[("a", "new"), ("b", "2"), ("gone", "x")]);
+        let db = dir.open();
+        db.set(b"a".to_vec(), b"old".to_vec()).unwrap();
+
+        let err = db.merge_from(&other, ConflictPolicy::Error).unwrap_err();
+        assert!(matches!(err, DatabaseError::KeyConflict(key) if key == b"a"));
+        assert!(!db.contains_key(b"b").unwrap());
+
+        assert_eq!(
+            db.merge_from(&other, ConflictPolicy::KeepExisting).unwrap(),
+            1
+        );
+        assert_eq!(db.get(b"a").unwrap(), Some(b"old".to_vec()));
+        assert_eq!(db.get(b"b").unwrap(), Some(b"2".to_vec()));
+        assert!(!db.contains_key(b"gone").unwrap());
+
+        assert_eq!(
+            db.merge_from(&other, ConflictPolicy::LastWriteWins)
+                .unwrap(),
+            2
+        );
+        assert_eq!(db.get(b"a").unwrap(), Some(b"new".to_vec()));
+    }
+
+    #[test]
+    fn merge_from_resolves_collections() {
+        let dir = TempDir::new("merge-from-collections");
+        let path = dir.path().join("other.db");
+        {
+            let other = MyDatabase::new(DatabaseConfig {
+                file_path: path.clone(),
+                ..DatabaseConfig::default()
+            })
+            .unwrap();
+            other
+                .rpush(b"l".to_vec(), vec![b"a".to_vec(), b"b".to_vec()])
+                .unwrap();
+            other.lpop(b"l".to_vec()).unwrap();
+        }
+        let db = dir.open();
+        assert_eq!(db.merge_from(&path, ConflictPolicy::Error).unwrap(), 1);
+        assert_eq!(db.lrange(b"l", 0, -1).unwrap(), vec![b"b".to_vec()]);
+    }
+
+    #[test]
+    fn delete_many_writes_durable_tombstones() {
+        let dir = TempDir::new("delete-many");
+        {
+            let db = dir.open();
+            for key in ["a", "b", "c"] {
+                let (key, value) = kv(key, "v");
+                db.set(key, value).unwrap();
+            }
+            db.delete_many(&[]).unwrap();
+            db.delete_many(&[b"a".to_vec(), b"c".to_vec(), b"absent".to_vec()])
+                .unwrap();
+            assert!(!db.contains_key(b"a").unwrap());
+            assert!(db.contains_key(b"b").unwrap());
+        }
+        let db = dir.open();
+        assert_eq!(
+            db.keys(b"").unwrap().collect::<Vec<_>>(),
+            vec![b"b".to_vec()]
+        );
+    }
+
+    #[test]
+    fn delete_many_is_subject_to_backpressure() {
+        let dir = TempDir::new("delete-many-backpressure");
+        let db = MyDatabase::new(DatabaseConfig {
+            backpressure: BackpressurePolicy::Reject { hard_limit: 1 },
+            compaction_schedule: CompactionSchedule::Window {
+                start_hour: 0,
+                end_hour: 0,
+            },
+            ..dir.config()
+        })
+        .unwrap();
+        db.set(b"a".to_vec(), b"v".to_vec()).unwrap();
+        assert!(matches!(
+            db.delete_many(&[b"a".to_vec()]),
+            Err(DatabaseError::Backpressure)
+        ));
+        assert!(db.contains_key(b"a").unwrap());
+    }
+
+    #[test]
+    fn purge_removes_every_trace_after_compaction() {
+        let dir = TempDir::new("purge");
+        let db = dir.open();
+        db.set(b"secret-key".to_vec(), b"secret-value".to_vec())
+            .unwrap();
+        db.set(b"secret-key".to_vec(), b"secret-value-2".to_vec())
+            .unwrap();
+        db.set(b"kept".to_vec(), b"v".to_vec()).unwrap();
+        db.purge(b"secret-key".to_vec()).unwrap();
+        assert_eq!(db.get(b"secret-key").unwrap(), None);
+        assert!(db.index_read().unwrap().get(&b"secret-key"[..]).is_none());
+
+        db.compact().unwrap();
+        let log = std::fs::read(&db.config.file_path).unwrap();
+        assert!(!log.windows(6).any(|w| w == b"secret"));
+        assert_eq!(db.get(b"kept").unwrap(), Some(b"v".to_vec()));
+    }
+
+    #[test]
+    fn reads_wait_for_the_log_replacement_to_finish() {
+        let dir = TempDir::new("generation-swap");
+        let db = dir.open();
+        db.set(b"k".to_vec(), b"v".to_vec()).unwrap();
+
+        let swap = FileSwap::begin(&db.shared);
+        assert!(db.open_reader().unwrap().is_none());
+        assert!(matches!(db.get(b"k"), Err(DatabaseError::Timeout)));
+        drop(swap);
+        assert_eq!(db.generation(), 0);
+        assert_eq!(db.get(b"k").unwrap(), Some(b"v".to_vec()));
+    }
+
+    #[test]
+    fn compaction_moves_entries_to_the_next_generation() {
+        let dir = TempDir::new("generation-compact");
+        let db = dir.open();
+        db.set(b"k".to_vec(), b"v1".to_vec()).unwrap();
+        db.set(b"k".to_vec(), b"v2".to_vec()).unwrap();
+        assert_eq!(db.index_read().unwrap()[&b"k"[..]].generation, 0);
+
+        db.compact().unwrap();
+        assert_eq!(db.generation(), 1);
+        assert_eq!(db.index_read().unwrap()[&b"k"[..]].generation, 1);
+        assert_eq!(db.open_reader().unwrap().unwrap().generation, 1);
+        assert_eq!(db.get(b"k").unwrap(), Some(b"v2".to_vec()));
+    }
+
+    #[test]
+    fn reads_during_compactions_see_current_values() {
+        let dir = TempDir::new("generation-race");
+        let db = dir.open();
+        for i in 0..50 {
+            let (key, value) = kv(&format!("k{i}"), &format!("v{i}"));
+            db.set(key, value).unwrap();
+        }
+        let done = Arc::new(AtomicBool::new(false));
+        let reader = {
+            let (db, done) = (db.clone(), Arc::clone(&done));
+            thread::spawn(move || {
+                let mut reads = 0;
+                while !done.load(Ordering::Acquire) {
+                    for i in 0..50 {
+                        let value = db.get(format!("k{i}").as_bytes()).unwrap();
+                        assert_eq!(value, Some(format!("v{i}").into_bytes()));
+                        reads += 1;
+                    }
+                }
+                reads
+            })
+        };
+        for _ in 0..20 {
+            db.set(b"churn".to_vec(), vec![0; 256]).unwrap();
+            db.compact().unwrap();
+        }
+        done.store(true, Ordering::Release);
+        assert!(reader.join().unwrap() > 0);
+    }
+
+    fn json_value(i: usize) -> Vec<u8> {
+        format!(r#"{{"id":{i},"name":"utilisateur {i}","email":"u{i}@example.com","active":true}}"#)
+            .into_bytes()
+    }
+
+    fn dictionary_db(dir: &TempDir) -> MyDatabase {
+        let db = MyDatabase::new(DatabaseConfig {
+            compression_dictionary: true,
+            compaction_raw_copy: true,
+            ..dir.config()
+        })
+        .unwrap();
+        for i in 0..200 {
+            db.set(format!("k{i}").into_bytes(), json_value(i)).unwrap();
+        }
+        db
+    }
+
+    #[test]
+    fn compaction_trains_a_dictionary_used_by_later_writes() {
+        let dir = TempDir::new("dictionary-compaction");
+        let db = dictionary_db(&dir);
+        let before = db.file_size().unwrap();
+        db.compact().unwrap();
+        assert!(db.file_size().unwrap() * 2 < before);
+
+        db.set(b"k200".to_vec(), json_value(200)).unwrap();
+        let dictionary = db.log_reader().unwrap().dictionary().unwrap().unwrap();
+        let entry = db.index_read().unwrap()[&b"k200"[..]];
+        let raw = db.log_reader().unwrap().read_raw(&entry).unwrap();
+        assert_eq!(
+            codec::verify_record(&raw).unwrap().dictionary,
+            Some(dictionary.id)
+        );
+
+        // Un nouveau dictionnaire : les copies brutes sont réencodées.
+        db.compact().unwrap();
+        let path = dir.path().join("copy.db");
+        db.clone_to(&path, |_| true).unwrap();
+        drop(db);
+        for config in [
+            dir.config(),
+            DatabaseConfig {
+                file_path: path,
+                ..dir.config()
+            },
+        ] {
+            let db = MyDatabase::new(config).unwrap();
+            let reopened = db.log_reader().unwrap().dictionary().unwrap().unwrap();
+            assert_eq!(reopened.id, dictionary.id + 1);
+            for i in 0..=200 {
+                let value = db.get(format!("k{i}").as_bytes()).unwrap();
+                assert_eq!(value, Some(json_value(i)));
+            }
+        }
+    }
+
+    #[test]
+    fn writes_during_compaction_switch_to_the_new_dictionary() {
+        let dir = TempDir::new("dictionary-catch-up");
+        let db = dictionary_db(&dir);
+        db.compact().unwrap();
+
+        let writer = db.clone();
+        let mut written = false;
+        db.compact_with_progress(|progress| {
+            if !written && progress.records_processed > 0 {
+                writer.set(b"late".to_vec(), json_value(999)).unwrap();
+                written = true;
+            }
+        })
+        .unwrap();
+        assert!(written);
+        assert_eq!(db.get(b"late").unwrap(), Some(json_value(999)));
+        drop((db, writer));
+        assert_eq!(dir.open().get(b"late").unwrap(), Some(json_value(999)));
+    }
+
+    #[test]
+    fn disabling_the_dictionary_drops_it_at_the_next_compaction() {
+        let dir = TempDir::new("dictionary-disabled");
+        dictionary_db(&dir).compact().unwrap();
+
+        let config = DatabaseConfig {
+            compact_on_open: true,
+            ..dir.config()
+        };
+        let db = MyDatabase::new(config.clone()).unwrap();
+        assert!(!db.recovery_summary().compacted);
+        db.compact().unwrap();
+        assert!(db.log_reader().unwrap().dictionary().unwrap().is_none());
+        drop(db);
+        let db = MyDatabase::new(config).unwrap();
+        assert_eq!(db.get(b"k7").unwrap(), Some(json_value(7)));
+    }
+
+    /// Valeur de 1 Kio peu compressible, propre à `seed`.
+    fn payload(seed: u32) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(2_654_435_761).wrapping_add(1);
+        (0..1024)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+                (state >> 16) as u8
+            })
+            .collect()
+    }
+
+    fn dedup_config(dir: &TempDir) -> DatabaseConfig {
+        DatabaseConfig {
+            dedup_min_size: Some(512),
+            ..dir.config()
+        }
+    }
+
+    #[test]
+    fn identical_values_are_stored_once() {
+        let dir = TempDir::new("dedup");
+        let db = MyDatabase::new(dedup_config(&dir)).unwrap();
+        for i in 0..10 {
+            db.set(format!("k{i}").into_bytes(), payload(1)).unwrap();
+        }
+        db.bulk_load((10..20).map(|i| (format!("k{i}").into_bytes(), payload(1))))
+            .unwrap();
+        db.set(b"other".to_vec(), payload(2)).unwrap();
+        assert!(db.file_size().unwrap() < 3 * 1024);
+        let size = db.value_size(b"k3").unwrap().unwrap();
+        assert_eq!(size.uncompressed, 1024);
+        assert!(size.compressed >= 1024);
+
+        let path = dir.path().join("copy.db");
+        db.clone_to(&path, |key| key != b"other").unwrap();
+        drop(db);
+        let copy = MyDatabase::new(DatabaseConfig {
+            file_path: path,
+            ..dir.config()
+        })
+        .unwrap();
+        assert!(copy.file_size().unwrap() < 2 * 1024);
+        let db = MyDatabase::new(dedup_config(&dir)).unwrap();
+        let before = db.file_size().unwrap();
+        db.set(b"k20".to_vec(), payload(1)).unwrap();
+        assert!(db.file_size().unwrap() - before < 64);
+        assert_eq!(db.get(b"k20").unwrap(), Some(payload(1)));
+        for db in [&db, &copy] {
+            for i in 0..20 {
+                let value = db.get(format!("k{i}").as_bytes()).unwrap();
+                assert_eq!(value, Some(payload(1)));
+            }
+        }
+    }
+
+    #[test]
+    fn compaction_drops_values_no_longer_referenced() {
+        let dir = TempDir::new("dedup-compaction");
+        let db = MyDatabase::new(dedup_config(&dir)).unwrap();
+        for i in 0..5 {
+            db.set(format!("k{i}").into_bytes(), payload(1)).unwrap();
+            db.set(format!("j{i}").into_bytes(), payload(2)).unwrap();
+        }
+        for i in 0..5 {
+            db.set(format!("k{i}").into_bytes(), b"petit".to_vec())
+                .unwrap();
+        }
+        db.compact().unwrap();
+        assert!(db.file_size().unwrap() < 2 * 1024);
+        assert_eq!(db.lock_blobs().unwrap().len(), 1);
+        for i in 0..5 {
+            assert_eq!(
+                db.get(format!("j{i}").as_bytes()).unwrap(),
+                Some(payload(2))
+            );
+        }
+        db.set(b"k0".to_vec(), payload(2)).unwrap();
+        drop(db);
+        let db = dir.open();
+        assert_eq!(db.get(b"k0").unwrap(), Some(payload(2)));
+        assert_eq!(db.get(b"k1").unwrap(), Some(b"petit".to_vec()));
+    }
+
+    #[test]
+    fn references_written_during_compaction_keep_their_value() {
+        let dir = TempDir::new("dedup-catch-up");
+        let db = MyDatabase::new(dedup_config(&dir)).unwrap();
+        for i in 0..20 {
+            db.set(format!("k{i}").into_bytes(), payload(i)).unwrap();
+        }
+        // Valeur partagée que plus aucune clé ne référence avant la copie.
+        db.set(b"orphan".to_vec(), payload(100)).unwrap();
+        db.delete(b"orphan".to_vec()).unwrap();
+
+        let writer = db.clone();
+        let mut written = false;
+        db.compact_with_progress(|progress| {
+            if !written && progress.records_processed > 0 {
+                writer.set(b"late".to_vec(), payload(100)).unwrap();
+                writer.set(b"fresh".to_vec(), payload(200)).unwrap();
+                writer.set(b"fresh2".to_vec(), payload(200)).unwrap();
+                written = true;
+            }
+        })
+        .unwrap();
+        assert!(written);
+        assert_eq!(db.get(b"late").unwrap(), Some(payload(100)));
+        assert_eq!(db.get(b"fresh2").unwrap(), Some(payload(200)));
+        drop((db, writer));
+        let db = dir.open();
+        assert_eq!(db.get(b"late").unwrap(), Some(payload(100)));
+        assert_eq!(db.get(b"fresh").unwrap(), Some(payload(200)));
+        assert_eq!(db.get(b"k7").unwrap(), Some(payload(7)));
+    }
+}
@@ -1,39 +1,493 @@
 use std::fmt;
 use std::io;
+use std::path::{Path, PathBuf};
 
 /// Erreurs métier de la base.
 #[derive(Debug)]
 pub enum DatabaseError {
-    Io(io::Error),
+    /// Erreur d'E/S, avec l'opération en cours et le fichier concerné (vide
+    /// s'il n'y en a pas).
+    Io {
+        op: IoOp,
+        path: PathBuf,
+        source: io::Error,
+    },
     CorruptedData,
+    /// Enregistrement illisible à une position connue du journal.
+    CorruptedRecord {
+        path: PathBuf,
+        offset: u64,
+    },
     InvalidFormat,
-    KeyNotFound(String),
+    KeyNotFound(Vec<u8>),
+    KeyConflict(Vec<u8>),
     ParseError(String),
     Utf8(std::string::FromUtf8Error),
-    LockPoisoned(&'static str),
+    LockPoisoned(LockResource),
+    Backpressure,
+    DatabaseFull,
+    WrongType,
+    TextIndexDisabled,
+    CompactionCancelled,
+    /// Délai d'attente dépassé.
+    Timeout,
+    /// Compaction impossible tant qu'un itérateur lit le journal actuel.
+    SnapshotInUse,
+    /// Métadonnées de plus de `MAX_META_LEN` octets.
+    MetadataTooLarge,
+    /// L'enregistrement désigné par l'index pour `key` porte une autre clé :
+    /// l'index ne reflète plus le journal (voir `MyDatabase::reindex`).
+    IndexMismatch {
+        key: Vec<u8>,
+        offset: u64,
+    },
+    /// Plus d'espace disque pour écrire : l'écriture est annulée et la base
+    /// reste en lecture seule jusqu'à une compaction réussie.
+    DiskFull,
+    /// Écriture ou compaction sur une base ouverte par `open_follower`.
+    ReadOnly,
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+    #[cfg(feature = "json")]
+    Json(serde_json::Error),
 }
 
 impl fmt::Display for DatabaseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            DatabaseError::Io(err) => write!(f, "Erreur E/S système : {}", err),
+            DatabaseError::Io { op, path, source } if path.as_os_str().is_empty() => {
+                write!(f, "Erreur E/S système ({}) : {}", op, source)
+            }
+            DatabaseError::Io { op, path, source } => write!(
+                f,
+                "Erreur E/S système ({}, {}) : {}",
+                op,
+                path.display(),
+                source
+            ),
             DatabaseError::CorruptedData => {
                 write!(f, "Données corrompues : le checksum ne correspond pas")
             }
+            DatabaseError::CorruptedRecord { path, offset } => write!(
+                f,
+                "Données corrompues : enregistrement illisible à l'offset {} de {}",
+                offset,
+                path.display()
+            ),
             DatabaseError::InvalidFormat => write!(f, "Format de fichier invalide ou incompatible"),
-            DatabaseError::KeyNotFound(key) => write!(f, "Clé non trouvée : '{}'", key),
+            DatabaseError::KeyNotFound(key) => {
+                write!(f, "Clé non trouvée : '{}'", String::from_utf8_lossy(key))
+            }
+            DatabaseError::KeyConflict(key) => write!(
+                f,
+                "Clé déjà présente dans la base : '{}'",
+                String::from_utf8_lossy(key)
+            ),
             DatabaseError::ParseError(msg) => write!(f, "Erreur de commande : {}", msg),
             DatabaseError::Utf8(err) => write!(f, "Données corrompues (UTF-8) : {}", err),
             DatabaseError::LockPoisoned(resource) => {
                 write!(f, "Verrouillage indisponible : {}", resource)
             }
+            DatabaseError::Backpressure => {
+                write!(f, "Écriture refusée : la compaction ne suit pas")
+            }
+            DatabaseError::DatabaseFull => {
+                write!(f, "Base pleine : plafond de données vivantes atteint")
+            }
+            DatabaseError::WrongType => {
+                write!(f, "Opération incompatible avec le type de la valeur")
+            }
+            DatabaseError::TextIndexDisabled => {
+                write!(f, "Index plein texte non activé")
+            }
+            DatabaseError::CompactionCancelled => write!(f, "Compaction annulée"),
+            DatabaseError::Timeout => write!(f, "Délai d'attente dépassé"),
+            DatabaseError::SnapshotInUse => {
+                write!(f, "Journal en cours de lecture par un itérateur")
+            }
+            DatabaseError::MetadataTooLarge => write!(f, "Métadonnées trop volumineuses"),
+            DatabaseError::IndexMismatch { key, offset } => write!(
+                f,
+                "Index incohérent : l'offset {} ne contient pas la clé '{}'",
+                offset,
+                String::from_utf8_lossy(key)
+            ),
+            DatabaseError::DiskFull => {
+                write!(f, "Disque plein : écriture annulée, base en lecture seule")
+            }
+            DatabaseError::ReadOnly => write!(f, "Base ouverte en lecture seule"),
+            #[cfg(feature = "sqlite")]
+            DatabaseError::Sqlite(err) => write!(f, "Erreur SQLite : {}", err),
+            #[cfg(feature = "json")]
+            DatabaseError::Json(err) => write!(f, "Erreur JSON : {}", err),
         }
     }
 }
 
-impl From<io::Error> for DatabaseError {
-    fn from(err: io::Error) -> Self {
-        DatabaseError::Io(err)
+/// Opération en cours lors d'une erreur d'E/S (`DatabaseError::Io`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IoOp {
+    LogOpen,
+    LogRead,
+    LogReplay,
+    LogWrite,
+    LogSync,
+    LogSize,
+    LogTruncate,
+    RecordRead,
+    DirSync,
+    PoisonReopen,
+    PoisonSize,
+    PoisonTruncate,
+    CompactionCreate,
+    CompactionPreallocate,
+    CompactionCopy,
+    CompactionCatchUp,
+    CompactionSync,
+    CompactionOpen,
+    CompactionReplace,
+    CompactionCopyBack,
+    CompactionTextIndexRemove,
+    StaleCompactionScan,
+    StaleCompactionRemove,
+    CopyCreate,
+    CopyWrite,
+    CopySync,
+    CopyPublish,
+    QuarantineOpen,
+    QuarantineWrite,
+    QuarantineSync,
+    TextIndexRead,
+    TextIndexWrite,
+    TextIndexSync,
+    TextIndexReplace,
+    TextIndexRemove,
+    TerminalRead,
+    TerminalWrite,
+}
+
+impl IoOp {
+    /// Libellé dans la langue `lang` ; `Display` donne le libellé français.
+    pub fn text(self, lang: Language) -> &'static str {
+        let (fr, en) = match self {
+            IoOp::LogOpen => ("ouverture du journal", "opening the log"),
+            IoOp::LogRead => ("lecture du journal", "reading the log"),
+            IoOp::LogReplay => ("relecture du journal", "replaying the log"),
+            IoOp::LogWrite => ("écriture du journal", "writing the log"),
+            IoOp::LogSync => ("synchronisation du journal", "syncing the log"),
+            IoOp::LogSize => ("taille du journal", "reading the log size"),
+            IoOp::LogTruncate => ("troncature du journal", "truncating the log"),
+            IoOp::RecordRead => ("lecture d'un enregistrement", "reading a record"),
+            IoOp::DirSync => ("synchronisation du répertoire", "syncing the directory"),
+            IoOp::PoisonReopen => (
+                "reprise : réouverture du journal",
+                "recovery: reopening the log",
+            ),
+            IoOp::PoisonSize => (
+                "reprise : taille du journal",
+                "recovery: reading the log size",
+            ),
+            IoOp::PoisonTruncate => (
+                "reprise : troncature du journal",
+                "recovery: truncating the log",
+            ),
+            IoOp::CompactionCreate => (
+                "compaction : création du fichier temporaire",
+                "compaction: creating the temporary file",
+            ),
+            IoOp::CompactionPreallocate => {
+                ("compaction : préallocation", "compaction: preallocating")
+            }
+            IoOp::CompactionCopy => ("compaction : copie", "compaction: copying"),
+            IoOp::CompactionCatchUp => ("compaction : rattrapage", "compaction: catching up"),
+            IoOp::CompactionSync => ("compaction : synchronisation", "compaction: syncing"),
+            IoOp::CompactionOpen => (
+                "compaction : ouverture du journal compacté",
+                "compaction: opening the compacted log",
+            ),
+            IoOp::CompactionReplace => (
+                "compaction : remplacement du journal",
+                "compaction: replacing the log",
+            ),
+            IoOp::CompactionCopyBack => (
+                "compaction : recopie du journal",
+                "compaction: copying the log back",
+            ),
+            IoOp::CompactionTextIndexRemove => (
+                "compaction : suppression de l'index plein texte",
+                "compaction: removing the full-text index",
+            ),
+            IoOp::StaleCompactionScan => (
+                "recherche des compactions interrompues",
+                "looking for interrupted compactions",
+            ),
+            IoOp::StaleCompactionRemove => (
+                "suppression d'une compaction interrompue",
+                "removing an interrupted compaction",
+            ),
+            IoOp::CopyCreate => ("création de la copie", "creating the copy"),
+            IoOp::CopyWrite => ("écriture de la copie", "writing the copy"),
+            IoOp::CopySync => ("synchronisation de la copie", "syncing the copy"),
+            IoOp::CopyPublish => ("publication de la copie", "publishing the copy"),
+            IoOp::QuarantineOpen => ("ouverture de la quarantaine", "opening the quarantine"),
+            IoOp::QuarantineWrite => ("écriture de la quarantaine", "writing the quarantine"),
+            IoOp::QuarantineSync => (
+                "synchronisation de la quarantaine",
+                "syncing the quarantine",
+            ),
+            IoOp::TextIndexRead => (
+                "lecture de l'index plein texte",
+                "reading the full-text index",
+            ),
+            IoOp::TextIndexWrite => (
+                "écriture de l'index plein texte",
+                "writing the full-text index",
+            ),
+            IoOp::TextIndexSync => (
+                "synchronisation de l'index plein texte",
+                "syncing the full-text index",
+            ),
+            IoOp::TextIndexReplace => (
+                "remplacement de l'index plein texte",
+                "replacing the full-text index",
+            ),
+            IoOp::TextIndexRemove => (
+                "suppression de l'index plein texte",
+                "removing the full-text index",
+            ),
+            IoOp::TerminalRead => ("lecture du terminal", "reading the terminal"),
+            IoOp::TerminalWrite => ("écriture du terminal", "writing to the terminal"),
+        };
+        match lang {
+            Language::Fr => fr,
+            Language::En => en,
+        }
+    }
+}
+
+impl fmt::Display for IoOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text(Language::Fr))
+    }
+}
+
+/// Verrou empoisonné désigné par `DatabaseError::LockPoisoned`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockResource {
+    Access,
+    Index,
+    File,
+    Compaction,
+    Recency,
+    TextIndex,
+    Dictionary,
+    Blobs,
+}
+
+impl LockResource {
+    /// Libellé dans la langue `lang` ; `Display` donne le libellé français.
+    pub fn text(self, lang: Language) -> &'static str {
+        let (fr, en) = match self {
+            LockResource::Access => ("lecteur/rédacteur", "reader/writer lock"),
+            LockResource::Index => ("index", "index"),
+            LockResource::File => ("fichier", "log file"),
+            LockResource::Compaction => ("compaction", "compaction lock"),
+            LockResource::Recency => ("récence", "recency list"),
+            LockResource::TextIndex => ("index plein texte", "full-text index"),
+            LockResource::Dictionary => ("dictionnaire de compression", "compression dictionary"),
+            LockResource::Blobs => ("valeurs partagées", "shared values"),
+        };
+        match lang {
+            Language::Fr => fr,
+            Language::En => en,
+        }
+    }
+}
+
+impl fmt::Display for LockResource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text(Language::Fr))
+    }
+}
+
+/// Langue des messages d'erreur.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Language {
+    /// Langue de `Display`.
+    #[default]
+    Fr,
+    En,
+}
+
+impl Language {
+    /// Langue demandée par la variable d'environnement `RDB_LANG` (`en` ou
+    /// `fr`, casse indifférente) ; français si elle est absente ou inconnue.
+    pub fn from_env() -> Self {
+        match std::env::var("RDB_LANG") {
+            Ok(lang) if lang.eq_ignore_ascii_case("en") => Language::En,
+            _ => Language::Fr,
+        }
+    }
+}
+
+/// Message d'une erreur dans une langue donnée (`DatabaseError::localized`).
+pub struct Localized<'a> {
+    error: &'a DatabaseError,
+    lang: Language,
+}
+
+impl fmt::Display for Localized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.lang {
+            Language::Fr => fmt::Display::fmt(self.error, f),
+            Language::En => self.error.fmt_en(f),
+        }
+    }
+}
+
+impl DatabaseError {
+    /// Code stable de la nature de l'erreur, indépendant de la langue du
+    /// message : à utiliser pour distinguer les erreurs par programme.
+    pub fn code(&self) -> &'static str {
+        match self {
+            DatabaseError::Io { .. } => "E_IO",
+            DatabaseError::CorruptedData | DatabaseError::CorruptedRecord { .. } => "E_CORRUPT",
+            DatabaseError::InvalidFormat => "E_FORMAT",
+            DatabaseError::KeyNotFound(_) => "E_NOT_FOUND",
+            DatabaseError::KeyConflict(_) => "E_CONFLICT",
+            DatabaseError::ParseError(_) => "E_PARSE",
+            DatabaseError::Utf8(_) => "E_UTF8",
+            DatabaseError::LockPoisoned(_) => "E_LOCKED",
+            DatabaseError::Backpressure => "E_BACKPRESSURE",
+            DatabaseError::DatabaseFull => "E_FULL",
+            DatabaseError::WrongType => "E_WRONG_TYPE",
+            DatabaseError::TextIndexDisabled => "E_NO_TEXT_INDEX",
+            DatabaseError::CompactionCancelled => "E_CANCELLED",
+            DatabaseError::Timeout => "E_TIMEOUT",
+            DatabaseError::SnapshotInUse => "E_SNAPSHOT_IN_USE",
+            DatabaseError::MetadataTooLarge => "E_META_TOO_LARGE",
+            DatabaseError::IndexMismatch { .. } => "E_INDEX_MISMATCH",
+            DatabaseError::DiskFull => "E_DISK_FULL",
+            DatabaseError::ReadOnly => "E_READONLY",
+            #[cfg(feature = "sqlite")]
+            DatabaseError::Sqlite(_) => "E_SQLITE",
+            #[cfg(feature = "json")]
+            DatabaseError::Json(_) => "E_JSON",
+        }
+    }
+
+    /// Message de l'erreur dans la langue `lang` ; `Display` est en
+    /// français.
+    pub fn localized(&self, lang: Language) -> Localized<'_> {
+        Localized { error: self, lang }
+    }
+
+    fn fmt_en(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DatabaseError::Io { op, path, source } if path.as_os_str().is_empty() => {
+                write!(
+                    f,
+                    "System I/O error ({}): {}",
+                    op.text(Language::En),
+                    source
+                )
+            }
+            DatabaseError::Io { op, path, source } => write!(
+                f,
+                "System I/O error ({}, {}): {}",
+                op.text(Language::En),
+                path.display(),
+                source
+            ),
+            DatabaseError::CorruptedData => write!(f, "Corrupted data: checksum mismatch"),
+            DatabaseError::CorruptedRecord { path, offset } => write!(
+                f,
+                "Corrupted data: unreadable record at offset {} of {}",
+                offset,
+                path.display()
+            ),
+            DatabaseError::InvalidFormat => write!(f, "Invalid or incompatible file format"),
+            DatabaseError::KeyNotFound(key) => {
+                write!(f, "Key not found: '{}'", String::from_utf8_lossy(key))
+            }
+            DatabaseError::KeyConflict(key) => write!(
+                f,
+                "Key already present in the database: '{}'",
+                String::from_utf8_lossy(key)
+            ),
+            DatabaseError::ParseError(msg) => write!(f, "Command error: {}", msg),
+            DatabaseError::Utf8(err) => write!(f, "Corrupted data (UTF-8): {}", err),
+            DatabaseError::LockPoisoned(resource) => {
+                write!(f, "Lock unavailable: {}", resource.text(Language::En))
+            }
+            DatabaseError::Backpressure => {
+                write!(f, "Write rejected: compaction is falling behind")
+            }
+            DatabaseError::DatabaseFull => write!(f, "Database full: live data cap reached"),
+            DatabaseError::WrongType => write!(f, "Operation not supported by the value's type"),
+            DatabaseError::TextIndexDisabled => write!(f, "Full-text index not enabled"),
+            DatabaseError::CompactionCancelled => write!(f, "Compaction cancelled"),
+            DatabaseError::Timeout => write!(f, "Timed out"),
+            DatabaseError::SnapshotInUse => write!(f, "Log is being read by an iterator"),
+            DatabaseError::MetadataTooLarge => write!(f, "Metadata too large"),
+            DatabaseError::IndexMismatch { key, offset } => write!(
+                f,
+                "Inconsistent index: offset {} does not hold key '{}'",
+                offset,
+                String::from_utf8_lossy(key)
+            ),
+            DatabaseError::DiskFull => {
+                write!(f, "Disk full: write rolled back, database is read-only")
+            }
+            DatabaseError::ReadOnly => write!(f, "Database opened read-only"),
+            #[cfg(feature = "sqlite")]
+            DatabaseError::Sqlite(err) => write!(f, "SQLite error: {}", err),
+            #[cfg(feature = "json")]
+            DatabaseError::Json(err) => write!(f, "JSON error: {}", err),
+        }
+    }
+
+    /// Erreur d'E/S survenue pendant `op` sur `path`.
+    pub fn io(op: IoOp, path: impl Into<PathBuf>, source: io::Error) -> Self {
+        DatabaseError::Io {
+            op,
+            path: path.into(),
+            source,
+        }
+    }
+
+    /// Vrai pour les erreurs signalant un journal endommagé.
+    pub fn is_corruption(&self) -> bool {
+        matches!(
+            self,
+            DatabaseError::CorruptedData
+                | DatabaseError::CorruptedRecord { .. }
+                | DatabaseError::IndexMismatch { .. }
+        )
+    }
+
+    /// Situe une corruption détectée en lisant l'enregistrement à `offset`.
+    pub(crate) fn at_record(self, path: &Path, offset: u64) -> Self {
+        match self {
+            DatabaseError::CorruptedData | DatabaseError::InvalidFormat => {
+                DatabaseError::CorruptedRecord {
+                    path: path.to_path_buf(),
+                    offset,
+                }
+            }
+            err => err,
+        }
+    }
+}
+
+/// Ajoute l'opération et le fichier concernés à une erreur d'E/S.
+pub(crate) trait IoContext<T> {
+    fn context(self, op: IoOp, path: &Path) -> Result<T, DatabaseError>;
+}
+
+impl<T> IoContext<T> for io::Result<T> {
+    fn context(self, op: IoOp, path: &Path) -> Result<T, DatabaseError> {
+        self.map_err(|source| DatabaseError::io(op, path, source))
     }
 }
 
@@ -43,4 +497,30 @@ impl From<std::string::FromUtf8Error> for DatabaseError {
     }
 }
 
-impl std::error::Error for DatabaseError {}
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for DatabaseError {
+    fn from(err: rusqlite::Error) -> Self {
+        DatabaseError::Sqlite(err)
+    }
+}
+
+#[cfg(feature = "json")]
+impl From<serde_json::Error> for DatabaseError {
+    fn from(err: serde_json::Error) -> Self {
+        DatabaseError::Json(err)
+    }
+}
+
+impl std::error::Error for DatabaseError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            DatabaseError::Io { source, .. } => Some(source),
+            DatabaseError::Utf8(err) => Some(err),
+            #[cfg(feature = "sqlite")]
+            DatabaseError::Sqlite(err) => Some(err),
+            #[cfg(feature = "json")]
+            DatabaseError::Json(err) => Some(err),
+            _ => None,
+        }
+    }
+}
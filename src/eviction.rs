@@ -0,0 +1,53 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Politique d'éviction automatique des clés.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Eviction {
+    /// Aucune éviction (comportement historique).
+    #[default]
+    None,
+    /// Supprime (tombstone) les clés les moins récemment utilisées dès que le
+    /// nombre de clés vivantes ou leur taille cumulée dépasse la limite fixée.
+    Lru {
+        max_keys: Option<usize>,
+        max_bytes: Option<u64>,
+    },
+}
+
+/// Ordre de récence des clés vivantes, de la plus ancienne à la plus récente.
+#[derive(Default)]
+pub(crate) struct LruTracker {
+    clock: u64,
+    ticks: HashMap<Vec<u8>, u64>,
+    order: BTreeMap<u64, Vec<u8>>,
+}
+
+impl LruTracker {
+    /// Marque `key` comme la plus récemment utilisée.
+    pub(crate) fn touch(&mut self, key: &[u8]) {
+        self.clock += 1;
+        if let Some(tick) = self.ticks.get_mut(key) {
+            let owned = self.order.remove(tick).unwrap_or_else(|| key.to_vec());
+            *tick = self.clock;
+            self.order.insert(self.clock, owned);
+        } else {
+            self.ticks.insert(key.to_vec(), self.clock);
+            self.order.insert(self.clock, key.to_vec());
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &[u8]) {
+        if let Some(tick) = self.ticks.remove(key) {
+            self.order.remove(&tick);
+        }
+    }
+
+    /// Clé la moins récemment utilisée.
+    pub(crate) fn oldest(&self) -> Option<&Vec<u8>> {
+        self.order.values().next()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.ticks.len()
+    }
+}
@@ -0,0 +1,109 @@
+//! Points d'injection de pannes pour tester la résistance aux crashs.
+//!
+//! Sans la fonctionnalité `failpoints`, les points d'injection ne coûtent rien.
+//! Avec elle, un test active un point par son nom :
+//!
+//! - `set.before_write`, `set.write`, `set.after_write` : chemin d'écriture
+//!   (`set`, `delete` et opérandes de fusion) ;
+//! - `compact.copy`, `compact.before_rename`, `compact.after_rename` : compaction ;
+//! - `recovery.read` : relecture du journal à l'ouverture.
+
+#[cfg(feature = "failpoints")]
+use std::collections::HashMap;
+use std::io::{self, Write};
+#[cfg(feature = "failpoints")]
+use std::sync::{LazyLock, Mutex};
+
+/// Effet d'un point d'injection actif.
+#[cfg(feature = "failpoints")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailAction {
+    /// Retourne une erreur d'E/S.
+    Error,
+    /// N'écrit que les `n` premiers octets puis retourne une erreur d'E/S ;
+    /// sur un point sans écriture, équivaut à `Error`.
+    ShortWrite(usize),
+    /// Comme `ShortWrite`, l'erreur étant celle d'un disque plein
+    /// (`ErrorKind::StorageFull`).
+    DiskFull(usize),
+    /// Panique, simulant un crash : rien de ce qui suit n'est exécuté.
+    Panic,
+}
+
+#[cfg(feature = "failpoints")]
+static REGISTRY: LazyLock<Mutex<HashMap<String, FailAction>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Active le point `name` jusqu'à `remove` ou `clear`.
+#[cfg(feature = "failpoints")]
+pub fn configure(name: &str, action: FailAction) {
+    registry().insert(name.to_string(), action);
+}
+
+/// Désactive le point `name`.
+#[cfg(feature = "failpoints")]
+pub fn remove(name: &str) {
+    registry().remove(name);
+}
+
+/// Désactive tous les points.
+#[cfg(feature = "failpoints")]
+pub fn clear() {
+    registry().clear();
+}
+
+#[cfg(feature = "failpoints")]
+fn registry() -> std::sync::MutexGuard<'static, HashMap<String, FailAction>> {
+    REGISTRY
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(feature = "failpoints")]
+fn action(name: &str) -> Option<FailAction> {
+    registry().get(name).copied()
+}
+
+#[cfg(feature = "failpoints")]
+fn injected(name: &str) -> io::Error {
+    io::Error::other(format!("panne injectée : {}", name))
+}
+
+/// Déclenche le point `name` s'il est actif.
+#[cfg(feature = "failpoints")]
+pub(crate) fn hit(name: &str) -> io::Result<()> {
+    match action(name) {
+        None => Ok(()),
+        Some(FailAction::Panic) => panic!("panne injectée : {}", name),
+        Some(FailAction::DiskFull(_)) => Err(io::Error::from(io::ErrorKind::StorageFull)),
+        Some(_) => Err(injected(name)),
+    }
+}
+
+#[cfg(not(feature = "failpoints"))]
+#[inline(always)]
+pub(crate) fn hit(_name: &str) -> io::Result<()> {
+    Ok(())
+}
+
+/// `write_all` pouvant être tronqué par le point `name`.
+pub(crate) fn write_all(name: &str, writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    #[cfg(feature = "failpoints")]
+    match action(name) {
+        None => {}
+        Some(FailAction::ShortWrite(len)) => {
+            writer.write_all(&bytes[..len.min(bytes.len())])?;
+            writer.flush()?;
+            return Err(injected(name));
+        }
+        Some(FailAction::DiskFull(len)) => {
+            writer.write_all(&bytes[..len.min(bytes.len())])?;
+            writer.flush()?;
+            return Err(io::Error::from(io::ErrorKind::StorageFull));
+        }
+        Some(_) => hit(name)?,
+    }
+    #[cfg(not(feature = "failpoints"))]
+    let _ = name;
+    writer.write_all(bytes)
+}
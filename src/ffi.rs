@@ -0,0 +1,176 @@
+//! Interface C minimale autour de [`MyDatabase`].
+//!
+//! Toutes les fonctions retournent `0` en cas de succès, `1` si la clé est
+//! absente (`rdb_get`) et `-1` en cas d'erreur. Le prototype C correspondant
+//! se trouve dans `include/rdb.h`.
+
+use crate::db::{DatabaseConfig, MyDatabase};
+use crate::error::DatabaseError;
+use std::ffi::{CStr, c_char, c_int};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::PathBuf;
+use std::ptr;
+
+const RDB_OK: c_int = 0;
+const RDB_NOT_FOUND: c_int = 1;
+const RDB_ERROR: c_int = -1;
+
+/// Handle opaque exposé aux appelants C.
+pub struct RdbHandle {
+    db: MyDatabase,
+}
+
+/// Ouvre (ou crée) une base. `max_size == 0` désactive la compaction automatique.
+/// Retourne `NULL` en cas d'erreur.
+///
+/// # Safety
+/// `path` doit pointer vers une chaîne C valide terminée par un octet nul.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rdb_open(path: *const c_char, max_size: u64) -> *mut RdbHandle {
+    if path.is_null() {
+        return ptr::null_mut();
+    }
+    let path = match unsafe { CStr::from_ptr(path) }.to_str() {
+        Ok(path) => PathBuf::from(path),
+        Err(_) => return ptr::null_mut(),
+    };
+
+    let config = DatabaseConfig {
+        file_path: path,
+        max_size,
+        ..DatabaseConfig::default()
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| MyDatabase::new(config))) {
+        Ok(Ok(db)) => Box::into_raw(Box::new(RdbHandle { db })),
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Lit une valeur. En cas de succès, `*out_value` reçoit un tampon à libérer
+/// avec `rdb_free` et `*out_len` sa taille.
+///
+/// # Safety
+/// `handle` doit provenir de `rdb_open`, `key` pointer vers `key_len` octets
+/// lisibles et `out_value`/`out_len` être des pointeurs valides en écriture.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rdb_get(
+    handle: *const RdbHandle,
+    key: *const u8,
+    key_len: usize,
+    out_value: *mut *mut u8,
+    out_len: *mut usize,
+) -> c_int {
+    if handle.is_null() || out_value.is_null() || out_len.is_null() {
+        return RDB_ERROR;
+    }
+    let Some(key) = (unsafe { bytes_from_raw(key, key_len) }) else {
+        return RDB_ERROR;
+    };
+    let db = unsafe { &(*handle).db };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| db.get_required(key))) {
+        Ok(Ok(value)) => {
+            let boxed = value.into_boxed_slice();
+            unsafe {
+                *out_len = boxed.len();
+                *out_value = Box::into_raw(boxed) as *mut u8;
+            }
+            RDB_OK
+        }
+        Ok(Err(DatabaseError::KeyNotFound(_))) => {
+            unsafe {
+                *out_len = 0;
+                *out_value = ptr::null_mut();
+            }
+            RDB_NOT_FOUND
+        }
+        _ => RDB_ERROR,
+    }
+}
+
+/// Ajoute ou met à jour une valeur.
+///
+/// # Safety
+/// `handle` doit provenir de `rdb_open`, `key` et `value` pointer vers
+/// respectivement `key_len` et `value_len` octets lisibles.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rdb_set(
+    handle: *const RdbHandle,
+    key: *const u8,
+    key_len: usize,
+    value: *const u8,
+    value_len: usize,
+) -> c_int {
+    if handle.is_null() {
+        return RDB_ERROR;
+    }
+    let (Some(key), Some(value)) = (unsafe { bytes_from_raw(key, key_len) }, unsafe {
+        bytes_from_raw(value, value_len)
+    }) else {
+        return RDB_ERROR;
+    };
+    let db = unsafe { &(*handle).db };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| db.set(key.to_vec(), value.to_vec()))) {
+        Ok(Ok(_)) => RDB_OK,
+        _ => RDB_ERROR,
+    }
+}
+
+/// Supprime une clé (tombstone).
+///
+/// # Safety
+/// `handle` doit provenir de `rdb_open` et `key` pointer vers `key_len` octets lisibles.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rdb_delete(
+    handle: *const RdbHandle,
+    key: *const u8,
+    key_len: usize,
+) -> c_int {
+    if handle.is_null() {
+        return RDB_ERROR;
+    }
+    let Some(key) = (unsafe { bytes_from_raw(key, key_len) }) else {
+        return RDB_ERROR;
+    };
+    let db = unsafe { &(*handle).db };
+
+    match panic::catch_unwind(AssertUnwindSafe(|| db.delete(key.to_vec()))) {
+        Ok(Ok(())) => RDB_OK,
+        _ => RDB_ERROR,
+    }
+}
+
+/// Libère un tampon retourné par `rdb_get`.
+///
+/// # Safety
+/// `value`/`len` doivent provenir d'un appel réussi à `rdb_get` et n'être libérés qu'une fois.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rdb_free(value: *mut u8, len: usize) {
+    if value.is_null() {
+        return;
+    }
+    drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(value, len)) });
+}
+
+/// Ferme la base et libère le handle.
+///
+/// # Safety
+/// `handle` doit provenir de `rdb_open` et ne plus être utilisé ensuite.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn rdb_close(handle: *mut RdbHandle) {
+    if handle.is_null() {
+        return;
+    }
+    drop(unsafe { Box::from_raw(handle) });
+}
+
+unsafe fn bytes_from_raw<'a>(data: *const u8, len: usize) -> Option<&'a [u8]> {
+    if len == 0 {
+        return Some(&[]);
+    }
+    if data.is_null() {
+        return None;
+    }
+    Some(unsafe { std::slice::from_raw_parts(data, len) })
+}
@@ -0,0 +1,204 @@
+use crate::db::{DatabaseConfig, FileSwap, LogReader, MyDatabase, RecoveryOptions};
+use crate::error::{DatabaseError, IoContext, IoOp};
+use std::fs::{File, Metadata};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+impl MyDatabase {
+    /// Ouvre en lecture seule un journal qu'un autre processus est en train
+    /// d'écrire. Les écritures et la compaction échouent avec
+    /// `DatabaseError::ReadOnly`.
+    ///
+    /// `refresh` intègre les enregistrements ajoutés depuis ; un
+    /// enregistrement encore incomplet en fin de journal est laissé pour le
+    /// suivant. Si l'écrivain a compacté, l'index est reconstruit depuis le
+    /// nouveau journal ; d'ici là, une lecture peut échouer avec
+    /// `DatabaseError::IndexMismatch`. Avec `poll`, un fil appelle `refresh`
+    /// à cet intervalle tant que la base est ouverte.
+    pub fn open_follower(
+        config: DatabaseConfig,
+        poll: Option<Duration>,
+    ) -> Result<Self, DatabaseError> {
+        let db = Self::open_mode(config, RecoveryOptions::default(), true)?;
+        if let Some(poll) = poll {
+            db.spawn_follower(poll);
+        }
+        Ok(db)
+    }
+
+    /// Appelle `refresh` toutes les `poll` jusqu'à la fermeture de la base
+    /// (dernier clone abandonné). Une erreur est retentée au tour suivant ;
+    /// les corruptions restent visibles dans `health`.
+    fn spawn_follower(&self, poll: Duration) {
+        let config = self.config.clone();
+        let shared = Arc::downgrade(&self.shared);
+        thread::spawn(move || {
+            loop {
+                thread::sleep(poll);
+                let Some(shared) = shared.upgrade() else {
+                    return;
+                };
+                let db = MyDatabase {
+                    config: config.clone(),
+                    shared,
+                };
+                let _ = db.refresh();
+            }
+        });
+    }
+
+    /// Vrai si le fichier désigné par le chemin du journal n'est plus celui
+    /// ouvert par la base : l'écrivain l'a remplacé en compactant. Hors Unix,
+    /// seul un journal devenu plus court que `indexed_end` est détecté.
+    pub(crate) fn log_replaced(&self, indexed_end: u64) -> Result<bool, DatabaseError> {
+        if self.file_size()? < indexed_end {
+            return Ok(true);
+        }
+        let path = &self.config.file_path;
+        let held = self.lock_file()?.metadata().context(IoOp::LogSize, path)?;
+        let current = std::fs::metadata(path).context(IoOp::LogSize, path)?;
+        Ok(!same_file(&held, &current))
+    }
+
+    /// Passe au journal qui a remplacé celui de la base et reconstruit
+    /// l'index. L'appelant doit détenir le verrou `access` en écriture.
+    /// Retourne le nombre de clés vivantes.
+    pub(crate) fn follow_replaced_log(&self) -> Result<usize, DatabaseError> {
+        let path = &self.config.file_path;
+        let file = File::open(path).context(IoOp::LogOpen, path)?;
+        let held = file.try_clone().context(IoOp::LogOpen, path)?;
+        let dictionary = LogReader::from_file(held, path).dictionary()?;
+        {
+            let mut file_guard = self.lock_file()?;
+            let mut dictionary_guard = self.lock_dictionary()?;
+            let swap = FileSwap::begin(&self.shared);
+            *file_guard = file;
+            *dictionary_guard = dictionary;
+            swap.commit();
+        }
+        self.reindex_locked()
+    }
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    (a.dev(), a.ino()) == (b.dev(), b.ino())
+}
+
+#[cfg(not(unix))]
+fn same_file(_: &Metadata, _: &Metadata) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{DataEntry, EntryType};
+    use crate::testutil::TempDir;
+    use std::fs::OpenOptions;
+    use std::io::Write;
+    use std::time::Instant;
+
+    #[test]
+    fn follower_refreshes_from_the_writer() {
+        let dir = TempDir::new("follower-refresh");
+        let writer = dir.open();
+        writer.set(b"a".to_vec(), b"1".to_vec()).unwrap();
+        let follower = MyDatabase::open_follower(dir.config(), None).unwrap();
+        assert_eq!(follower.get(b"a").unwrap(), Some(b"1".to_vec()));
+
+        writer.set(b"b".to_vec(), b"2".to_vec()).unwrap();
+        assert_eq!(follower.get(b"b").unwrap(), None);
+        assert_eq!(follower.refresh().unwrap(), 1);
+        assert_eq!(follower.get(b"b").unwrap(), Some(b"2".to_vec()));
+
+        assert!(matches!(
+            follower.set(b"c".to_vec(), b"3".to_vec()),
+            Err(DatabaseError::ReadOnly)
+        ));
+        assert!(matches!(follower.compact(), Err(DatabaseError::ReadOnly)));
+    }
+
+    #[test]
+    fn incomplete_tail_is_left_for_the_next_refresh() {
+        let dir = TempDir::new("follower-tail");
+        let writer = dir.open();
+        let follower = MyDatabase::open_follower(dir.config(), None).unwrap();
+        drop(writer);
+
+        let mut record = Vec::new();
+        DataEntry {
+            entry_type: EntryType::Data,
+            key: b"k".to_vec(),
+            value: b"value".to_vec(),
+        }
+        .write_to(&mut record);
+        let mut log = OpenOptions::new()
+            .append(true)
+            .open(&dir.config().file_path)
+            .unwrap();
+        log.write_all(&record[..record.len() / 2]).unwrap();
+        assert_eq!(follower.refresh().unwrap(), 0);
+        log.write_all(&record[record.len() / 2..]).unwrap();
+        assert_eq!(follower.refresh().unwrap(), 1);
+        assert_eq!(follower.get(b"k").unwrap(), Some(b"value".to_vec()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follower_switches_to_the_compacted_log() {
+        let dir = TempDir::new("follower-compaction");
+        let writer = dir.open();
+        writer.set(b"a".to_vec(), b"1".to_vec()).unwrap();
+        writer.set(b"a".to_vec(), b"2".to_vec()).unwrap();
+        writer.set(b"b".to_vec(), b"3".to_vec()).unwrap();
+        let follower = MyDatabase::open_follower(dir.config(), None).unwrap();
+
+        writer.delete(b"b".to_vec()).unwrap();
+        writer.compact().unwrap();
+        assert_eq!(follower.refresh().unwrap(), 1);
+        assert_eq!(follower.get(b"a").unwrap(), Some(b"2".to_vec()));
+        assert_eq!(follower.get(b"b").unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follower_reads_values_compressed_with_the_new_dictionary() {
+        let dir = TempDir::new("follower-dictionary");
+        let writer = MyDatabase::new(DatabaseConfig {
+            compression_dictionary: true,
+            ..dir.config()
+        })
+        .unwrap();
+        let value = |i: u32| format!(r#"{{"id":{i},"name":"utilisateur {i}"}}"#).into_bytes();
+        for i in 0..20 {
+            writer.set(format!("k{i}").into_bytes(), value(i)).unwrap();
+        }
+        let follower = MyDatabase::open_follower(dir.config(), None).unwrap();
+
+        writer.compact().unwrap();
+        writer.set(b"k20".to_vec(), value(20)).unwrap();
+        follower.refresh().unwrap();
+        for i in 0..=20 {
+            let key = format!("k{i}");
+            assert_eq!(follower.get(key.as_bytes()).unwrap(), Some(value(i)));
+        }
+    }
+
+    #[test]
+    fn polling_follower_sees_new_writes() {
+        let dir = TempDir::new("follower-poll");
+        let writer = dir.open();
+        let follower =
+            MyDatabase::open_follower(dir.config(), Some(Duration::from_millis(1))).unwrap();
+        writer.set(b"k".to_vec(), b"v".to_vec()).unwrap();
+
+        let started = Instant::now();
+        while follower.get(b"k").unwrap().is_none() {
+            assert!(started.elapsed() < Duration::from_secs(10));
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use crate::db::MyDatabase;
+use crate::error::DatabaseError;
+use crate::schedule::BackpressurePolicy;
+use std::fs::OpenOptions;
+use std::sync::atomic::Ordering;
+
+/// État de santé de la base, destiné aux sondes de supervision.
+#[derive(Debug, Clone)]
+pub struct HealthStatus {
+    /// Le journal est lisible.
+    pub can_read: bool,
+    /// Une écriture serait acceptée : base ouverte en écriture, journal
+    /// ouvrable en ajout, ni disque plein, ni contre-pression, ni plafond
+    /// `enforce_max_size` atteints.
+    pub can_write: bool,
+    /// Horodatage (ms UNIX) du dernier `fsync` du journal, `None` si aucun.
+    pub last_sync: Option<u64>,
+    /// Issue de la dernière compaction, `None` si aucune depuis l'ouverture.
+    pub last_compaction: Option<CompactionOutcome>,
+    /// Une corruption du journal a été rencontrée depuis l'ouverture.
+    pub corruption_detected: bool,
+}
+
+/// Résultat d'une compaction.
+#[derive(Debug, Clone)]
+pub struct CompactionOutcome {
+    /// Horodatage (ms UNIX) de fin.
+    pub finished_at: u64,
+    /// Message d'erreur si la compaction a échoué.
+    pub error: Option<String>,
+    /// Enregistrements mis en quarantaine (`compaction_quarantine`).
+    pub quarantined: usize,
+}
+
+impl HealthStatus {
+    /// Vrai si la base lit, écrit, n'a pas détecté de corruption et que sa
+    /// dernière compaction n'a pas échoué.
+    pub fn is_healthy(&self) -> bool {
+        self.can_read
+            && self.can_write
+            && !self.corruption_detected
+            && self
+                .last_compaction
+                .as_ref()
+                .is_none_or(|outcome| outcome.error.is_none())
+    }
+}
+
+impl MyDatabase {
+    /// Sonde l'état de la base sans rien écrire.
+    pub fn health(&self) -> HealthStatus {
+        let can_read = self
+            .read_access()
+            .and_then(|_guard| self.log_reader())
+            .is_ok();
+        let can_write = can_read
+            && OpenOptions::new()
+                .append(true)
+                .open(&self.config.file_path)
+                .is_ok()
+            && self.write_admitted();
+        let last_sync = match self.shared.last_sync.load(Ordering::Relaxed) {
+            0 => None,
+            at => Some(at),
+        };
+        let last_compaction = self
+            .shared
+            .last_compaction
+            .lock()
+            .ok()
+            .and_then(|outcome| outcome.clone());
+
+        HealthStatus {
+            can_read,
+            can_write,
+            last_sync,
+            last_compaction,
+            corruption_detected: self.shared.corruption_detected.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Vrai si ni la lecture seule, ni un disque plein, ni `backpressure`, ni
+    /// `enforce_max_size` ne bloqueraient une écriture maintenant.
+    fn write_admitted(&self) -> bool {
+        if self.shared.read_only || self.shared.disk_full.load(Ordering::Relaxed) {
+            return false;
+        }
+        let hard_limit = match self.config.backpressure {
+            BackpressurePolicy::Inline => None,
+            BackpressurePolicy::Stall { hard_limit, .. }
+            | BackpressurePolicy::Reject { hard_limit } => Some(hard_limit),
+        };
+        if hard_limit.is_some_and(|limit| self.file_size().is_ok_and(|size| size >= limit)) {
+            return false;
+        }
+        if self.config.enforce_max_size && self.config.max_size > 0 {
+            let live_bytes = self.shared.live_bytes.load(Ordering::Relaxed);
+            return live_bytes < self.config.max_size;
+        }
+        true
+    }
+
+    /// Mémorise une corruption rencontrée pour `health`.
+    pub(crate) fn note_corruption(&self, err: &DatabaseError) {
+        if err.is_corruption() {
+            self.shared
+                .corruption_detected
+                .store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// Mémorise l'issue d'une compaction pour `health`.
+    /// Une annulation demandée n'est pas un échec et n'est pas retenue.
+    pub(crate) fn record_compaction(&self, result: &Result<usize, DatabaseError>) {
+        if matches!(result, Err(DatabaseError::CompactionCancelled)) {
+            return;
+        }
+        match result {
+            Ok(_) => self.shared.disk_full.store(false, Ordering::Relaxed),
+            Err(err) => self.note_corruption(err),
+        }
+        let outcome = CompactionOutcome {
+            finished_at: self.config.clock.now_millis(),
+            error: result.as_ref().err().map(ToString::to_string),
+            quarantined: *result.as_ref().unwrap_or(&0),
+        };
+        if let Ok(mut last) = self.shared.last_compaction.lock() {
+            *last = Some(outcome);
+        }
+    }
+}
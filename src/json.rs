@@ -0,0 +1,162 @@
+use crate::db::MyDatabase;
+use crate::error::DatabaseError;
+use crate::merge::{MergeOp, Value};
+use serde_json::Value as Json;
+
+/// Segment d'un chemin de type JSONPath : `$`, `.champ`, `["champ"]`, `[index]`.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+impl MyDatabase {
+    /// Écrit `value` (texte JSON) au chemin `path` du document stocké sous
+    /// `key`. Seul un enregistrement delta est ajouté au journal ; les objets
+    /// intermédiaires manquants sont créés.
+    pub fn json_set(&self, key: Vec<u8>, path: &str, value: &str) -> Result<(), DatabaseError> {
+        let segments = parse_path(path)?;
+        let parsed: Json = serde_json::from_str(value)?;
+        let value = serde_json::to_vec(&parsed)?;
+        self.update(key, |current| {
+            let bytes = match current {
+                None => None,
+                Some(Value::Bytes(bytes)) => Some(bytes.as_slice()),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            // Valide l'écriture avant de l'ajouter au journal.
+            let mut document = parse_document(bytes)?;
+            set(&mut document, &segments, parsed)?;
+            let op = MergeOp::JsonSet {
+                path: path.to_string(),
+                value,
+            };
+            Ok((Some(op), ()))
+        })
+    }
+
+    /// Lit la valeur au chemin `path` du document stocké sous `key`,
+    /// sérialisée en JSON. `None` si la clé ou le chemin n'existe pas.
+    pub fn json_get(&self, key: &[u8], path: &str) -> Result<Option<String>, DatabaseError> {
+        let segments = parse_path(path)?;
+        let document = match self.read_value(key)? {
+            None => return Ok(None),
+            Some(Value::Bytes(bytes)) => parse_document(Some(&bytes))?,
+            Some(_) => return Err(DatabaseError::WrongType),
+        };
+        get(&document, &segments)
+            .map(serde_json::to_string)
+            .transpose()
+            .map_err(DatabaseError::from)
+    }
+}
+
+/// Applique une écriture JSON à une valeur simple ; utilisé par le repli des
+/// opérandes de fusion.
+pub(crate) fn apply_set(
+    bytes: Option<&[u8]>,
+    path: &str,
+    value: &[u8],
+) -> Result<Vec<u8>, DatabaseError> {
+    let segments = parse_path(path)?;
+    let mut document = parse_document(bytes)?;
+    set(&mut document, &segments, serde_json::from_slice(value)?)?;
+    Ok(serde_json::to_vec(&document)?)
+}
+
+/// Un document absent est un objet vide.
+fn parse_document(bytes: Option<&[u8]>) -> Result<Json, DatabaseError> {
+    match bytes {
+        None => Ok(Json::Object(Default::default())),
+        Some(bytes) => Ok(serde_json::from_slice(bytes)?),
+    }
+}
+
+pub(crate) fn parse_path(path: &str) -> Result<Vec<Segment>, DatabaseError> {
+    let invalid = || DatabaseError::ParseError(format!("chemin JSON invalide: '{}'", path));
+    let rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut chars = rest.chars().peekable();
+    let mut segments = Vec::new();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                let mut field = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c == '.' || c == '[' {
+                        break;
+                    }
+                    field.push(c);
+                    chars.next();
+                }
+                if field.is_empty() {
+                    return Err(invalid());
+                }
+                segments.push(Segment::Field(field));
+            }
+            '[' => {
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(invalid()),
+                    }
+                }
+                let quoted = inner
+                    .strip_prefix('"')
+                    .and_then(|s| s.strip_suffix('"'))
+                    .or_else(|| inner.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')));
+                match quoted {
+                    Some(field) => segments.push(Segment::Field(field.to_string())),
+                    None => segments.push(Segment::Index(inner.parse().map_err(|_| invalid())?)),
+                }
+            }
+            _ => return Err(invalid()),
+        }
+    }
+
+    Ok(segments)
+}
+
+fn get<'a>(mut node: &'a Json, segments: &[Segment]) -> Option<&'a Json> {
+    for segment in segments {
+        node = match segment {
+            Segment::Field(field) => node.as_object()?.get(field)?,
+            Segment::Index(index) => node.as_array()?.get(*index)?,
+        };
+    }
+    Some(node)
+}
+
+/// Un index égal à la longueur du tableau ajoute un élément en fin.
+fn set(node: &mut Json, segments: &[Segment], value: Json) -> Result<(), DatabaseError> {
+    let Some((segment, rest)) = segments.split_first() else {
+        *node = value;
+        return Ok(());
+    };
+
+    let child = match (segment, node) {
+        (Segment::Field(field), Json::Object(map)) => map
+            .entry(field.clone())
+            .or_insert_with(|| Json::Object(Default::default())),
+        (Segment::Index(index), Json::Array(items)) if *index < items.len() => &mut items[*index],
+        (Segment::Index(index), Json::Array(items)) if *index == items.len() => {
+            items.push(Json::Object(Default::default()));
+            &mut items[*index]
+        }
+        (Segment::Index(index), _) => {
+            return Err(DatabaseError::ParseError(format!(
+                "index JSON hors limites: {}",
+                index
+            )));
+        }
+        (Segment::Field(field), _) => {
+            return Err(DatabaseError::ParseError(format!(
+                "champ JSON '{}' sur une valeur qui n'est pas un objet",
+                field
+            )));
+        }
+    };
+    set(child, rest, value)
+}
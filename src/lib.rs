@@ -1,9 +1,58 @@
-mod codec;
+mod clock;
+pub mod codec;
+mod collections;
+mod compaction;
 mod db;
 mod error;
+mod eviction;
+#[cfg(feature = "failpoints")]
+pub mod failpoints;
+#[cfg(not(feature = "failpoints"))]
+mod failpoints;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod follower;
+mod health;
+#[cfg(feature = "json")]
+mod json;
+mod locks;
+mod merge;
+mod quarantine;
+mod schedule;
+mod sharded;
+mod snapshot;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod stats;
+#[cfg(test)]
+mod testutil;
+mod text;
+mod throttle;
+mod token;
+pub mod tools;
 
-pub use crate::codec::{Compressor, DataEntry, EntryType, Lz77};
+pub use crate::clock::{Clock, ManualClock, SystemClock};
+pub use crate::codec::{
+    Compressor, DataEntry, DecodedRecord, Dictionary, EntryType, Lz77, MAX_META_LEN, Record,
+};
+pub use crate::compaction::{CompactionHandle, CompactionProgress};
+#[allow(deprecated)]
+pub use crate::db::append_entry;
 pub use crate::db::{
-    append_entry, DatabaseConfig, IndexEntry, LogIter, LogRecord, MyDatabase, SharedState,
+    ConflictPolicy, DatabaseConfig, IndexEntry, LogIter, LogRecord, MyDatabase, RecoveryMode,
+    RecoveryOptions, RecoverySummary, SharedState, ValueMeta, ValueSize, ValueWithMeta,
+    WarmupTarget, WriteOptions,
+};
+pub use crate::error::{DatabaseError, IoOp, Language, Localized, LockResource};
+pub use crate::eviction::Eviction;
+pub use crate::health::{CompactionOutcome, HealthStatus};
+pub use crate::schedule::{BackpressurePolicy, CompactionSchedule};
+pub use crate::sharded::ShardedDatabase;
+pub use crate::snapshot::{KeyIter, ScanIter};
+pub use crate::stats::{
+    CompactionEstimate, CompressionStats, CompressorStats, DatabaseStats, LockContentionStats,
+    LockWaitStats, ThrottleStats,
 };
-pub use crate::error::DatabaseError;
+pub use crate::text::TextIndexConfig;
+pub use crate::throttle::ThrottleState;
+pub use crate::token::SyncToken;
@@ -0,0 +1,250 @@
+use crate::codec::Dictionary;
+use crate::db::{IndexEntry, MyDatabase, SharedState, live_size};
+use crate::error::{DatabaseError, IoContext, IoOp, LockResource};
+use crate::stats::LockKind;
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::sync::{
+    MutexGuard, PoisonError, RwLockReadGuard, RwLockWriteGuard, TryLockError, TryLockResult,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+type Index = HashMap<Vec<u8>, IndexEntry>;
+
+/// Intervalle entre deux tentatives de prise de verrou avec `lock_timeout`.
+const LOCK_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+impl SharedState {
+    /// Vrai si un panic a empoisonné l'un des verrous portant des données.
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.access.is_poisoned()
+            || self.file.is_poisoned()
+            || self.index.is_poisoned()
+            || self.recency.is_poisoned()
+            || self.text.is_poisoned()
+            || self.blobs.is_poisoned()
+    }
+}
+
+impl MyDatabase {
+    /// Prend le verrou `access` en lecture, après avoir remis la base en état
+    /// si un panic antérieur a empoisonné l'un de ses verrous.
+    pub(crate) fn read_access(&self) -> Result<RwLockReadGuard<'_, ()>, DatabaseError> {
+        if self.shared.is_poisoned() {
+            self.recover_poisoned()?;
+        }
+        let started = Instant::now();
+        let guard = match self.config.lock_timeout {
+            None => self
+                .shared
+                .access
+                .read()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Access))?,
+            Some(timeout) => acquire_within(timeout, LockResource::Access, || {
+                self.shared.access.try_read()
+            })?,
+        };
+        self.shared
+            .lock_waits
+            .record(LockKind::AccessShared, started.elapsed());
+        Ok(guard)
+    }
+
+    /// Comme `read_access`, en écriture.
+    pub(crate) fn write_access(&self) -> Result<RwLockWriteGuard<'_, ()>, DatabaseError> {
+        if self.shared.is_poisoned() {
+            self.recover_poisoned()?;
+        }
+        let started = Instant::now();
+        let guard = match self.config.lock_timeout {
+            None => self
+                .shared
+                .access
+                .write()
+                .map_err(|_| DatabaseError::LockPoisoned(LockResource::Access))?,
+            Some(timeout) => acquire_within(timeout, LockResource::Access, || {
+                self.shared.access.try_write()
+            })?,
+        };
+        self.shared
+            .lock_waits
+            .record(LockKind::AccessExclusive, started.elapsed());
+        Ok(guard)
+    }
+
+    pub(crate) fn index_read(&self) -> Result<RwLockReadGuard<'_, Index>, DatabaseError> {
+        let started = Instant::now();
+        let guard = self
+            .shared
+            .index
+            .read()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::Index))?;
+        self.shared
+            .lock_waits
+            .record(LockKind::IndexShared, started.elapsed());
+        Ok(guard)
+    }
+
+    pub(crate) fn index_write(&self) -> Result<RwLockWriteGuard<'_, Index>, DatabaseError> {
+        let started = Instant::now();
+        let guard = self
+            .shared
+            .index
+            .write()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::Index))?;
+        self.shared
+            .lock_waits
+            .record(LockKind::IndexExclusive, started.elapsed());
+        Ok(guard)
+    }
+
+    pub(crate) fn lock_file(&self) -> Result<MutexGuard<'_, File>, DatabaseError> {
+        let started = Instant::now();
+        let guard = self
+            .shared
+            .file
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::File))?;
+        self.shared
+            .lock_waits
+            .record(LockKind::File, started.elapsed());
+        Ok(guard)
+    }
+
+    /// Dictionnaire de compression du journal courant ; remplacé sous le
+    /// verrou `access` en écriture, avec le journal.
+    pub(crate) fn lock_dictionary(
+        &self,
+    ) -> Result<MutexGuard<'_, Option<Arc<Dictionary>>>, DatabaseError> {
+        self.shared
+            .dictionary
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::Dictionary))
+    }
+
+    /// Enregistrements `Blob` du journal courant, par empreinte ; remplacés
+    /// avec le journal.
+    pub(crate) fn lock_blobs(
+        &self,
+    ) -> Result<MutexGuard<'_, HashMap<u64, IndexEntry>>, DatabaseError> {
+        self.shared
+            .blobs
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::Blobs))
+    }
+
+    /// Le verrou de compaction ne protège aucune donnée : l'état qu'une
+    /// compaction interrompue laisse derrière elle est repris par
+    /// `recover_poisoned` (son fichier temporaire est supprimé à l'ouverture
+    /// suivante).
+    pub(crate) fn lock_compaction(&self) -> Result<MutexGuard<'_, ()>, DatabaseError> {
+        let compaction = &self.shared.compaction;
+        let guard =
+            match self.config.lock_timeout {
+                None => compaction.lock().unwrap_or_else(PoisonError::into_inner),
+                Some(timeout) => acquire_within(timeout, LockResource::Compaction, || {
+                    match compaction.try_lock() {
+                        Err(TryLockError::Poisoned(poisoned)) => Ok(poisoned.into_inner()),
+                        result => result,
+                    }
+                })?,
+            };
+        compaction.clear_poison();
+        Ok(guard)
+    }
+
+    /// Reconstruit l'état partagé depuis le journal après un panic : le
+    /// descripteur est rouvert (une compaction a pu remplacer le fichier),
+    /// un enregistrement tronqué en fin de journal est retiré, puis l'index,
+    /// les valeurs partagées, la récence et l'index plein texte sont
+    /// recalculés. En lecture seule
+    /// (suiveur), le journal est rouvert sans écriture et n'est jamais
+    /// tronqué : la fin incomplète est celle que l'écrivain est en train
+    /// d'ajouter, intégrée par un `refresh` ultérieur. En cas d'échec, les
+    /// verrous restent empoisonnés et l'appel suivant retente la reprise.
+    fn recover_poisoned(&self) -> Result<(), DatabaseError> {
+        let shared = &self.shared;
+        let path = &self.config.file_path;
+        let _access_guard = shared
+            .access
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        if !shared.is_poisoned() {
+            // Un autre appel a terminé la reprise pendant l'attente.
+            return Ok(());
+        }
+
+        let mut file = shared.file.lock().unwrap_or_else(PoisonError::into_inner);
+        let read_only = shared.read_only;
+        let reopened = OpenOptions::new()
+            .read(true)
+            .append(!read_only)
+            .open(path)
+            .context(IoOp::PoisonReopen, path)?;
+        let replay = Self::recover_log(path, self.config.max_record_size)?;
+        let (mut index, mut blobs, end) = (replay.index, replay.blobs, replay.end);
+        let generation = self.generation();
+        for entry in index.values_mut().chain(blobs.values_mut()) {
+            entry.generation = generation;
+        }
+        if !read_only {
+            let len = reopened.metadata().context(IoOp::PoisonSize, path)?.len();
+            if len > end {
+                reopened.set_len(end).context(IoOp::PoisonTruncate, path)?;
+            }
+        }
+        *file = reopened;
+
+        shared
+            .live_bytes
+            .store(live_size(&index), Ordering::Relaxed);
+        shared
+            .blob_bytes
+            .store(live_size(&blobs), Ordering::Relaxed);
+        shared.indexed_end.store(end, Ordering::Relaxed);
+        *shared
+            .recency
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Self::initial_recency(&self.config, &index);
+        *shared.index.write().unwrap_or_else(PoisonError::into_inner) = index;
+        shared.index.clear_poison();
+        *shared.blobs.lock().unwrap_or_else(PoisonError::into_inner) = blobs;
+        shared.blobs.clear_poison();
+
+        let mut text = shared.text.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(config) = text.as_ref().map(|text| text.config()) {
+            *text = Some(self.build_text_index(config)?);
+        }
+
+        shared.text.clear_poison();
+        shared.recency.clear_poison();
+        shared.file.clear_poison();
+        shared.access.clear_poison();
+        shared.poison_recoveries.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Tente `try_lock` jusqu'à obtenir le verrou ou dépasser `timeout`.
+fn acquire_within<G>(
+    timeout: Duration,
+    resource: LockResource,
+    mut try_lock: impl FnMut() -> TryLockResult<G>,
+) -> Result<G, DatabaseError> {
+    let started = Instant::now();
+    loop {
+        match try_lock() {
+            Ok(guard) => return Ok(guard),
+            Err(TryLockError::Poisoned(_)) => return Err(DatabaseError::LockPoisoned(resource)),
+            Err(TryLockError::WouldBlock) if started.elapsed() >= timeout => {
+                return Err(DatabaseError::Timeout);
+            }
+            Err(TryLockError::WouldBlock) => {
+                thread::sleep(LOCK_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+            }
+        }
+    }
+}
@@ -1,190 +1,927 @@
-use rust_database::{DatabaseConfig, DatabaseError, MyDatabase};
+mod command;
+mod table;
+
+use command::{Command, CompactMode, ParseError, SetValue, Warmup};
+use rust_database::{
+    CompactionProgress, DatabaseConfig, DatabaseError, DatabaseStats, IoOp, Language, LogRecord,
+    MyDatabase, WarmupTarget, tools,
+};
 use std::fs;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::io::{self, BufRead, Write};
+use std::thread;
+use std::time::Duration;
+use table::{Cell, Format, JsonString, Table};
+
+/// `println!` du message `fr` ou `en` selon la langue `lang`, avec les
+/// mêmes arguments.
+macro_rules! say {
+    ($lang:expr, $fr:literal, $en:literal $(, $arg:expr)* $(,)?) => {
+        match $lang {
+            Language::Fr => println!($fr $(, $arg)*),
+            Language::En => println!($en $(, $arg)*),
+        }
+    };
+}
 
 /// Point d'entrée CLI (REPL).
 fn main() -> Result<(), DatabaseError> {
+    let lang = Language::from_env();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(subcommand) = args.first() {
+        return run_subcommand(lang, subcommand, &args[1..]);
+    }
+
     let config = DatabaseConfig::new();
     let db = MyDatabase::new(config)?;
 
     println!("=== Rust Database CLI (REPL) ===");
-    println!("Commandes disponibles:");
-    println!("  SET <clé> <valeur>  - Ajoute/met à jour une clé");
-    println!("  SET <clé> --file <chemin>  - Stocke le contenu d'un fichier");
-    println!("  GET <clé>           - Récupère une valeur");
-    println!("  GET <clé> --file <chemin>  - Écrit la valeur dans un fichier");
-    println!("  DELETE <clé>        - Supprime une clé (Tombstone)");
-    println!("  COMPACT             - Compacter le fichier de log");
-    println!("  LOG [--limit N]     - Affiche les entrées du journal");
-    println!("  EXIT                - Quitte le programme\n");
+    let recovery = db.recovery_summary();
+    let compacted = match (recovery.compacted, lang) {
+        (false, _) => "",
+        (true, Language::Fr) => ", compacté",
+        (true, Language::En) => ", compacted",
+    };
+    say!(
+        lang,
+        "Journal: {} enregistrement(s) relu(s), {} octet(s) tronqué(s), {:.0} % d'octets morts{}",
+        "Log: {} record(s) replayed, {} byte(s) truncated, {:.0} % dead bytes{}",
+        recovery.records_scanned,
+        recovery.truncated_bytes,
+        recovery.dead_ratio() * 100.0,
+        compacted
+    );
+    say!(
+        lang,
+        "Commandes disponibles (HELP <commande> pour le détail):",
+        "Available commands (HELP <command> for details):"
+    );
+    println!("{}", command::Overview);
 
     loop {
         print!("rdb > ");
-        io::stdout().flush().map_err(DatabaseError::Io)?;
+        io::stdout()
+            .flush()
+            .map_err(|err| DatabaseError::io(IoOp::TerminalWrite, "", err))?;
 
         let mut input = String::new();
-        io::stdin()
+        let read = io::stdin()
             .read_line(&mut input)
-            .map_err(DatabaseError::Io)?;
-
-        let input = input.trim();
-
-        if input.is_empty() {
-            continue;
+            .map_err(|err| DatabaseError::io(IoOp::TerminalRead, "", err))?;
+        if read == 0 {
+            // Fin de l'entrée (Ctrl-D ou fichier redirigé épuisé).
+            break;
         }
 
-        let parts: Vec<&str> = input.split_whitespace().collect();
-        let command = parts[0].to_uppercase();
-
-        match command.as_str() {
-            "SET" => {
-                if parts.len() < 3 {
-                    println!("Usage: SET <clé> <valeur>");
-                    println!("   ou: SET <clé> --file <chemin>");
-                    continue;
-                }
+        let command = match command::parse(&input) {
+            Ok(Some(command)) => command,
+            Ok(None) => continue,
+            Err(ParseError::Unknown(name)) => {
+                say!(
+                    lang,
+                    "Commande inconnue: {} (HELP liste les commandes)",
+                    "Unknown command: {} (HELP lists the commands)",
+                    name
+                );
+                continue;
+            }
+            Err(ParseError::UnterminatedQuote) if lang == Language::En => {
+                println!("Unterminated quote");
+                continue;
+            }
+            Err(e) => {
+                println!("{}", e);
+                continue;
+            }
+        };
+        let name = command.name();
 
-                let key = parts[1].as_bytes().to_vec();
-                let value = if parts[2] == "--file" {
-                    if parts.len() < 4 {
-                        println!("Usage: SET <clé> --file <chemin>");
-                        continue;
-                    }
-                    let path = PathBuf::from(parts[3]);
-                    match fs::read(&path) {
-                        Ok(bytes) => bytes,
+        match command {
+            Command::Set { key, value } => {
+                let (value, is_file) = match value {
+                    SetValue::Inline(value) => (value, false),
+                    SetValue::File(path) => match fs::read(&path) {
+                        Ok(bytes) => (bytes, true),
                         Err(e) => {
-                            println!("Erreur lecture fichier: {}", e);
+                            say!(lang, "Erreur lecture fichier: {}", "File read error: {}", e);
                             continue;
                         }
-                    }
-                } else {
-                    parts[2..].join(" ").as_bytes().to_vec()
+                    },
+                    SetValue::Lines { delimiter } => (read_lines(delimiter.as_deref())?, true),
                 };
 
-                let is_file = parts[2] == "--file";
                 match db.set(key.clone(), value.clone()) {
-                    Ok(_) if is_file => {
-                        println!("SET '{}' = <{} octets>", display_bytes(&key), value.len())
-                    }
+                    Ok(_) if is_file => say!(
+                        lang,
+                        "SET '{}' = <{} octets>",
+                        "SET '{}' = <{} bytes>",
+                        display_bytes(&key),
+                        value.len()
+                    ),
                     Ok(_) => println!(
                         "SET '{}' = '{}'",
                         display_bytes(&key),
                         display_bytes(&value)
                     ),
-                    Err(e) => println!("Erreur SET: {}", e),
-                }
-            }
-
-            "GET" => {
-                if parts.len() < 2 {
-                    println!("Usage: GET <clé>");
-                    println!("   ou: GET <clé> --file <chemin>");
-                    continue;
-                }
-
-                let key = parts[1].as_bytes().to_vec();
-
-                match db.get(&key) {
-                    Ok(Some(value)) => {
-                        if parts.len() >= 4 && parts[2] == "--file" {
-                            let path = PathBuf::from(parts[3]);
-                            match fs::write(&path, &value) {
-                                Ok(_) => println!(
-                                    "GET '{}' -> fichier écrit: {}",
-                                    display_bytes(&key),
-                                    path.display()
-                                ),
-                                Err(e) => println!("Erreur écriture fichier: {}", e),
-                            }
-                        } else {
-                            println!(
-                                "GET '{}' = '{}'",
-                                display_bytes(&key),
-                                display_bytes(&value)
-                            );
-                        }
-                    }
-                    Ok(None) => println!("Clé '{}' non trouvée", display_bytes(&key)),
-                    Err(e) => println!("Erreur GET: {}", e),
+                    Err(e) => print_error(lang, "SET", &e),
                 }
             }
 
-            "DELETE" => {
-                if parts.len() < 2 {
-                    println!("Usage: DELETE <clé>");
-                    continue;
-                }
+            Command::Get { key, file } => match db.get_required(&key) {
+                Ok(value) => match file {
+                    Some(path) => match fs::write(&path, &value) {
+                        Ok(_) => say!(
+                            lang,
+                            "GET '{}' -> fichier écrit: {}",
+                            "GET '{}' -> file written: {}",
+                            display_bytes(&key),
+                            path.display()
+                        ),
+                        Err(e) => say!(
+                            lang,
+                            "Erreur écriture fichier: {}",
+                            "File write error: {}",
+                            e
+                        ),
+                    },
+                    None => println!(
+                        "GET '{}' = '{}'",
+                        display_bytes(&key),
+                        display_bytes(&value)
+                    ),
+                },
+                Err(DatabaseError::KeyNotFound(key)) => say!(
+                    lang,
+                    "Clé '{}' non trouvée",
+                    "Key '{}' not found",
+                    display_bytes(&key)
+                ),
+                Err(e) => print_error(lang, "GET", &e),
+            },
 
-                let key = parts[1].as_bytes().to_vec();
+            Command::Size { key } => match db.value_size(&key) {
+                Ok(Some(size)) => say!(
+                    lang,
+                    "SIZE '{}' = {} octet(s) ({} stocké(s))",
+                    "SIZE '{}' = {} byte(s) ({} stored)",
+                    display_bytes(&key),
+                    size.uncompressed,
+                    size.compressed
+                ),
+                Ok(None) => say!(
+                    lang,
+                    "Clé '{}' non trouvée",
+                    "Key '{}' not found",
+                    display_bytes(&key)
+                ),
+                Err(e) => print_error(lang, "SIZE", &e),
+            },
+
+            Command::Delete { key } => match db.delete(key.clone()) {
+                Ok(_) => say!(
+                    lang,
+                    "DELETE '{}' (Tombstone écrit)",
+                    "DELETE '{}' (tombstone written)",
+                    display_bytes(&key)
+                ),
+                Err(e) => print_error(lang, "DELETE", &e),
+            },
+
+            Command::Purge { key } => match db.purge(key.clone()) {
+                Ok(_) => say!(
+                    lang,
+                    "PURGE '{}' (effacée du journal à la prochaine compaction)",
+                    "PURGE '{}' (erased from the log at the next compaction)",
+                    display_bytes(&key)
+                ),
+                Err(e) => print_error(lang, "PURGE", &e),
+            },
 
-                match db.delete(key.clone()) {
-                    Ok(_) => println!("DELETE '{}' (Tombstone écrit)", display_bytes(&key)),
-                    Err(e) => println!("Erreur DELETE: {}", e),
+            Command::Push { front, key, items } => {
+                let result = if front {
+                    db.lpush(key.clone(), items)
+                } else {
+                    db.rpush(key.clone(), items)
+                };
+                match result {
+                    Ok(len) => say!(
+                        lang,
+                        "{} '{}' -> {} éléments",
+                        "{} '{}' -> {} items",
+                        name,
+                        display_bytes(&key),
+                        len
+                    ),
+                    Err(e) => print_error(lang, name, &e),
                 }
             }
 
-            "EXIT" | "QUIT" => {
-                println!("Fermeture de la base de données...");
-                break;
+            Command::Pop { front, key } => {
+                let result = if front {
+                    db.lpop(key.clone())
+                } else {
+                    db.rpop(key.clone())
+                };
+                match result {
+                    Ok(Some(item)) => println!(
+                        "{} '{}' = '{}'",
+                        name,
+                        display_bytes(&key),
+                        display_bytes(&item)
+                    ),
+                    Ok(None) => say!(
+                        lang,
+                        "Liste '{}' vide ou absente",
+                        "List '{}' is empty or missing",
+                        display_bytes(&key)
+                    ),
+                    Err(e) => print_error(lang, name, &e),
+                }
             }
 
-            "COMPACT" => match db.compact() {
-                Ok(_) => println!("Compaction terminée, log réduit."),
-                Err(e) => println!("Erreur COMPACT: {}", e),
+            Command::LRange { key, start, stop } => match db.lrange(&key, start, stop) {
+                Ok(items) => {
+                    for (idx, item) in items.iter().enumerate() {
+                        println!("{}) '{}'", idx + 1, display_bytes(item));
+                    }
+                    if items.is_empty() {
+                        say!(lang, "(liste vide)", "(empty list)");
+                    }
+                }
+                Err(e) => print_error(lang, "LRANGE", &e),
             },
 
-            "LOG" => {
-                let limit = if parts.len() >= 3 && parts[1] == "--limit" {
-                    parts[2].parse::<usize>().ok()
+            Command::HSet { key, field, value } => {
+                match db.hset(key.clone(), field.clone(), value) {
+                    Ok(created) => say!(
+                        lang,
+                        "HSET '{}' '{}' ({})",
+                        "HSET '{}' '{}' ({})",
+                        display_bytes(&key),
+                        display_bytes(&field),
+                        match (created, lang) {
+                            (true, Language::Fr) => "nouveau champ",
+                            (true, Language::En) => "new field",
+                            (false, Language::Fr) => "mis à jour",
+                            (false, Language::En) => "updated",
+                        }
+                    ),
+                    Err(e) => print_error(lang, "HSET", &e),
+                }
+            }
+
+            Command::HGet { key, field } => match db.hget(&key, &field) {
+                Ok(Some(value)) => println!(
+                    "HGET '{}' '{}' = '{}'",
+                    display_bytes(&key),
+                    display_bytes(&field),
+                    display_bytes(&value)
+                ),
+                Ok(None) => say!(
+                    lang,
+                    "Champ '{}' non trouvé",
+                    "Field '{}' not found",
+                    display_bytes(&field)
+                ),
+                Err(e) => print_error(lang, "HGET", &e),
+            },
+
+            Command::HDel { key, fields } => match db.hdel(key.clone(), fields) {
+                Ok(removed) => say!(
+                    lang,
+                    "HDEL '{}' -> {} champs supprimés",
+                    "HDEL '{}' -> {} fields removed",
+                    display_bytes(&key),
+                    removed
+                ),
+                Err(e) => print_error(lang, "HDEL", &e),
+            },
+
+            Command::HGetAll { key } => match db.hgetall(&key) {
+                Ok(fields) if fields.is_empty() => say!(lang, "(table vide)", "(empty hash)"),
+                Ok(fields) => {
+                    for (field, value) in fields {
+                        println!("{} = '{}'", display_bytes(&field), display_bytes(&value));
+                    }
+                }
+                Err(e) => print_error(lang, "HGETALL", &e),
+            },
+
+            Command::SetUpdate {
+                remove,
+                key,
+                members,
+            } => {
+                let result = if remove {
+                    db.srem(key.clone(), members)
                 } else {
-                    None
+                    db.sadd(key.clone(), members)
                 };
+                match result {
+                    Ok(count) => say!(
+                        lang,
+                        "{} '{}' -> {} membres",
+                        "{} '{}' -> {} members",
+                        name,
+                        display_bytes(&key),
+                        count
+                    ),
+                    Err(e) => print_error(lang, name, &e),
+                }
+            }
+
+            Command::SIsMember { key, member } => match db.sismember(&key, &member) {
+                Ok(true) => say!(
+                    lang,
+                    "'{}' est membre de '{}'",
+                    "'{}' is a member of '{}'",
+                    display_bytes(&member),
+                    display_bytes(&key)
+                ),
+                Ok(false) => say!(
+                    lang,
+                    "'{}' n'est pas membre de '{}'",
+                    "'{}' is not a member of '{}'",
+                    display_bytes(&member),
+                    display_bytes(&key)
+                ),
+                Err(e) => print_error(lang, "SISMEMBER", &e),
+            },
+
+            Command::SMembers { key } => match db.smembers(&key) {
+                Ok(members) if members.is_empty() => say!(lang, "(ensemble vide)", "(empty set)"),
+                Ok(members) => {
+                    for member in members {
+                        println!("'{}'", display_bytes(&member));
+                    }
+                }
+                Err(e) => print_error(lang, "SMEMBERS", &e),
+            },
 
-                match db.log_iter() {
-                    Ok(iter) => {
-                        for (idx, record) in iter.flatten().enumerate() {
-                            if let Some(max) = limit
-                                && idx >= max
-                            {
-                                break;
-                            }
-                            let entry_type = match record.entry_type {
-                                rust_database::EntryType::Data => "DATA",
-                                rust_database::EntryType::Tombstone => "TOMBSTONE",
-                            };
-                            println!(
-                                "#{idx} offset={} size={} type={} key={} checksum_ok={}",
-                                record.offset,
-                                record.size,
-                                entry_type,
-                                display_bytes(&record.key),
-                                record.checksum_ok
-                            );
+            Command::ZAdd { key, score, member } => {
+                match db.zadd(key.clone(), score, member.clone()) {
+                    Ok(created) => say!(
+                        lang,
+                        "ZADD '{}' '{}' = {} ({})",
+                        "ZADD '{}' '{}' = {} ({})",
+                        display_bytes(&key),
+                        display_bytes(&member),
+                        score,
+                        match (created, lang) {
+                            (true, Language::Fr) => "nouveau membre",
+                            (true, Language::En) => "new member",
+                            (false, Language::Fr) => "mis à jour",
+                            (false, Language::En) => "updated",
                         }
+                    ),
+                    Err(e) => print_error(lang, "ZADD", &e),
+                }
+            }
+
+            Command::ZRem { key, members } => match db.zrem(key.clone(), members) {
+                Ok(count) => say!(
+                    lang,
+                    "ZREM '{}' -> {} membres",
+                    "ZREM '{}' -> {} members",
+                    display_bytes(&key),
+                    count
+                ),
+                Err(e) => print_error(lang, "ZREM", &e),
+            },
+
+            Command::ZScore { key, member } => match db.zscore(&key, &member) {
+                Ok(Some(score)) => println!(
+                    "ZSCORE '{}' '{}' = {}",
+                    display_bytes(&key),
+                    display_bytes(&member),
+                    score
+                ),
+                Ok(None) => say!(
+                    lang,
+                    "Membre '{}' non trouvé",
+                    "Member '{}' not found",
+                    display_bytes(&member)
+                ),
+                Err(e) => print_error(lang, "ZSCORE", &e),
+            },
+
+            Command::ZRangeByScore { key, min, max } => match db.zrange_by_score(&key, min, max) {
+                Ok(members) if members.is_empty() => say!(lang, "(aucun membre)", "(no member)"),
+                Ok(members) => {
+                    for (idx, (member, score)) in members.iter().enumerate() {
+                        println!("{}) '{}' = {}", idx + 1, display_bytes(member), score);
                     }
-                    Err(e) => println!("Erreur LOG: {}", e),
+                }
+                Err(e) => print_error(lang, "ZRANGEBYSCORE", &e),
+            },
+
+            Command::SetBit { key, offset, bit } => match db.setbit(key.clone(), offset, bit) {
+                Ok(previous) => say!(
+                    lang,
+                    "SETBIT '{}' [{}] = {} (ancien: {})",
+                    "SETBIT '{}' [{}] = {} (previous: {})",
+                    display_bytes(&key),
+                    offset,
+                    bit as u8,
+                    previous as u8
+                ),
+                Err(e) => print_error(lang, "SETBIT", &e),
+            },
+
+            Command::GetBit { key, offset } => match db.getbit(&key, offset) {
+                Ok(bit) => println!(
+                    "GETBIT '{}' [{}] = {}",
+                    display_bytes(&key),
+                    offset,
+                    bit as u8
+                ),
+                Err(e) => print_error(lang, "GETBIT", &e),
+            },
+
+            Command::BitCount { key } => match db.bitcount(&key) {
+                Ok(count) => println!("BITCOUNT '{}' = {}", display_bytes(&key), count),
+                Err(e) => print_error(lang, "BITCOUNT", &e),
+            },
+
+            Command::Warmup(warmup) => {
+                let target = match &warmup {
+                    Warmup::Prefix(prefix) => WarmupTarget::Prefix(prefix),
+                    Warmup::Keys(keys) => WarmupTarget::Keys(keys),
+                };
+                match db.warmup(target) {
+                    Ok(count) => say!(
+                        lang,
+                        "WARMUP: {} clé(s) préchargée(s)",
+                        "WARMUP: {} key(s) preloaded",
+                        count
+                    ),
+                    Err(e) => print_error(lang, "WARMUP", &e),
                 }
             }
 
-            _ => {
-                println!("Commande inconnue. Commandes disponibles :");
-                println!("  SET <clé> <valeur> : Enregistrer une donnée");
-                println!("  SET <clé> --file <chemin> : Enregistrer un fichier");
-                println!("  GET <clé>          : Lire une donnée");
-                println!("  GET <clé> --file <chemin> : Écrire une donnée en fichier");
-                println!("  DELETE <clé>       : Supprimer une donnée");
-                println!("  COMPACT            : Réduire le fichier de log");
-                println!("  LOG [--limit N]    : Voir le le fichier de log");
-                println!("  EXIT               : Quitter le programme");
+            Command::Export { prefix, path } => match db.export_keyspace(&prefix, &path) {
+                Ok(count) => say!(
+                    lang,
+                    "EXPORT: {} clé(s) écrite(s) dans {}",
+                    "EXPORT: {} key(s) written to {}",
+                    count,
+                    path.display()
+                ),
+                Err(e) => print_error(lang, "EXPORT", &e),
+            },
+
+            Command::Help { topic: None } => print!("{}", command::Overview),
+
+            Command::Help { topic: Some(topic) } => match command::find(&topic) {
+                Some(spec) => print!("{}", spec),
+                None => say!(lang, "Commande inconnue: {}", "Unknown command: {}", topic),
+            },
+
+            Command::Exit => {
+                say!(
+                    lang,
+                    "Fermeture de la base de données...",
+                    "Closing the database..."
+                );
+                break;
+            }
+
+            Command::Compact(CompactMode::DryRun) => match db.compaction_estimate() {
+                Ok(estimate) => say!(
+                    lang,
+                    "Estimation: {} entrées vivantes ({} octets), {} octets récupérables sur {}",
+                    "Estimate: {} live entries ({} bytes), {} bytes reclaimable out of {}",
+                    estimate.live_records,
+                    estimate.live_bytes,
+                    estimate.reclaimable_bytes,
+                    estimate.file_size
+                ),
+                Err(e) => print_error(lang, "COMPACT", &e),
+            },
+
+            Command::Compact(CompactMode::Progress) => {
+                let handle = db.compact_in_background();
+                let mut reported = None;
+                while !handle.is_finished() {
+                    let progress = handle.progress();
+                    let step = progress.percent() as u32 / 10;
+                    if progress.records_total > 0 && reported != Some(step) {
+                        print_progress(lang, &progress);
+                        reported = Some(step);
+                    }
+                    thread::sleep(Duration::from_millis(100));
+                }
+                let progress = handle.progress();
+                match handle.wait() {
+                    Ok(_) => {
+                        print_progress(lang, &progress);
+                        say!(
+                            lang,
+                            "Compaction terminée, log réduit.",
+                            "Compaction done, log shrunk."
+                        );
+                    }
+                    Err(e) => print_error(lang, "COMPACT", &e),
+                }
             }
+
+            Command::Compact(CompactMode::Run) => match db.compact() {
+                Ok(_) => say!(
+                    lang,
+                    "Compaction terminée, log réduit.",
+                    "Compaction done, log shrunk."
+                ),
+                Err(e) => print_error(lang, "COMPACT", &e),
+            },
+
+            Command::Reindex => match db.reindex() {
+                Ok(count) => say!(
+                    lang,
+                    "REINDEX: index reconstruit ({} clé(s) vivante(s))",
+                    "REINDEX: index rebuilt ({} live key(s))",
+                    count
+                ),
+                Err(e) => print_error(lang, "REINDEX", &e),
+            },
+
+            Command::Stats {
+                format: Some(format),
+            } => match db.stats() {
+                Ok(stats) => print!("{}", stats_table(&stats).render(format)),
+                Err(e) => print_error_as(lang, Some(format), "STATS", &e),
+            },
+
+            Command::Stats { format: None } => match db.stats() {
+                Ok(stats) => {
+                    say!(
+                        lang,
+                        "Fichier: {} octets, {} entrée(s) indexée(s)",
+                        "File: {} bytes, {} indexed entries",
+                        stats.file_size,
+                        stats.index_len
+                    );
+                    let total = stats.compression.total();
+                    say!(
+                        lang,
+                        "Compression: {} -> {} octets (ratio {:.2})",
+                        "Compression: {} -> {} bytes (ratio {:.2})",
+                        total.raw_bytes,
+                        total.compressed_bytes,
+                        total.ratio()
+                    );
+                    say!(
+                        lang,
+                        "  lz77: {} valeur(s), {} -> {} octets (ratio {:.2})",
+                        "  lz77: {} value(s), {} -> {} bytes (ratio {:.2})",
+                        stats.compression.lz77.values,
+                        stats.compression.lz77.raw_bytes,
+                        stats.compression.lz77.compressed_bytes,
+                        stats.compression.lz77.ratio()
+                    );
+                    say!(
+                        lang,
+                        "  brut: {} valeur(s), {} octets",
+                        "  raw: {} value(s), {} bytes",
+                        stats.compression.raw.values,
+                        stats.compression.raw.raw_bytes
+                    );
+                    say!(
+                        lang,
+                        "Reprises après panic: {}",
+                        "Recoveries after panic: {}",
+                        stats.poison_recoveries
+                    );
+                }
+                Err(e) => print_error(lang, "STATS", &e),
+            },
+
+            Command::Health => {
+                let health = db.health();
+                let state = match (health.is_healthy(), lang) {
+                    (true, _) => "OK",
+                    (false, Language::Fr) => "DÉGRADÉ",
+                    (false, Language::En) => "DEGRADED",
+                };
+                say!(lang, "État: {}", "State: {}", state);
+                say!(
+                    lang,
+                    "  lecture: {}",
+                    "  read: {}",
+                    yes_no(lang, health.can_read)
+                );
+                say!(
+                    lang,
+                    "  écriture: {}",
+                    "  write: {}",
+                    yes_no(lang, health.can_write)
+                );
+                match health.last_sync {
+                    Some(at) => say!(lang, "  dernier fsync: {} ms", "  last fsync: {} ms", at),
+                    None => say!(lang, "  dernier fsync: aucun", "  last fsync: none"),
+                }
+                match &health.last_compaction {
+                    Some(outcome) => match &outcome.error {
+                        None => say!(
+                            lang,
+                            "  dernière compaction: réussie ({} ms)",
+                            "  last compaction: succeeded ({} ms)",
+                            outcome.finished_at
+                        ),
+                        Some(err) => say!(
+                            lang,
+                            "  dernière compaction: échec ({} ms) : {}",
+                            "  last compaction: failed ({} ms): {}",
+                            outcome.finished_at,
+                            err
+                        ),
+                    },
+                    None => say!(
+                        lang,
+                        "  dernière compaction: aucune",
+                        "  last compaction: none"
+                    ),
+                }
+                say!(
+                    lang,
+                    "  corruption détectée: {}",
+                    "  corruption detected: {}",
+                    yes_no(lang, health.corruption_detected)
+                );
+            }
+
+            Command::Scan { prefix, format } => match scan_table(&db, &prefix) {
+                Ok(table) => print!("{}", table.render(format.unwrap_or(Format::Table))),
+                Err(e) => print_error_as(lang, format, "SCAN", &e),
+            },
+
+            Command::Log { limit, format } => match db.log_iter() {
+                Ok(iter) => {
+                    let records = iter.flatten().take(limit.unwrap_or(usize::MAX));
+                    print!(
+                        "{}",
+                        log_table(lang, records).render(format.unwrap_or(Format::Table))
+                    );
+                }
+                Err(e) => print_error_as(lang, format, "LOG", &e),
+            },
         }
     }
 
     Ok(())
 }
 
+/// Une ligne par enregistrement du journal.
+fn log_table(lang: Language, records: impl Iterator<Item = LogRecord>) -> Table {
+    let mut table = Table::new(&[
+        "idx",
+        "offset",
+        "size",
+        "type",
+        "key",
+        "value_len",
+        "checksum",
+    ]);
+    for (idx, record) in records.enumerate() {
+        let entry_type = match record.entry_type {
+            rust_database::EntryType::Data => "DATA",
+            rust_database::EntryType::Tombstone => "TOMBSTONE",
+            rust_database::EntryType::Merge => "MERGE",
+            rust_database::EntryType::Dictionary => "DICTIONARY",
+            rust_database::EntryType::Blob => "BLOB",
+        };
+        table.push(vec![
+            idx.into(),
+            record.offset.into(),
+            record.size.into(),
+            entry_type.into(),
+            display_bytes(&record.key).into(),
+            record.value_len.into(),
+            match (record.checksum_ok, lang) {
+                (true, _) => "ok",
+                (false, Language::Fr) => "invalide",
+                (false, Language::En) => "invalid",
+            }
+            .into(),
+        ]);
+    }
+    table
+}
+
+/// Une ligne par clé vivante de `prefix` : type, tailles, et âge mesuré en octets
+/// écrits au journal depuis la dernière écriture de la clé (le journal ne
+/// conserve pas d'horodatage).
+fn scan_table(db: &MyDatabase, prefix: &[u8]) -> Result<Table, DatabaseError> {
+    let entries = db.scan_meta()?;
+    let file_size = db.stats()?.file_size;
+    let mut table = Table::new(&["key", "type", "size", "stored", "age_bytes"]);
+    for (key, meta) in entries {
+        if !key.starts_with(prefix) {
+            continue;
+        }
+        // Les tailles ne sont définies que pour une valeur simple.
+        let (kind, size, stored) = match db.value_size(&key) {
+            Ok(Some(size)) => ("bytes", size.uncompressed.into(), size.compressed.into()),
+            Ok(None) => continue,
+            Err(DatabaseError::WrongType) => ("collection", Cell::Empty, Cell::Empty),
+            Err(err) => return Err(err),
+        };
+        let end = meta.offset + u64::from(meta.size);
+        table.push(vec![
+            display_bytes(&key).into(),
+            kind.into(),
+            size,
+            stored,
+            file_size.saturating_sub(end).into(),
+        ]);
+    }
+    Ok(table)
+}
+
+/// Une ligne par mesure de `STATS`.
+fn stats_table(stats: &DatabaseStats) -> Table {
+    let compression = &stats.compression;
+    let total = compression.total();
+    let mut table = Table::new(&["metric", "value"]);
+    let rows: [(&str, Cell); 11] = [
+        ("file_size", stats.file_size.into()),
+        ("index_len", stats.index_len.into()),
+        ("raw_bytes", total.raw_bytes.into()),
+        ("compressed_bytes", total.compressed_bytes.into()),
+        ("ratio", total.ratio().into()),
+        ("lz77_values", compression.lz77.values.into()),
+        ("lz77_raw_bytes", compression.lz77.raw_bytes.into()),
+        (
+            "lz77_compressed_bytes",
+            compression.lz77.compressed_bytes.into(),
+        ),
+        ("stored_raw_values", compression.raw.values.into()),
+        ("stored_raw_bytes", compression.raw.raw_bytes.into()),
+        ("poison_recoveries", stats.poison_recoveries.into()),
+    ];
+    for (metric, value) in rows {
+        table.push(vec![metric.into(), value]);
+    }
+    table
+}
+
+/// Signale l'échec de la commande `name`, dans la langue `lang`, précédé
+/// du code de l'erreur.
+fn print_error(lang: Language, name: &str, err: &DatabaseError) {
+    print_error_as(lang, None, name, err);
+}
+
+/// Comme `print_error`, mais en `--format json` l'erreur est écrite sous la
+/// forme `{"code": ..., "message": ...}` pour rester lisible par un script.
+fn print_error_as(lang: Language, format: Option<Format>, name: &str, err: &DatabaseError) {
+    if format == Some(Format::Json) {
+        println!(
+            "{{\"code\":{},\"message\":{}}}",
+            JsonString(err.code()),
+            JsonString(&err.localized(lang).to_string())
+        );
+        return;
+    }
+    let label = match lang {
+        Language::Fr => "Erreur",
+        Language::En => "Error",
+    };
+    println!(
+        "{} {} [{}]: {}",
+        label,
+        name,
+        err.code(),
+        err.localized(lang)
+    );
+}
+
+fn print_progress(lang: Language, progress: &CompactionProgress) {
+    say!(
+        lang,
+        "  {:.0}% ({}/{} entrées, {} octets écrits)",
+        "  {:.0}% ({}/{} entries, {} bytes written)",
+        progress.percent(),
+        progress.records_processed,
+        progress.records_total,
+        progress.bytes_written
+    );
+}
+
+fn yes_no(lang: Language, value: bool) -> &'static str {
+    match (value, lang) {
+        (true, Language::Fr) => "oui",
+        (false, Language::Fr) => "non",
+        (true, Language::En) => "yes",
+        (false, Language::En) => "no",
+    }
+}
+
+/// Sous-commandes non interactives : `rdb diff <a.db> <b.db>`.
+fn run_subcommand(lang: Language, subcommand: &str, args: &[String]) -> Result<(), DatabaseError> {
+    match subcommand {
+        "diff" => {
+            let [path_a, path_b] = args else {
+                println!("Usage: rdb diff <a.db> <b.db>");
+                std::process::exit(2);
+            };
+
+            let report = tools::diff(path_a, path_b)?;
+            say!(
+                lang,
+                "Uniquement dans A ({}):",
+                "Only in A ({}):",
+                report.only_in_a.len()
+            );
+            for (key, size) in &report.only_in_a {
+                say!(
+                    lang,
+                    "  {} ({} octets)",
+                    "  {} ({} bytes)",
+                    display_bytes(key),
+                    size
+                );
+            }
+            say!(
+                lang,
+                "Uniquement dans B ({}):",
+                "Only in B ({}):",
+                report.only_in_b.len()
+            );
+            for (key, size) in &report.only_in_b {
+                say!(
+                    lang,
+                    "  {} ({} octets)",
+                    "  {} ({} bytes)",
+                    display_bytes(key),
+                    size
+                );
+            }
+            say!(
+                lang,
+                "Modifiées ({}):",
+                "Changed ({}):",
+                report.changed.len()
+            );
+            for (key, size_a, size_b) in &report.changed {
+                say!(
+                    lang,
+                    "  {} (A: {} octets, B: {} octets)",
+                    "  {} (A: {} bytes, B: {} bytes)",
+                    display_bytes(key),
+                    size_a,
+                    size_b
+                );
+            }
+            if !report.is_empty() {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        _ => {
+            say!(
+                lang,
+                "Sous-commande inconnue: {}",
+                "Unknown subcommand: {}",
+                subcommand
+            );
+            println!("Usage: rdb [diff <a.db> <b.db>]");
+            std::process::exit(2);
+        }
+    }
+}
+
+/// Lit les lignes suivantes de l'entrée standard jusqu'à une ligne égale à
+/// `delimiter` (exclue) ou, sans délimiteur, jusqu'à la fin de l'entrée. Les
+/// sauts de ligne lus sont conservés.
+fn read_lines(delimiter: Option<&str>) -> Result<Vec<u8>, DatabaseError> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut value = Vec::new();
+    loop {
+        print!("... ");
+        io::stdout()
+            .flush()
+            .map_err(|err| DatabaseError::io(IoOp::TerminalWrite, "", err))?;
+
+        let start = value.len();
+        let read = input
+            .read_until(b'\n', &mut value)
+            .map_err(|err| DatabaseError::io(IoOp::TerminalRead, "", err))?;
+        if read == 0 {
+            if delimiter.is_none() {
+                // Ctrl-D a laissé le curseur après l'invite.
+                println!();
+            }
+            return Ok(value);
+        }
+        if let Some(delimiter) = delimiter {
+            let line = value[start..].trim_ascii_end();
+            if line == delimiter.as_bytes() {
+                value.truncate(start);
+                return Ok(value);
+            }
+        }
+    }
+}
+
 /// Affiche une valeur UTF-8 ou un hex en fallback.
 fn display_bytes(bytes: &[u8]) -> String {
     match std::str::from_utf8(bytes) {
@@ -0,0 +1,665 @@
+use crate::db::KeyValue;
+use crate::error::DatabaseError;
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+/// Profondeur maximale d'une chaîne d'opérandes avant réécriture complète.
+pub(crate) const MAX_MERGE_DEPTH: usize = 32;
+
+/// Valeur matérialisée après application des opérandes de fusion.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Bytes(Vec<u8>),
+    List(VecDeque<Vec<u8>>),
+    Hash(BTreeMap<Vec<u8>, Vec<u8>>),
+    Set(BTreeSet<Vec<u8>>),
+    SortedSet(SortedSet),
+}
+
+/// Ensemble trié : score par membre et index secondaire ordonné par
+/// (score, membre).
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct SortedSet {
+    scores: BTreeMap<Vec<u8>, f64>,
+    order: BTreeSet<(Score, Vec<u8>)>,
+}
+
+/// Score ordonné totalement (`f64::total_cmp`).
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Score(f64);
+
+impl Eq for Score {}
+
+impl PartialOrd for Score {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Score {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl SortedSet {
+    /// Insère ou met à jour un membre. Retourne `true` s'il est nouveau.
+    pub(crate) fn insert(&mut self, member: Vec<u8>, score: f64) -> bool {
+        let previous = self.scores.insert(member.clone(), score);
+        if let Some(previous) = previous {
+            self.order.remove(&(Score(previous), member.clone()));
+        }
+        self.order.insert((Score(score), member));
+        previous.is_none()
+    }
+
+    pub(crate) fn remove(&mut self, member: &[u8]) -> bool {
+        match self.scores.remove(member) {
+            Some(score) => self.order.remove(&(Score(score), member.to_vec())),
+            None => false,
+        }
+    }
+
+    pub(crate) fn score(&self, member: &[u8]) -> Option<f64> {
+        self.scores.get(member).copied()
+    }
+
+    /// Membres dont le score est compris entre `min` et `max` inclus, par
+    /// score croissant.
+    pub(crate) fn range_by_score(&self, min: f64, max: f64) -> Vec<(Vec<u8>, f64)> {
+        if min > max {
+            return Vec::new();
+        }
+        self.order
+            .range((Score(min), Vec::new())..)
+            .take_while(|(score, _)| score.0 <= max)
+            .map(|(score, member)| (member.clone(), score.0))
+            .collect()
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+}
+
+/// Opérande de fusion enregistrée dans le journal.
+#[derive(Debug, Clone)]
+pub(crate) enum MergeOp {
+    /// Remplace entièrement la valeur (base d'une chaîne).
+    Replace(Value),
+    Push {
+        front: bool,
+        items: Vec<Vec<u8>>,
+    },
+    Pop {
+        front: bool,
+        count: u32,
+    },
+    HashSet {
+        field: Vec<u8>,
+        value: Vec<u8>,
+    },
+    HashDel {
+        fields: Vec<Vec<u8>>,
+    },
+    SetAdd {
+        members: Vec<Vec<u8>>,
+    },
+    SetRem {
+        members: Vec<Vec<u8>>,
+    },
+    ZAdd {
+        score: f64,
+        member: Vec<u8>,
+    },
+    ZRem {
+        members: Vec<Vec<u8>>,
+    },
+    SetBit {
+        offset: u64,
+        bit: bool,
+    },
+    #[cfg(feature = "json")]
+    JsonSet {
+        path: String,
+        value: Vec<u8>,
+    },
+    /// Remplace `removed` octets à partir de `offset` par `bytes` (voir
+    /// `MyDatabase::set_delta`).
+    Splice {
+        offset: u32,
+        removed: u32,
+        bytes: Vec<u8>,
+    },
+}
+
+/// Contenu d'un enregistrement `EntryType::Merge` : l'opérande et un pointeur
+/// vers l'enregistrement précédent de la même clé.
+pub(crate) struct MergeRecord {
+    pub prev: Option<(u64, u32)>,
+    pub op: MergeOp,
+}
+
+const VALUE_BYTES: u8 = 0;
+const VALUE_LIST: u8 = 1;
+const VALUE_HASH: u8 = 2;
+const VALUE_SET: u8 = 3;
+const VALUE_SORTED_SET: u8 = 4;
+
+const OP_REPLACE: u8 = 0;
+const OP_PUSH: u8 = 1;
+const OP_POP: u8 = 2;
+const OP_HASH_SET: u8 = 3;
+const OP_HASH_DEL: u8 = 4;
+const OP_SET_ADD: u8 = 5;
+const OP_SET_REM: u8 = 6;
+const OP_ZADD: u8 = 7;
+const OP_ZREM: u8 = 8;
+const OP_SET_BIT: u8 = 9;
+#[cfg(feature = "json")]
+const OP_JSON_SET: u8 = 10;
+const OP_SPLICE: u8 = 11;
+
+/// Plus grand décalage de bit accepté (valeur de 512 Mio).
+pub(crate) const MAX_BIT_OFFSET: u64 = (1 << 32) - 1;
+
+impl MergeRecord {
+    /// \[Précédent ? (1B)\] \[Offset (8B)\] \[Taille (4B)\] \[Opérande\]
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        match self.prev {
+            Some((offset, size)) => {
+                out.push(1);
+                out.extend_from_slice(&offset.to_be_bytes());
+                out.extend_from_slice(&size.to_be_bytes());
+            }
+            None => out.push(0),
+        }
+
+        match &self.op {
+            MergeOp::Replace(value) => {
+                out.push(OP_REPLACE);
+                encode_value(&mut out, value);
+            }
+            MergeOp::Push { front, items } => {
+                out.push(OP_PUSH);
+                out.push(*front as u8);
+                encode_items(&mut out, items.iter());
+            }
+            MergeOp::Pop { front, count } => {
+                out.push(OP_POP);
+                out.push(*front as u8);
+                out.extend_from_slice(&count.to_be_bytes());
+            }
+            MergeOp::HashSet { field, value } => {
+                out.push(OP_HASH_SET);
+                encode_pairs(&mut out, [(field, value)].into_iter());
+            }
+            MergeOp::HashDel { fields } => {
+                out.push(OP_HASH_DEL);
+                encode_items(&mut out, fields.iter());
+            }
+            MergeOp::SetAdd { members } => {
+                out.push(OP_SET_ADD);
+                encode_items(&mut out, members.iter());
+            }
+            MergeOp::SetRem { members } => {
+                out.push(OP_SET_REM);
+                encode_items(&mut out, members.iter());
+            }
+            MergeOp::ZAdd { score, member } => {
+                out.push(OP_ZADD);
+                out.extend_from_slice(&score.to_bits().to_be_bytes());
+                encode_items(&mut out, [member].into_iter());
+            }
+            MergeOp::ZRem { members } => {
+                out.push(OP_ZREM);
+                encode_items(&mut out, members.iter());
+            }
+            MergeOp::SetBit { offset, bit } => {
+                out.push(OP_SET_BIT);
+                out.extend_from_slice(&offset.to_be_bytes());
+                out.push(*bit as u8);
+            }
+            #[cfg(feature = "json")]
+            MergeOp::JsonSet { path, value } => {
+                out.push(OP_JSON_SET);
+                let path = path.as_bytes().to_vec();
+                encode_items(&mut out, [&path, value].into_iter());
+            }
+            MergeOp::Splice {
+                offset,
+                removed,
+                bytes,
+            } => {
+                out.push(OP_SPLICE);
+                out.extend_from_slice(&offset.to_be_bytes());
+                out.extend_from_slice(&removed.to_be_bytes());
+                out.extend_from_slice(bytes);
+            }
+        }
+        out
+    }
+
+    pub(crate) fn from_bytes(input: &[u8]) -> Result<Self, DatabaseError> {
+        let mut cursor = Cursor::new(input);
+        let prev = match cursor.u8()? {
+            0 => None,
+            1 => Some((cursor.u64()?, cursor.u32()?)),
+            _ => return Err(DatabaseError::InvalidFormat),
+        };
+
+        let op = match cursor.u8()? {
+            OP_REPLACE => MergeOp::Replace(decode_value(&mut cursor)?),
+            OP_PUSH => MergeOp::Push {
+                front: cursor.bool()?,
+                items: decode_items(&mut cursor)?,
+            },
+            OP_POP => MergeOp::Pop {
+                front: cursor.bool()?,
+                count: cursor.u32()?,
+            },
+            OP_HASH_SET => {
+                let mut pairs = decode_pairs(&mut cursor)?;
+                if pairs.len() != 1 {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                let (field, value) = pairs.remove(0);
+                MergeOp::HashSet { field, value }
+            }
+            OP_HASH_DEL => MergeOp::HashDel {
+                fields: decode_items(&mut cursor)?,
+            },
+            OP_SET_ADD => MergeOp::SetAdd {
+                members: decode_items(&mut cursor)?,
+            },
+            OP_SET_REM => MergeOp::SetRem {
+                members: decode_items(&mut cursor)?,
+            },
+            OP_ZADD => {
+                let score = f64::from_bits(cursor.u64()?);
+                let mut members = decode_items(&mut cursor)?;
+                if members.len() != 1 {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                MergeOp::ZAdd {
+                    score,
+                    member: members.remove(0),
+                }
+            }
+            OP_ZREM => MergeOp::ZRem {
+                members: decode_items(&mut cursor)?,
+            },
+            OP_SET_BIT => {
+                let offset = cursor.u64()?;
+                if offset > MAX_BIT_OFFSET {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                MergeOp::SetBit {
+                    offset,
+                    bit: cursor.bool()?,
+                }
+            }
+            #[cfg(feature = "json")]
+            OP_JSON_SET => {
+                let mut items = decode_items(&mut cursor)?;
+                if items.len() != 2 {
+                    return Err(DatabaseError::InvalidFormat);
+                }
+                let value = items.pop().unwrap_or_default();
+                let path = String::from_utf8(items.pop().unwrap_or_default())?;
+                MergeOp::JsonSet { path, value }
+            }
+            OP_SPLICE => MergeOp::Splice {
+                offset: cursor.u32()?,
+                removed: cursor.u32()?,
+                bytes: cursor.rest().to_vec(),
+            },
+            _ => return Err(DatabaseError::InvalidFormat),
+        };
+        cursor.finish()?;
+
+        Ok(Self { prev, op })
+    }
+}
+
+/// Applique une opérande à la valeur courante. `None` signifie clé absente.
+pub(crate) fn apply(value: Option<Value>, op: MergeOp) -> Result<Option<Value>, DatabaseError> {
+    match op {
+        MergeOp::Replace(value) => Ok(Some(value)),
+        MergeOp::Push { front, items } => {
+            let mut list = match value {
+                None => VecDeque::new(),
+                Some(Value::List(list)) => list,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            for item in items {
+                if front {
+                    list.push_front(item);
+                } else {
+                    list.push_back(item);
+                }
+            }
+            Ok(Some(Value::List(list)))
+        }
+        MergeOp::Pop { front, count } => {
+            let mut list = match value {
+                None => return Ok(None),
+                Some(Value::List(list)) => list,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            for _ in 0..count {
+                if front {
+                    list.pop_front();
+                } else {
+                    list.pop_back();
+                }
+            }
+            Ok((!list.is_empty()).then_some(Value::List(list)))
+        }
+        MergeOp::HashSet {
+            field,
+            value: field_value,
+        } => {
+            let mut map = match value {
+                None => BTreeMap::new(),
+                Some(Value::Hash(map)) => map,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            map.insert(field, field_value);
+            Ok(Some(Value::Hash(map)))
+        }
+        MergeOp::HashDel { fields } => {
+            let mut map = match value {
+                None => return Ok(None),
+                Some(Value::Hash(map)) => map,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            for field in &fields {
+                map.remove(field);
+            }
+            Ok((!map.is_empty()).then_some(Value::Hash(map)))
+        }
+        MergeOp::SetAdd { members } => {
+            let mut set = match value {
+                None => BTreeSet::new(),
+                Some(Value::Set(set)) => set,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            set.extend(members);
+            Ok(Some(Value::Set(set)))
+        }
+        MergeOp::SetRem { members } => {
+            let mut set = match value {
+                None => return Ok(None),
+                Some(Value::Set(set)) => set,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            for member in &members {
+                set.remove(member);
+            }
+            Ok((!set.is_empty()).then_some(Value::Set(set)))
+        }
+        MergeOp::ZAdd { score, member } => {
+            let mut zset = match value {
+                None => SortedSet::default(),
+                Some(Value::SortedSet(zset)) => zset,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            zset.insert(member, score);
+            Ok(Some(Value::SortedSet(zset)))
+        }
+        MergeOp::ZRem { members } => {
+            let mut zset = match value {
+                None => return Ok(None),
+                Some(Value::SortedSet(zset)) => zset,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            for member in &members {
+                zset.remove(member);
+            }
+            Ok((!zset.is_empty()).then_some(Value::SortedSet(zset)))
+        }
+        MergeOp::SetBit { offset, bit } => {
+            let mut bytes = match value {
+                None => Vec::new(),
+                Some(Value::Bytes(bytes)) => bytes,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            let byte = (offset / 8) as usize;
+            if bytes.len() <= byte {
+                bytes.resize(byte + 1, 0);
+            }
+            let mask = 0x80 >> (offset % 8);
+            if bit {
+                bytes[byte] |= mask;
+            } else {
+                bytes[byte] &= !mask;
+            }
+            Ok(Some(Value::Bytes(bytes)))
+        }
+        #[cfg(feature = "json")]
+        MergeOp::JsonSet { path, value: json } => {
+            let bytes = match &value {
+                None => None,
+                Some(Value::Bytes(bytes)) => Some(bytes.as_slice()),
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            Ok(Some(Value::Bytes(crate::json::apply_set(
+                bytes, &path, &json,
+            )?)))
+        }
+        MergeOp::Splice {
+            offset,
+            removed,
+            bytes: inserted,
+        } => {
+            let mut bytes = match value {
+                None => Vec::new(),
+                Some(Value::Bytes(bytes)) => bytes,
+                Some(_) => return Err(DatabaseError::WrongType),
+            };
+            let start = offset as usize;
+            let end = start + removed as usize;
+            if end > bytes.len() {
+                return Err(DatabaseError::CorruptedData);
+            }
+            bytes.splice(start..end, inserted);
+            Ok(Some(Value::Bytes(bytes)))
+        }
+    }
+}
+
+/// Opérande transformant `old` en `new` : seule la plage comprise entre le
+/// plus long préfixe et le plus long suffixe communs est réécrite.
+pub(crate) fn splice_diff(old: &[u8], new: &[u8]) -> MergeOp {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    MergeOp::Splice {
+        offset: prefix as u32,
+        removed: (old.len() - prefix - suffix) as u32,
+        bytes: new[prefix..new.len() - suffix].to_vec(),
+    }
+}
+
+/// Bit à la position `offset` (bit de poids fort en premier, comme Redis).
+/// Les bits au-delà de la valeur valent 0.
+pub(crate) fn get_bit(bytes: &[u8], offset: u64) -> bool {
+    bytes
+        .get((offset / 8) as usize)
+        .is_some_and(|byte| byte & (0x80 >> (offset % 8)) != 0)
+}
+
+/// Taille de la valeur : octets bruts, ou encodage pour une collection.
+pub(crate) fn value_size(value: &Value) -> u64 {
+    match value {
+        Value::Bytes(bytes) => bytes.len() as u64,
+        value => {
+            let mut out = Vec::new();
+            encode_value(&mut out, value);
+            out.len() as u64
+        }
+    }
+}
+
+fn encode_value(out: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Bytes(bytes) => {
+            out.push(VALUE_BYTES);
+            out.extend_from_slice(bytes);
+        }
+        Value::List(items) => {
+            out.push(VALUE_LIST);
+            encode_items(out, items.iter());
+        }
+        Value::Hash(map) => {
+            out.push(VALUE_HASH);
+            encode_pairs(out, map.iter());
+        }
+        Value::Set(set) => {
+            out.push(VALUE_SET);
+            encode_items(out, set.iter());
+        }
+        Value::SortedSet(zset) => {
+            out.push(VALUE_SORTED_SET);
+            out.extend_from_slice(&(zset.len() as u32).to_be_bytes());
+            for (member, score) in &zset.scores {
+                out.extend_from_slice(&score.to_bits().to_be_bytes());
+                out.extend_from_slice(&(member.len() as u32).to_be_bytes());
+                out.extend_from_slice(member);
+            }
+        }
+    }
+}
+
+fn decode_value(cursor: &mut Cursor<'_>) -> Result<Value, DatabaseError> {
+    match cursor.u8()? {
+        VALUE_BYTES => Ok(Value::Bytes(cursor.rest().to_vec())),
+        VALUE_LIST => Ok(Value::List(decode_items(cursor)?.into())),
+        VALUE_HASH => Ok(Value::Hash(decode_pairs(cursor)?.into_iter().collect())),
+        VALUE_SET => Ok(Value::Set(decode_items(cursor)?.into_iter().collect())),
+        VALUE_SORTED_SET => {
+            let count = cursor.u32()? as usize;
+            let mut zset = SortedSet::default();
+            for _ in 0..count {
+                let score = f64::from_bits(cursor.u64()?);
+                let len = cursor.u32()? as usize;
+                zset.insert(cursor.bytes(len)?.to_vec(), score);
+            }
+            Ok(Value::SortedSet(zset))
+        }
+        _ => Err(DatabaseError::InvalidFormat),
+    }
+}
+
+fn encode_items<'a>(out: &mut Vec<u8>, items: impl ExactSizeIterator<Item = &'a Vec<u8>>) {
+    out.extend_from_slice(&(items.len() as u32).to_be_bytes());
+    for item in items {
+        out.extend_from_slice(&(item.len() as u32).to_be_bytes());
+        out.extend_from_slice(item);
+    }
+}
+
+fn decode_items(cursor: &mut Cursor<'_>) -> Result<Vec<Vec<u8>>, DatabaseError> {
+    let count = cursor.u32()? as usize;
+    let mut items = Vec::new();
+    for _ in 0..count {
+        let len = cursor.u32()? as usize;
+        items.push(cursor.bytes(len)?.to_vec());
+    }
+    Ok(items)
+}
+
+fn encode_pairs<'a>(
+    out: &mut Vec<u8>,
+    pairs: impl ExactSizeIterator<Item = (&'a Vec<u8>, &'a Vec<u8>)>,
+) {
+    out.extend_from_slice(&(pairs.len() as u32).to_be_bytes());
+    for (field, value) in pairs {
+        for bytes in [field, value] {
+            out.extend_from_slice(&(bytes.len() as u32).to_be_bytes());
+            out.extend_from_slice(bytes);
+        }
+    }
+}
+
+fn decode_pairs(cursor: &mut Cursor<'_>) -> Result<Vec<KeyValue>, DatabaseError> {
+    let count = cursor.u32()? as usize;
+    let mut pairs = Vec::new();
+    for _ in 0..count {
+        let field_len = cursor.u32()? as usize;
+        let field = cursor.bytes(field_len)?.to_vec();
+        let value_len = cursor.u32()? as usize;
+        pairs.push((field, cursor.bytes(value_len)?.to_vec()));
+    }
+    Ok(pairs)
+}
+
+/// Lecteur borné : toute lecture hors limites est une erreur de format.
+struct Cursor<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a [u8]) -> Self {
+        Self { input, pos: 0 }
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DatabaseError> {
+        let end = self
+            .pos
+            .checked_add(len)
+            .filter(|end| *end <= self.input.len())
+            .ok_or(DatabaseError::InvalidFormat)?;
+        let slice = &self.input[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let slice = &self.input[self.pos..];
+        self.pos = self.input.len();
+        slice
+    }
+
+    fn u8(&mut self) -> Result<u8, DatabaseError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool, DatabaseError> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(DatabaseError::InvalidFormat),
+        }
+    }
+
+    fn u32(&mut self) -> Result<u32, DatabaseError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn u64(&mut self) -> Result<u64, DatabaseError> {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(self.bytes(8)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn finish(&self) -> Result<(), DatabaseError> {
+        if self.pos == self.input.len() {
+            Ok(())
+        } else {
+            Err(DatabaseError::InvalidFormat)
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::error::{DatabaseError, IoContext, IoOp};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Enregistrements écartés par une compaction : leurs octets bruts sont
+/// ajoutés à `<journal>.quarantine` et chacun est décrit par une ligne de
+/// `<journal>.quarantine.txt` (horodatage, position d'origine, clé, erreur).
+pub(crate) struct Quarantine {
+    path: PathBuf,
+    report_path: PathBuf,
+    files: Option<(File, File)>,
+    count: usize,
+}
+
+impl Quarantine {
+    pub(crate) fn new(log_path: &Path) -> Self {
+        Self {
+            path: log_path.with_extension("db.quarantine"),
+            report_path: log_path.with_extension("db.quarantine.txt"),
+            files: None,
+            count: 0,
+        }
+    }
+
+    /// Met de côté l'enregistrement `raw` lu à `offset` pour `key`. Les
+    /// fichiers ne sont créés qu'au premier enregistrement écarté.
+    pub(crate) fn add(
+        &mut self,
+        key: &[u8],
+        raw: &[u8],
+        offset: u64,
+        reason: &DatabaseError,
+        now_millis: u64,
+    ) -> Result<(), DatabaseError> {
+        if self.files.is_none() {
+            let open = |path: &Path| {
+                OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path)
+                    .context(IoOp::QuarantineOpen, path)
+            };
+            self.files = Some((open(&self.path)?, open(&self.report_path)?));
+        }
+        let Some((records, report)) = self.files.as_mut() else {
+            return Ok(());
+        };
+
+        let position = records
+            .metadata()
+            .context(IoOp::QuarantineWrite, &self.path)?
+            .len();
+        records
+            .write_all(raw)
+            .context(IoOp::QuarantineWrite, &self.path)?;
+        writeln!(
+            report,
+            "{} offset={} quarantaine={} taille={} clé={} erreur={}",
+            now_millis,
+            offset,
+            position,
+            raw.len(),
+            String::from_utf8_lossy(key),
+            reason
+        )
+        .context(IoOp::QuarantineWrite, &self.report_path)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Nombre d'enregistrements écartés.
+    pub(crate) fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Rend les écritures durables avant que le journal d'origine ne soit
+    /// remplacé.
+    pub(crate) fn sync(&mut self) -> Result<(), DatabaseError> {
+        if let Some((records, report)) = self.files.as_mut() {
+            records
+                .sync_all()
+                .context(IoOp::QuarantineSync, &self.path)?;
+            report
+                .sync_all()
+                .context(IoOp::QuarantineSync, &self.report_path)?;
+        }
+        Ok(())
+    }
+}
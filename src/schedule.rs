@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+/// Politique de déclenchement de la compaction automatique.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompactionSchedule {
+    /// Compaction dès que `max_size` est atteint (comportement historique).
+    #[default]
+    Always,
+    /// Uniquement entre `start_hour` (inclus) et `end_hour` (exclu), en heures UTC.
+    /// Une fenêtre peut chevaucher minuit (ex. 22 → 4).
+    Window { start_hour: u8, end_hour: u8 },
+    /// Uniquement si aucune écriture n'a eu lieu depuis `secs` secondes.
+    Idle { secs: u64 },
+}
+
+/// Comportement des écritures quand la compaction ne suit pas.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BackpressurePolicy {
+    /// Compaction synchrone dans l'appel d'écriture dès que `max_size` est
+    /// atteint (comportement historique).
+    #[default]
+    Inline,
+    /// Pas de compaction dans le chemin d'écriture : dès que `max_size` ou
+    /// `hard_limit` est atteint, une écriture lance une compaction en
+    /// arrière-plan (si `compaction_schedule` l'autorise). Au-delà de
+    /// `hard_limit` octets, `set` attend jusqu'à `max_stall` qu'elle libère
+    /// de la place, puis échoue avec `DatabaseError::Backpressure`.
+    Stall {
+        hard_limit: u64,
+        max_stall: Duration,
+    },
+    /// Comme `Stall`, sans attente : `set` échoue immédiatement.
+    Reject { hard_limit: u64 },
+}
+
+impl CompactionSchedule {
+    /// Indique si la compaction est autorisée à `now_ms` (ms UNIX), la dernière
+    /// écriture datant de `last_write_ms` (0 si aucune).
+    pub fn allows(&self, now_ms: u64, last_write_ms: u64) -> bool {
+        match *self {
+            CompactionSchedule::Always => true,
+            CompactionSchedule::Window {
+                start_hour,
+                end_hour,
+            } => {
+                let hour = ((now_ms / 3_600_000) % 24) as u8;
+                if start_hour <= end_hour {
+                    hour >= start_hour && hour < end_hour
+                } else {
+                    hour >= start_hour || hour < end_hour
+                }
+            }
+            CompactionSchedule::Idle { secs } => {
+                now_ms.saturating_sub(last_write_ms) >= secs.saturating_mul(1000)
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::db::{DatabaseConfig, KeyValue, MyDatabase};
+use crate::error::DatabaseError;
+use crate::token::SyncToken;
+use std::path::{Path, PathBuf};
+
+/// Base partitionnée : les clés sont réparties par hachage entre plusieurs
+/// `MyDatabase` indépendantes (fichiers et verrous distincts).
+///
+/// Le nombre de shards fait partie du format : une base doit toujours être
+/// rouverte avec le même nombre.
+pub struct ShardedDatabase {
+    shards: Vec<MyDatabase>,
+}
+
+impl ShardedDatabase {
+    /// Ouvre `shard_count` shards nommés `<fichier>.<n>.<ext>` à partir de
+    /// `config.file_path`. Les autres réglages (dont `max_size`) s'appliquent à
+    /// chaque shard. Si l'ouverture d'un shard échoue lors de la création de
+    /// la base, les fichiers déjà créés sont supprimés.
+    pub fn new(config: DatabaseConfig, shard_count: usize) -> Result<Self, DatabaseError> {
+        if shard_count == 0 {
+            return Err(DatabaseError::ParseError(
+                "une base partitionnée demande au moins un shard".to_string(),
+            ));
+        }
+
+        let paths: Vec<PathBuf> = (0..shard_count)
+            .map(|n| shard_path(&config.file_path, n))
+            .collect();
+        let existing = paths.iter().filter(|path| path.exists()).count();
+        if (existing != 0 && existing != shard_count)
+            || shard_path(&config.file_path, shard_count).exists()
+        {
+            return Err(DatabaseError::InvalidFormat);
+        }
+
+        let mut shards = Vec::with_capacity(shard_count);
+        for file_path in &paths {
+            let opened = MyDatabase::new(DatabaseConfig {
+                file_path: file_path.clone(),
+                ..config.clone()
+            });
+            match opened {
+                Ok(shard) => shards.push(shard),
+                Err(err) => {
+                    drop(shards);
+                    // Des shards partiels rendraient la base inouvrable.
+                    if existing == 0 {
+                        for path in &paths {
+                            let _ = std::fs::remove_file(path);
+                        }
+                    }
+                    return Err(err);
+                }
+            }
+        }
+        Ok(Self { shards })
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Accès direct aux shards (statistiques, maintenance).
+    pub fn shards(&self) -> &[MyDatabase] {
+        &self.shards
+    }
+
+    /// Shard responsable de `key`.
+    pub fn shard_for(&self, key: &[u8]) -> &MyDatabase {
+        &self.shards[(fnv1a(key) % self.shards.len() as u64) as usize]
+    }
+
+    /// Comme `MyDatabase::set` ; le jeton est celui du shard de `key`.
+    pub fn set(&self, key: Vec<u8>, value: Vec<u8>) -> Result<SyncToken, DatabaseError> {
+        self.shard_for(&key).set(key, value)
+    }
+
+    pub fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.shard_for(key).get(key)
+    }
+
+    pub fn delete(&self, key: Vec<u8>) -> Result<(), DatabaseError> {
+        self.shard_for(&key).delete(key)
+    }
+
+    /// Valeurs vivantes de tous les shards dont la clé commence par `prefix`,
+    /// triées par clé.
+    pub fn scan(&self, prefix: &[u8]) -> Result<Vec<KeyValue>, DatabaseError> {
+        let mut entries = Vec::new();
+        for shard in &self.shards {
+            entries.extend(shard.scan(prefix)?);
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+
+    /// Compacte chaque shard, l'un après l'autre : les écritures vers les
+    /// autres shards ne sont pas bloquées.
+    pub fn compact(&self) -> Result<(), DatabaseError> {
+        for shard in &self.shards {
+            shard.compact()?;
+        }
+        Ok(())
+    }
+}
+
+/// `base.db` devient `base.<n>.db`.
+fn shard_path(path: &Path, n: usize) -> PathBuf {
+    match path.extension() {
+        Some(ext) => path.with_extension(format!("{}.{}", n, ext.to_string_lossy())),
+        None => path.with_extension(n.to_string()),
+    }
+}
+
+/// Hachage FNV-1a 64 bits : stable d'une version de Rust à l'autre, ce qui
+/// garde la répartition des clés identique entre deux ouvertures.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in bytes {
+        hash ^= u64::from(*byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash
+}
@@ -0,0 +1,246 @@
+use crate::db::{IndexEntry, KeyValue, LogReader, MyDatabase, SharedState, ValueMeta};
+use crate::error::DatabaseError;
+use crate::merge::Value;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::vec;
+
+/// Maintient un instantané en vie : tant qu'il en existe, la compaction ne
+/// remplace pas le journal sur les systèmes où un fichier ouvert ne peut pas
+/// l'être (hors Unix, voir `MyDatabase::compact`).
+struct SnapshotPin {
+    shared: Arc<SharedState>,
+}
+
+impl SnapshotPin {
+    fn new(shared: &Arc<SharedState>) -> Self {
+        shared.live_snapshots.fetch_add(1, Ordering::AcqRel);
+        Self {
+            shared: Arc::clone(shared),
+        }
+    }
+}
+
+impl Drop for SnapshotPin {
+    fn drop(&mut self) {
+        self.shared.live_snapshots.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// Itérateur de `scan_iter` sur un instantané de l'index : les écritures et
+/// compactions ultérieures ne le modifient pas. Le journal est ouvert à la
+/// création de l'instantané ; ce descripteur garde lisible la génération de
+/// fichier correspondante même si une compaction la remplace.
+pub struct ScanIter {
+    entries: vec::IntoIter<(Vec<u8>, IndexEntry)>,
+    reader: LogReader,
+    pin: SnapshotPin,
+}
+
+impl Iterator for ScanIter {
+    type Item = Result<KeyValue, DatabaseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (key, entry) in self.entries.by_ref() {
+            match MyDatabase::resolve(&mut self.reader, &entry, &key) {
+                Ok((Some(Value::Bytes(value)), _)) => return Some(Ok((key, value))),
+                Ok(_) => continue,
+                Err(err) => {
+                    if err.is_corruption() {
+                        self.pin
+                            .shared
+                            .corruption_detected
+                            .store(true, Ordering::Relaxed);
+                    }
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Itérateur de `keys` sur un instantané des clés vivantes.
+pub struct KeyIter {
+    keys: vec::IntoIter<Vec<u8>>,
+}
+
+impl Iterator for KeyIter {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.keys.next()
+    }
+}
+
+impl MyDatabase {
+    /// Comme `scan`, en parcourant paresseusement un instantané : une clé
+    /// n'est jamais vue deux fois ni manquée à cause d'une écriture ou d'une
+    /// compaction concurrente.
+    pub fn scan_iter(&self, prefix: &[u8]) -> Result<ScanIter, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let entries = self.snapshot_entries(prefix)?;
+        Ok(ScanIter {
+            entries: entries.into_iter(),
+            reader: self.log_reader()?.with_read_ahead(self.config.read_ahead),
+            pin: SnapshotPin::new(&self.shared),
+        })
+    }
+
+    /// Clés vivantes commençant par `prefix`, triées, figées à l'appel.
+    pub fn keys(&self, prefix: &[u8]) -> Result<KeyIter, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let keys: Vec<Vec<u8>> = self
+            .snapshot_entries(prefix)?
+            .into_iter()
+            .map(|(key, _)| key)
+            .collect();
+        Ok(KeyIter {
+            keys: keys.into_iter(),
+        })
+    }
+
+    /// Clés vivantes triées, décrites d'après l'index seul : aucune valeur
+    /// n'est lue. Le journal ne conservant ni horodatage ni durée de vie, la
+    /// position de l'enregistrement sert à repérer les clés les plus anciennes.
+    pub fn scan_meta(&self) -> Result<Vec<(Vec<u8>, ValueMeta)>, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        Ok(self
+            .snapshot_entries(b"")?
+            .into_iter()
+            .map(|(key, entry)| {
+                let meta = ValueMeta {
+                    size: entry.size,
+                    offset: entry.offset,
+                    generation: entry.generation,
+                };
+                (key, meta)
+            })
+            .collect())
+    }
+
+    /// Écrit dans `path` (qui ne doit pas exister) un journal autonome,
+    /// ouvrable par `MyDatabase::new`, des clés vivantes commençant par
+    /// `prefix`. La copie porte sur un instantané : les écritures
+    /// concurrentes ne sont pas bloquées et n'y figurent pas. Comme pour
+    /// `clone_to`, `path` n'existe jamais à moitié écrit et un fichier
+    /// existant n'est pas écrasé. Retourne le nombre de clés exportées.
+    pub fn export_keyspace(
+        &self,
+        prefix: &[u8],
+        path: impl AsRef<Path>,
+    ) -> Result<usize, DatabaseError> {
+        let (mut entries, mut reader, _pin) = {
+            let _access_guard = self.read_access()?;
+            (
+                self.snapshot_entries(prefix)?,
+                self.log_reader()?.with_read_ahead(self.config.read_ahead),
+                SnapshotPin::new(&self.shared),
+            )
+        };
+        entries.sort_by_key(|(_, entry)| entry.offset);
+        self.write_copy(&entries, &mut reader, path.as_ref())
+    }
+
+    /// Entrées vivantes de `prefix` triées par clé. L'appelant doit détenir
+    /// le verrou `access`.
+    fn snapshot_entries(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, IndexEntry)>, DatabaseError> {
+        let mut entries: Vec<(Vec<u8>, IndexEntry)> = self
+            .index_read()?
+            .iter()
+            .filter(|(key, entry)| !entry.tombstone && key.starts_with(prefix))
+            .map(|(key, entry)| (key.clone(), *entry))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::db::{DatabaseConfig, MyDatabase};
+    use crate::testutil::TempDir;
+    use std::sync::atomic::Ordering;
+
+    fn fill(db: &MyDatabase, count: usize) {
+        for i in 0..count {
+            db.set(
+                format!("k{i:02}").into_bytes(),
+                format!("v{i}").into_bytes(),
+            )
+            .unwrap();
+        }
+    }
+
+    #[test]
+    fn scan_iter_ignores_later_writes() {
+        let dir = TempDir::new("snapshot-writes");
+        let db = dir.open();
+        fill(&db, 3);
+        let iter = db.scan_iter(b"k").unwrap();
+        let keys = db.keys(b"k").unwrap();
+        db.set(b"k00".to_vec(), b"changed".to_vec()).unwrap();
+        db.set(b"k99".to_vec(), b"new".to_vec()).unwrap();
+        db.delete(b"k01".to_vec()).unwrap();
+
+        let seen: Vec<_> = iter.map(Result::unwrap).collect();
+        assert_eq!(
+            seen,
+            vec![
+                (b"k00".to_vec(), b"v0".to_vec()),
+                (b"k01".to_vec(), b"v1".to_vec()),
+                (b"k02".to_vec(), b"v2".to_vec()),
+            ]
+        );
+        assert_eq!(keys.count(), 3);
+    }
+
+    #[test]
+    fn scan_iter_survives_compaction() {
+        let dir = TempDir::new("snapshot-compaction");
+        let db = dir.open();
+        fill(&db, 20);
+        for i in 0..20 {
+            db.set(
+                format!("k{i:02}").into_bytes(),
+                format!("w{i}").into_bytes(),
+            )
+            .unwrap();
+        }
+        let mut iter = db.scan_iter(b"").unwrap();
+        assert_eq!(db.shared.live_snapshots.load(Ordering::Relaxed), 1);
+        let first = iter.next().unwrap().unwrap();
+        db.compact().unwrap();
+        db.set(b"k00".to_vec(), b"after".to_vec()).unwrap();
+
+        let mut seen = vec![first];
+        seen.extend(iter.map(Result::unwrap));
+        assert_eq!(seen.len(), 20);
+        for (i, (key, value)) in seen.into_iter().enumerate() {
+            assert_eq!(key, format!("k{i:02}").into_bytes());
+            assert_eq!(value, format!("w{i}").into_bytes());
+        }
+        assert_eq!(db.shared.live_snapshots.load(Ordering::Relaxed), 0);
+    }
+
+    #[test]
+    fn export_keyspace_writes_an_openable_copy() {
+        let dir = TempDir::new("snapshot-export");
+        let db = dir.open();
+        fill(&db, 3);
+        db.set(b"other".to_vec(), b"x".to_vec()).unwrap();
+        let path = dir.path().join("export.db");
+        assert_eq!(db.export_keyspace(b"k", &path).unwrap(), 3);
+        assert!(db.export_keyspace(b"k", &path).is_err());
+
+        let copy = MyDatabase::new(DatabaseConfig {
+            file_path: path,
+            ..DatabaseConfig::default()
+        })
+        .unwrap();
+        assert_eq!(copy.keys(b"").unwrap().count(), 3);
+        assert_eq!(copy.get(b"k01").unwrap(), Some(b"v1".to_vec()));
+        assert!(!copy.contains_key(b"other").unwrap());
+    }
+}
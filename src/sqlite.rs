@@ -0,0 +1,83 @@
+use crate::db::MyDatabase;
+use crate::error::DatabaseError;
+use rusqlite::Connection;
+use rusqlite::types::ValueRef;
+use std::path::Path;
+
+impl MyDatabase {
+    /// Exporte toutes les entrées vivantes dans une table SQLite `(key BLOB, value BLOB)`.
+    /// La table est créée si besoin ; les clés existantes sont remplacées.
+    pub fn export_sqlite(
+        &self,
+        path: impl AsRef<Path>,
+        table: &str,
+    ) -> Result<usize, DatabaseError> {
+        let entries = {
+            let _access_guard = self.read_access()?;
+            self.read_live_entries()?
+        };
+
+        let table = quote_identifier(table);
+        let mut conn = Connection::open(path)?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            &format!(
+                "CREATE TABLE IF NOT EXISTS {table} (key BLOB PRIMARY KEY, value BLOB NOT NULL)"
+            ),
+            [],
+        )?;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT OR REPLACE INTO {table} (key, value) VALUES (?1, ?2)"
+            ))?;
+            for (key, value) in &entries {
+                stmt.execute((key, value))?;
+            }
+        }
+        tx.commit()?;
+
+        Ok(entries.len())
+    }
+
+    /// Importe les lignes retournées par `query` (colonne 0 = clé, colonne 1 = valeur).
+    /// Les colonnes TEXT, BLOB et numériques sont acceptées ; NULL est refusé.
+    pub fn import_sqlite(
+        &self,
+        path: impl AsRef<Path>,
+        query: &str,
+    ) -> Result<usize, DatabaseError> {
+        let conn = Connection::open(path)?;
+        let mut stmt = conn.prepare(query)?;
+        if stmt.column_count() < 2 {
+            return Err(DatabaseError::ParseError(
+                "la requête doit retourner au moins deux colonnes (clé, valeur)".to_string(),
+            ));
+        }
+
+        let mut rows = stmt.query([])?;
+        let mut count = 0;
+        while let Some(row) = rows.next()? {
+            let key = value_to_bytes(row.get_ref(0)?)?;
+            let value = value_to_bytes(row.get_ref(1)?)?;
+            self.set(key, value)?;
+            count += 1;
+        }
+
+        Ok(count)
+    }
+}
+
+fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+fn value_to_bytes(value: ValueRef<'_>) -> Result<Vec<u8>, DatabaseError> {
+    match value {
+        ValueRef::Blob(bytes) | ValueRef::Text(bytes) => Ok(bytes.to_vec()),
+        ValueRef::Integer(n) => Ok(n.to_string().into_bytes()),
+        ValueRef::Real(n) => Ok(n.to_string().into_bytes()),
+        ValueRef::Null => Err(DatabaseError::ParseError(
+            "valeur NULL non supportée".to_string(),
+        )),
+    }
+}
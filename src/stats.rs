@@ -0,0 +1,192 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+/// Instantané des statistiques de la base.
+#[derive(Debug, Clone)]
+pub struct DatabaseStats {
+    pub file_size: u64,
+    pub index_len: usize,
+    pub compaction_throttle: ThrottleStats,
+    /// Reprises effectuées après un panic ayant empoisonné un verrou.
+    pub poison_recoveries: u64,
+    pub lock_contention: LockContentionStats,
+    /// Valeurs écrites depuis l'ouverture, avant et après compression.
+    pub compression: CompressionStats,
+}
+
+/// État du limiteur de débit de la compaction.
+#[derive(Debug, Clone, Copy)]
+pub struct ThrottleStats {
+    pub bytes_per_sec: Option<u64>,
+    pub active: bool,
+    pub total_wait: Duration,
+}
+
+/// Estimation du résultat d'une compaction.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactionEstimate {
+    pub file_size: u64,
+    pub live_records: usize,
+    pub live_bytes: u64,
+    pub reclaimable_bytes: u64,
+}
+
+/// Attente cumulée pour obtenir un verrou.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LockWaitStats {
+    pub acquisitions: u64,
+    pub total_wait: Duration,
+    pub max_wait: Duration,
+}
+
+/// Contention des verrous par verrou et par mode : les lectures (`get`,
+/// `scan`, ...) les prennent en partage, les écritures et le remplacement du
+/// journal par la compaction en exclusif.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LockContentionStats {
+    pub access_shared: LockWaitStats,
+    pub access_exclusive: LockWaitStats,
+    pub index_shared: LockWaitStats,
+    pub index_exclusive: LockWaitStats,
+    pub file: LockWaitStats,
+}
+
+/// Volume des valeurs écrites par un compresseur.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompressorStats {
+    pub values: u64,
+    /// Octets avant compression.
+    pub raw_bytes: u64,
+    /// Octets stockés dans le journal.
+    pub compressed_bytes: u64,
+}
+
+impl CompressorStats {
+    /// Taille stockée rapportée à la taille d'origine (1.0 si rien n'a été
+    /// écrit) : plus il est bas, plus la compression est utile.
+    pub fn ratio(&self) -> f64 {
+        if self.raw_bytes == 0 {
+            return 1.0;
+        }
+        self.compressed_bytes as f64 / self.raw_bytes as f64
+    }
+}
+
+/// Compression des valeurs, par compresseur. Les réécritures par la
+/// compaction ne sont pas comptées.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CompressionStats {
+    pub lz77: CompressorStats,
+    /// Valeurs stockées sans compression, faute d'un gain suffisant (voir
+    /// `DatabaseConfig::min_compression_savings`).
+    pub raw: CompressorStats,
+}
+
+impl CompressionStats {
+    /// Cumul de tous les compresseurs.
+    pub fn total(&self) -> CompressorStats {
+        CompressorStats {
+            values: self.lz77.values + self.raw.values,
+            raw_bytes: self.lz77.raw_bytes + self.raw.raw_bytes,
+            compressed_bytes: self.lz77.compressed_bytes + self.raw.compressed_bytes,
+        }
+    }
+}
+
+/// Compteurs de `CompressionStats`, mis à jour sans verrou.
+#[derive(Debug, Default)]
+pub(crate) struct CompressionCounters {
+    lz77: CompressorCounter,
+    raw: CompressorCounter,
+}
+
+#[derive(Debug, Default)]
+struct CompressorCounter {
+    values: AtomicU64,
+    raw_bytes: AtomicU64,
+    compressed_bytes: AtomicU64,
+}
+
+impl CompressionCounters {
+    /// Compte une valeur de `raw` octets stockée sur `compressed` octets,
+    /// sans compression si `stored_raw`.
+    pub(crate) fn record(&self, raw: usize, compressed: usize, stored_raw: bool) {
+        let counter = match stored_raw {
+            true => &self.raw,
+            false => &self.lz77,
+        };
+        counter.values.fetch_add(1, Ordering::Relaxed);
+        counter.raw_bytes.fetch_add(raw as u64, Ordering::Relaxed);
+        counter
+            .compressed_bytes
+            .fetch_add(compressed as u64, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> CompressionStats {
+        CompressionStats {
+            lz77: self.lz77.snapshot(),
+            raw: self.raw.snapshot(),
+        }
+    }
+}
+
+impl CompressorCounter {
+    fn snapshot(&self) -> CompressorStats {
+        CompressorStats {
+            values: self.values.load(Ordering::Relaxed),
+            raw_bytes: self.raw_bytes.load(Ordering::Relaxed),
+            compressed_bytes: self.compressed_bytes.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Verrous instrumentés.
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum LockKind {
+    AccessShared,
+    AccessExclusive,
+    IndexShared,
+    IndexExclusive,
+    File,
+}
+
+/// Compteurs de `LockContentionStats`, mis à jour sans verrou.
+#[derive(Debug, Default)]
+pub(crate) struct LockWaits {
+    counters: [WaitCounter; 5],
+}
+
+#[derive(Debug, Default)]
+struct WaitCounter {
+    acquisitions: AtomicU64,
+    total_nanos: AtomicU64,
+    max_nanos: AtomicU64,
+}
+
+impl LockWaits {
+    pub(crate) fn record(&self, kind: LockKind, wait: Duration) {
+        let nanos = u64::try_from(wait.as_nanos()).unwrap_or(u64::MAX);
+        let counter = &self.counters[kind as usize];
+        counter.acquisitions.fetch_add(1, Ordering::Relaxed);
+        counter.total_nanos.fetch_add(nanos, Ordering::Relaxed);
+        counter.max_nanos.fetch_max(nanos, Ordering::Relaxed);
+    }
+
+    pub(crate) fn snapshot(&self) -> LockContentionStats {
+        let stats = |kind: LockKind| {
+            let counter = &self.counters[kind as usize];
+            LockWaitStats {
+                acquisitions: counter.acquisitions.load(Ordering::Relaxed),
+                total_wait: Duration::from_nanos(counter.total_nanos.load(Ordering::Relaxed)),
+                max_wait: Duration::from_nanos(counter.max_nanos.load(Ordering::Relaxed)),
+            }
+        };
+        LockContentionStats {
+            access_shared: stats(LockKind::AccessShared),
+            access_exclusive: stats(LockKind::AccessExclusive),
+            index_shared: stats(LockKind::IndexShared),
+            index_exclusive: stats(LockKind::IndexExclusive),
+            file: stats(LockKind::File),
+        }
+    }
+}
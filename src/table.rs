@@ -0,0 +1,235 @@
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+/// Rendu d'une sortie en plusieurs lignes (`--format`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// Colonnes alignées, avec une ligne d'en-tête.
+    Table,
+    /// Tableau d'objets, un par ligne.
+    Json,
+    /// En-tête puis une ligne par enregistrement, séparateur virgule.
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Valeur d'une cellule ; les nombres sont alignés à droite et écrits sans
+/// guillemets en JSON, une cellule vide y vaut `null`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cell {
+    Text(String),
+    Number(String),
+    Empty,
+}
+
+impl Cell {
+    fn as_str(&self) -> &str {
+        match self {
+            Cell::Text(text) | Cell::Number(text) => text,
+            Cell::Empty => "",
+        }
+    }
+}
+
+impl From<String> for Cell {
+    fn from(text: String) -> Self {
+        Cell::Text(text)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(text: &str) -> Self {
+        Cell::Text(text.to_string())
+    }
+}
+
+impl From<u64> for Cell {
+    fn from(n: u64) -> Self {
+        Cell::Number(n.to_string())
+    }
+}
+
+impl From<usize> for Cell {
+    fn from(n: usize) -> Self {
+        Cell::Number(n.to_string())
+    }
+}
+
+impl From<u32> for Cell {
+    fn from(n: u32) -> Self {
+        Cell::Number(n.to_string())
+    }
+}
+
+impl From<f64> for Cell {
+    fn from(n: f64) -> Self {
+        Cell::Number(format!("{:.2}", n))
+    }
+}
+
+/// Lignes à colonnes fixes, rendues dans l'un des `Format`.
+pub struct Table {
+    columns: &'static [&'static str],
+    rows: Vec<Vec<Cell>>,
+}
+
+impl Table {
+    pub fn new(columns: &'static [&'static str]) -> Self {
+        Self {
+            columns,
+            rows: Vec::new(),
+        }
+    }
+
+    /// Ajoute une ligne ; elle doit avoir une cellule par colonne.
+    pub fn push(&mut self, row: Vec<Cell>) {
+        debug_assert_eq!(row.len(), self.columns.len());
+        self.rows.push(row);
+    }
+
+    /// Adaptateur `Display` pour le format `format`.
+    pub fn render(&self, format: Format) -> Rendered<'_> {
+        Rendered {
+            table: self,
+            format,
+        }
+    }
+
+    fn fmt_table(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut widths: Vec<usize> = self.columns.iter().map(|c| c.chars().count()).collect();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.as_str().chars().count());
+            }
+        }
+
+        let header: Vec<Cell> = self.columns.iter().map(|&c| Cell::from(c)).collect();
+        write_aligned(f, &header, &widths)?;
+        let rule: Vec<Cell> = widths.iter().map(|&w| Cell::from("-".repeat(w))).collect();
+        write_aligned(f, &rule, &widths)?;
+        for row in &self.rows {
+            write_aligned(f, row, &widths)?;
+        }
+        Ok(())
+    }
+
+    fn fmt_json(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('[')?;
+        for (i, row) in self.rows.iter().enumerate() {
+            if i > 0 {
+                f.write_char(',')?;
+            }
+            f.write_char('{')?;
+            for (j, (column, cell)) in self.columns.iter().zip(row).enumerate() {
+                if j > 0 {
+                    f.write_char(',')?;
+                }
+                write_json_string(f, column)?;
+                f.write_char(':')?;
+                match cell {
+                    Cell::Number(n) => f.write_str(n)?,
+                    Cell::Text(text) => write_json_string(f, text)?,
+                    Cell::Empty => f.write_str("null")?,
+                }
+            }
+            f.write_char('}')?;
+        }
+        writeln!(f, "]")
+    }
+
+    fn fmt_csv(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_csv_line(f, self.columns.iter().copied())?;
+        for row in &self.rows {
+            write_csv_line(f, row.iter().map(Cell::as_str))?;
+        }
+        Ok(())
+    }
+}
+
+/// Table rendue dans un format donné (voir `Table::render`).
+pub struct Rendered<'a> {
+    table: &'a Table,
+    format: Format,
+}
+
+impl fmt::Display for Rendered<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.format {
+            Format::Table => self.table.fmt_table(f),
+            Format::Json => self.table.fmt_json(f),
+            Format::Csv => self.table.fmt_csv(f),
+        }
+    }
+}
+
+/// Écrit `row` en colonnes de largeurs `widths`, séparées par deux espaces.
+fn write_aligned(f: &mut fmt::Formatter<'_>, row: &[Cell], widths: &[usize]) -> fmt::Result {
+    let mut line = String::new();
+    for (i, (cell, &width)) in row.iter().zip(widths).enumerate() {
+        if i > 0 {
+            line.push_str("  ");
+        }
+        match cell {
+            Cell::Number(n) => write!(line, "{:>width$}", n)?,
+            Cell::Text(text) => write!(line, "{:<width$}", text)?,
+            Cell::Empty => write!(line, "{:width$}", "")?,
+        }
+    }
+    writeln!(f, "{}", line.trim_end())
+}
+
+/// Chaîne JSON entre guillemets, caractères de contrôle échappés.
+pub struct JsonString<'a>(pub &'a str);
+
+impl fmt::Display for JsonString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_json_string(f, self.0)
+    }
+}
+
+fn write_json_string(f: &mut fmt::Formatter<'_>, text: &str) -> fmt::Result {
+    f.write_char('"')?;
+    for c in text.chars() {
+        match c {
+            '"' => f.write_str("\\\"")?,
+            '\\' => f.write_str("\\\\")?,
+            '\n' => f.write_str("\\n")?,
+            '\r' => f.write_str("\\r")?,
+            '\t' => f.write_str("\\t")?,
+            c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+            c => f.write_char(c)?,
+        }
+    }
+    f.write_char('"')
+}
+
+/// Écrit une ligne CSV ; un champ contenant une virgule, un guillemet ou un
+/// saut de ligne est entouré de guillemets, ceux qu'il contient doublés.
+fn write_csv_line<'a>(
+    f: &mut fmt::Formatter<'_>,
+    fields: impl Iterator<Item = &'a str>,
+) -> fmt::Result {
+    for (i, field) in fields.enumerate() {
+        if i > 0 {
+            f.write_char(',')?;
+        }
+        if field.contains([',', '"', '\n', '\r']) {
+            write!(f, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            f.write_str(field)?;
+        }
+    }
+    writeln!(f)
+}
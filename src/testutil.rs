@@ -0,0 +1,41 @@
+//! Outils communs aux tests unitaires.
+
+use crate::db::{DatabaseConfig, MyDatabase};
+use std::path::{Path, PathBuf};
+
+/// Répertoire temporaire propre à un test, supprimé à la fin de celui-ci.
+pub(crate) struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    pub(crate) fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("rdb-test-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self { path }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Configuration par défaut d'une base `db.db` dans ce répertoire.
+    pub(crate) fn config(&self) -> DatabaseConfig {
+        DatabaseConfig {
+            file_path: self.path.join("db.db"),
+            ..DatabaseConfig::default()
+        }
+    }
+
+    /// Ouvre (ou rouvre) la base de `config`.
+    pub(crate) fn open(&self) -> MyDatabase {
+        MyDatabase::new(self.config()).unwrap()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
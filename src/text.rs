@@ -0,0 +1,400 @@
+use crate::codec::{DataEntry, EntryType};
+use crate::db::{self, IndexEntry, MyDatabase};
+use crate::error::{DatabaseError, IoContext, IoOp, LockResource};
+use crate::merge::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+const SNAPSHOT_MAGIC: &[u8; 8] = b"RDBTEXT1";
+
+/// Réglages du tokeniseur de l'index plein texte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextIndexConfig {
+    /// Longueur minimale (en caractères) d'un terme indexé.
+    pub min_token_len: usize,
+    /// Longueur maximale (en caractères) d'un terme indexé.
+    pub max_token_len: usize,
+}
+
+impl Default for TextIndexConfig {
+    fn default() -> Self {
+        Self {
+            min_token_len: 2,
+            max_token_len: 64,
+        }
+    }
+}
+
+/// Index inversé terme -> clés, avec la fréquence du terme dans chaque valeur.
+pub(crate) struct TextIndex {
+    config: TextIndexConfig,
+    postings: HashMap<String, HashMap<Vec<u8>, u32>>,
+    documents: HashMap<Vec<u8>, Vec<(String, u32)>>,
+}
+
+impl TextIndex {
+    pub(crate) fn new(config: TextIndexConfig) -> Self {
+        Self {
+            config,
+            postings: HashMap::new(),
+            documents: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn config(&self) -> TextIndexConfig {
+        self.config
+    }
+
+    /// Réindexe `key` d'après sa valeur courante. Les valeurs absentes, non
+    /// UTF-8 ou de type collection sont retirées de l'index.
+    pub(crate) fn update(&mut self, key: &[u8], value: Option<&Value>) {
+        self.remove(key);
+        let Some(Value::Bytes(bytes)) = value else {
+            return;
+        };
+        let Ok(text) = std::str::from_utf8(bytes) else {
+            return;
+        };
+
+        let mut frequencies: HashMap<String, u32> = HashMap::new();
+        for token in tokenize(text, &self.config) {
+            *frequencies.entry(token).or_default() += 1;
+        }
+        self.insert(key.to_vec(), frequencies.into_iter().collect());
+    }
+
+    fn insert(&mut self, key: Vec<u8>, terms: Vec<(String, u32)>) {
+        if terms.is_empty() {
+            return;
+        }
+        for (term, count) in &terms {
+            self.postings
+                .entry(term.clone())
+                .or_default()
+                .insert(key.clone(), *count);
+        }
+        self.documents.insert(key, terms);
+    }
+
+    fn remove(&mut self, key: &[u8]) {
+        let Some(terms) = self.documents.remove(key) else {
+            return;
+        };
+        for (term, _) in terms {
+            if let Some(keys) = self.postings.get_mut(&term) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(&term);
+                }
+            }
+        }
+    }
+
+    /// Clés contenant tous les termes de `query`, triées par score TF-IDF
+    /// décroissant.
+    pub(crate) fn search(&self, query: &str) -> Vec<(Vec<u8>, f64)> {
+        let terms: HashSet<String> = tokenize(query, &self.config).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let total = self.documents.len() as f64;
+        let mut scores: Option<HashMap<&Vec<u8>, f64>> = None;
+        for term in &terms {
+            let Some(keys) = self.postings.get(term) else {
+                return Vec::new();
+            };
+            let idf = (1.0 + total / keys.len() as f64).ln();
+            scores = Some(match scores {
+                None => keys
+                    .iter()
+                    .map(|(key, count)| (key, *count as f64 * idf))
+                    .collect(),
+                Some(previous) => previous
+                    .into_iter()
+                    .filter_map(|(key, score)| {
+                        keys.get(key)
+                            .map(|count| (key, score + *count as f64 * idf))
+                    })
+                    .collect(),
+            });
+        }
+
+        let mut hits: Vec<(Vec<u8>, f64)> = scores
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(key, score)| (key.clone(), score))
+            .collect();
+        hits.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        hits
+    }
+
+    /// Sérialise l'index avec la position du journal qu'il reflète.
+    fn to_bytes(&self, log_offset: u64) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(SNAPSHOT_MAGIC);
+        out.extend_from_slice(&log_offset.to_be_bytes());
+        out.extend_from_slice(&(self.config.min_token_len as u32).to_be_bytes());
+        out.extend_from_slice(&(self.config.max_token_len as u32).to_be_bytes());
+        out.extend_from_slice(&(self.documents.len() as u32).to_be_bytes());
+        for (key, terms) in &self.documents {
+            out.extend_from_slice(&(key.len() as u32).to_be_bytes());
+            out.extend_from_slice(key);
+            out.extend_from_slice(&(terms.len() as u32).to_be_bytes());
+            for (term, count) in terms {
+                out.extend_from_slice(&(term.len() as u32).to_be_bytes());
+                out.extend_from_slice(term.as_bytes());
+                out.extend_from_slice(&count.to_be_bytes());
+            }
+        }
+        out
+    }
+
+    /// Relit un instantané ; `None` s'il est illisible ou construit avec
+    /// d'autres réglages.
+    fn from_bytes(input: &[u8], config: TextIndexConfig) -> Option<(Self, u64)> {
+        let mut reader = SnapshotReader { input, pos: 0 };
+        if reader.bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return None;
+        }
+        let log_offset = u64::from_be_bytes(reader.bytes(8)?.try_into().ok()?);
+        let min_token_len = reader.u32()? as usize;
+        let max_token_len = reader.u32()? as usize;
+        if (min_token_len, max_token_len) != (config.min_token_len, config.max_token_len) {
+            return None;
+        }
+
+        let mut index = Self::new(config);
+        for _ in 0..reader.u32()? {
+            let len = reader.u32()? as usize;
+            let key = reader.bytes(len)?.to_vec();
+            let mut terms = Vec::new();
+            for _ in 0..reader.u32()? {
+                let len = reader.u32()? as usize;
+                let term = String::from_utf8(reader.bytes(len)?.to_vec()).ok()?;
+                terms.push((term, reader.u32()?));
+            }
+            index.insert(key, terms);
+        }
+        (reader.pos == input.len()).then_some((index, log_offset))
+    }
+}
+
+struct SnapshotReader<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl SnapshotReader<'_> {
+    fn bytes(&mut self, len: usize) -> Option<&[u8]> {
+        let end = self.pos.checked_add(len)?;
+        let bytes = self.input.get(self.pos..end)?;
+        self.pos = end;
+        Some(bytes)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        Some(u32::from_be_bytes(self.bytes(4)?.try_into().ok()?))
+    }
+}
+
+/// Découpe en termes alphanumériques, en minuscules.
+fn tokenize<'a>(text: &'a str, config: &'a TextIndexConfig) -> impl Iterator<Item = String> + 'a {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|token| {
+            let len = token.chars().count();
+            len >= config.min_token_len && len <= config.max_token_len
+        })
+        .map(str::to_lowercase)
+}
+
+impl MyDatabase {
+    /// Active l'index plein texte : les valeurs UTF-8 sont tokenisées à
+    /// l'écriture. L'index est rechargé depuis son instantané `<fichier>.text`
+    /// s'il existe (puis rattrapé sur la fin du journal), sinon reconstruit.
+    pub fn enable_text_index(&self, config: TextIndexConfig) -> Result<(), DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let log_end = std::fs::metadata(&self.config.file_path)
+            .context(IoOp::LogSize, &self.config.file_path)?
+            .len();
+
+        let snapshot = match std::fs::read(self.text_snapshot_path()) {
+            Ok(bytes) => TextIndex::from_bytes(&bytes, config)
+                .filter(|(_, log_offset)| *log_offset <= log_end),
+            Err(err) if err.kind() == ErrorKind::NotFound => None,
+            Err(err) => {
+                return Err(DatabaseError::io(
+                    IoOp::TextIndexRead,
+                    self.text_snapshot_path(),
+                    err,
+                ));
+            }
+        };
+        let text = match snapshot {
+            Some((mut text, log_offset)) => match self.catch_up_text(&mut text, log_offset) {
+                Ok(()) => text,
+                Err(_) => self.build_text_index(config)?,
+            },
+            None => self.build_text_index(config)?,
+        };
+
+        self.persist_text_index(&text, log_end)?;
+        *self
+            .shared
+            .text
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))? = Some(text);
+        Ok(())
+    }
+
+    /// Recherche les clés dont la valeur contient tous les termes de `query`,
+    /// classées par score décroissant.
+    pub fn search(&self, query: &str) -> Result<Vec<(Vec<u8>, f64)>, DatabaseError> {
+        let _access_guard = self.read_access()?;
+        let text = self
+            .shared
+            .text
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))?;
+        match text.as_ref() {
+            Some(text) => Ok(text.search(query)),
+            None => Err(DatabaseError::TextIndexDisabled),
+        }
+    }
+
+    /// Répercute une écriture sur l'index plein texte s'il est actif.
+    /// L'appelant doit détenir le verrou d'écriture `access`.
+    pub(crate) fn sync_text_entry(
+        &self,
+        entry: &DataEntry,
+        location: &IndexEntry,
+    ) -> Result<(), DatabaseError> {
+        let mut text = self
+            .shared
+            .text
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))?;
+        let Some(text) = text.as_mut() else {
+            return Ok(());
+        };
+
+        match entry.entry_type {
+            EntryType::Data => {
+                text.update(&entry.key, Some(&Value::Bytes(entry.value.clone())));
+            }
+            EntryType::Tombstone => text.update(&entry.key, None),
+            EntryType::Merge => {
+                let mut reader = self.log_reader()?;
+                let (value, _) = Self::resolve(&mut reader, location, &entry.key)?;
+                text.update(&entry.key, value.as_ref());
+            }
+            EntryType::Dictionary | EntryType::Blob => {}
+        }
+        Ok(())
+    }
+
+    pub(crate) fn text_snapshot_path(&self) -> PathBuf {
+        self.config.file_path.with_extension("db.text")
+    }
+
+    /// Écrit durablement l'instantané de l'index, associé à la position
+    /// `log_offset` du journal. Rien n'est écrit en lecture seule : un
+    /// suiveur ne modifie pas les fichiers de l'écrivain.
+    pub(crate) fn persist_text_index(
+        &self,
+        text: &TextIndex,
+        log_offset: u64,
+    ) -> Result<(), DatabaseError> {
+        if self.shared.read_only {
+            return Ok(());
+        }
+        let path = self.text_snapshot_path();
+        let temp_path = path.with_extension("text.tmp");
+        let mut file = File::create(&temp_path).context(IoOp::TextIndexWrite, &temp_path)?;
+        file.write_all(&text.to_bytes(log_offset))
+            .context(IoOp::TextIndexWrite, &temp_path)?;
+        file.sync_all().context(IoOp::TextIndexSync, &temp_path)?;
+        drop(file);
+        replace_file(&temp_path, &path)?;
+        db::sync_parent_dir(&path)
+    }
+
+    /// Reconstruit l'index à partir des valeurs vivantes.
+    pub(crate) fn build_text_index(
+        &self,
+        config: TextIndexConfig,
+    ) -> Result<TextIndex, DatabaseError> {
+        let index = self.index_read()?;
+        let mut text = TextIndex::new(config);
+        let mut reader = self.log_reader()?;
+        for (key, entry) in index.iter().filter(|(_, entry)| !entry.tombstone) {
+            let (value, _) = Self::resolve(&mut reader, entry, key)?;
+            text.update(key, value.as_ref());
+        }
+        Ok(text)
+    }
+
+    /// Réindexe les clés écrites dans le journal après `log_offset`.
+    fn catch_up_text(&self, text: &mut TextIndex, log_offset: u64) -> Result<(), DatabaseError> {
+        let mut keys = HashSet::new();
+        for record in self.log_iter_from(log_offset)? {
+            let record = record?;
+            if !record.checksum_ok {
+                return Err(DatabaseError::CorruptedRecord {
+                    path: self.config.file_path.clone(),
+                    offset: record.offset,
+                });
+            }
+            if record.entry_type != EntryType::Blob {
+                keys.insert(record.key);
+            }
+        }
+
+        self.reindex_text_keys(text, keys)
+    }
+
+    /// Réindexe `keys` si l'index plein texte est actif. L'appelant doit
+    /// détenir le verrou `access`.
+    pub(crate) fn refresh_text_keys(&self, keys: HashSet<Vec<u8>>) -> Result<(), DatabaseError> {
+        let mut text = self
+            .shared
+            .text
+            .lock()
+            .map_err(|_| DatabaseError::LockPoisoned(LockResource::TextIndex))?;
+        match text.as_mut() {
+            Some(text) => self.reindex_text_keys(text, keys),
+            None => Ok(()),
+        }
+    }
+
+    fn reindex_text_keys(
+        &self,
+        text: &mut TextIndex,
+        keys: HashSet<Vec<u8>>,
+    ) -> Result<(), DatabaseError> {
+        let index = self.index_read()?;
+        let mut reader = self.log_reader()?;
+        for key in keys {
+            let value = match index.get(&key).filter(|entry| !entry.tombstone) {
+                Some(entry) => Self::resolve(&mut reader, entry, &key)?.0,
+                None => None,
+            };
+            text.update(&key, value.as_ref());
+        }
+        Ok(())
+    }
+}
+
+fn replace_file(from: &Path, to: &Path) -> Result<(), DatabaseError> {
+    match std::fs::rename(from, to) {
+        Ok(_) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::AlreadyExists => {
+            std::fs::remove_file(to).context(IoOp::TextIndexReplace, to)?;
+            std::fs::rename(from, to).context(IoOp::TextIndexReplace, to)?;
+            Ok(())
+        }
+        Err(err) => Err(DatabaseError::io(IoOp::TextIndexReplace, to, err)),
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// État partagé du limiteur de débit de la compaction.
+#[derive(Default)]
+pub struct ThrottleState {
+    active: AtomicBool,
+    waited_micros: AtomicU64,
+}
+
+/// Marque la compaction comme en cours tant qu'il est vivant.
+pub(crate) struct ThrottleGuard<'a> {
+    state: &'a ThrottleState,
+}
+
+/// Limiteur de débit en octets par seconde.
+pub(crate) struct RateLimiter<'a> {
+    bytes_per_sec: Option<u64>,
+    started: Instant,
+    written: u64,
+    state: &'a ThrottleState,
+}
+
+impl ThrottleState {
+    pub(crate) fn begin(&self) -> ThrottleGuard<'_> {
+        self.active.store(true, Ordering::Relaxed);
+        ThrottleGuard { state: self }
+    }
+
+    /// Indique si une compaction limitée est en cours.
+    pub fn is_active(&self) -> bool {
+        self.active.load(Ordering::Relaxed)
+    }
+
+    /// Temps total passé en attente par le limiteur.
+    pub fn total_wait(&self) -> Duration {
+        Duration::from_micros(self.waited_micros.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for ThrottleGuard<'_> {
+    fn drop(&mut self) {
+        self.state.active.store(false, Ordering::Relaxed);
+    }
+}
+
+impl<'a> RateLimiter<'a> {
+    pub(crate) fn new(bytes_per_sec: Option<u64>, state: &'a ThrottleState) -> Self {
+        Self {
+            bytes_per_sec: bytes_per_sec.filter(|rate| *rate > 0),
+            started: Instant::now(),
+            written: 0,
+            state,
+        }
+    }
+
+    /// Comptabilise `bytes` octets écrits et dort si le budget est dépassé.
+    pub(crate) fn consume(&mut self, bytes: usize) {
+        let Some(rate) = self.bytes_per_sec else {
+            return;
+        };
+
+        self.written += bytes as u64;
+        let expected = Duration::from_secs_f64(self.written as f64 / rate as f64);
+        let elapsed = self.started.elapsed();
+        if expected > elapsed {
+            let wait = expected - elapsed;
+            self.state
+                .waited_micros
+                .fetch_add(wait.as_micros() as u64, Ordering::Relaxed);
+            thread::sleep(wait);
+        }
+    }
+}
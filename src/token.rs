@@ -0,0 +1,114 @@
+use crate::db::MyDatabase;
+use crate::error::DatabaseError;
+use std::sync::atomic::Ordering;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Intervalle de vérification de `wait_for`.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// Position dans l'historique des écritures d'une base ouverte : toute
+/// lecture satisfaisant le jeton voit les écritures qui l'ont précédé.
+///
+/// Un jeton n'a de sens que pour la base qui l'a émis et ses clones. Les
+/// clones partageant l'index, un jeton est satisfait dès son émission ; il
+/// rend la garantie explicite pour le code qui transmet les jetons entre
+/// composants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SyncToken {
+    applied: u64,
+}
+
+impl MyDatabase {
+    /// Jeton couvrant toutes les écritures déjà visibles.
+    pub fn sync_token(&self) -> SyncToken {
+        SyncToken {
+            applied: self.shared.applied_writes.load(Ordering::Acquire),
+        }
+    }
+
+    /// Attend au plus `timeout` que les écritures couvertes par `token`
+    /// soient visibles ; `DatabaseError::Timeout` sinon.
+    pub fn wait_for(&self, token: SyncToken, timeout: Duration) -> Result<(), DatabaseError> {
+        let started = Instant::now();
+        while self.sync_token() < token {
+            if started.elapsed() >= timeout {
+                return Err(DatabaseError::Timeout);
+            }
+            thread::sleep(WAIT_POLL_INTERVAL.min(timeout.saturating_sub(started.elapsed())));
+        }
+        Ok(())
+    }
+
+    /// `get` après `wait_for(token, timeout)`.
+    pub fn read_at_least(
+        &self,
+        key: &[u8],
+        token: SyncToken,
+        timeout: Duration,
+    ) -> Result<Option<Vec<u8>>, DatabaseError> {
+        self.wait_for(token, timeout)?;
+        self.get(key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testutil::TempDir;
+
+    #[test]
+    fn set_returns_a_token_covering_the_write() {
+        let dir = TempDir::new("token-set");
+        let db = dir.open();
+        let before = db.sync_token();
+        let token = db.set(b"k".to_vec(), b"v".to_vec()).unwrap();
+        assert!(token > before);
+        assert_eq!(token, db.sync_token());
+        let read = db.read_at_least(b"k", token, Duration::ZERO).unwrap();
+        assert_eq!(read, Some(b"v".to_vec()));
+    }
+
+    #[test]
+    fn clones_share_the_token_sequence() {
+        let dir = TempDir::new("token-clone");
+        let db = dir.open();
+        let token = db.clone().set(b"k".to_vec(), b"v".to_vec()).unwrap();
+        db.wait_for(token, Duration::ZERO).unwrap();
+        assert_eq!(db.get(b"k").unwrap(), Some(b"v".to_vec()));
+    }
+
+    #[test]
+    fn wait_for_times_out_on_a_future_token() {
+        let dir = TempDir::new("token-timeout");
+        let db = dir.open();
+        let future = SyncToken {
+            applied: db.sync_token().applied + 1,
+        };
+        let started = Instant::now();
+        let err = db.wait_for(future, Duration::from_millis(20)).unwrap_err();
+        assert!(matches!(err, DatabaseError::Timeout));
+        assert!(started.elapsed() >= Duration::from_millis(20));
+        let err = db
+            .read_at_least(b"k", future, Duration::from_millis(1))
+            .unwrap_err();
+        assert!(matches!(err, DatabaseError::Timeout));
+    }
+
+    #[test]
+    fn wait_for_returns_once_another_thread_writes() {
+        let dir = TempDir::new("token-wait");
+        let db = dir.open();
+        let future = SyncToken {
+            applied: db.sync_token().applied + 1,
+        };
+        let writer = db.clone();
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(10));
+            writer.set(b"k".to_vec(), b"v".to_vec()).unwrap()
+        });
+        let read = db.read_at_least(b"k", future, Duration::from_secs(10));
+        assert_eq!(read.unwrap(), Some(b"v".to_vec()));
+        assert_eq!(handle.join().unwrap(), future);
+    }
+}
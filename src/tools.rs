@@ -0,0 +1,152 @@
+//! Outils hors ligne opérant directement sur des fichiers de base.
+
+use crate::codec;
+use crate::db::{IndexEntry, LogReader, MyDatabase};
+use crate::error::DatabaseError;
+use crate::merge::{self, Value};
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::path::Path;
+use std::vec;
+
+/// Différences entre deux fichiers de base, clés triées.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct DiffReport {
+    /// Clés présentes uniquement dans A, avec la taille de leur valeur.
+    pub only_in_a: Vec<(Vec<u8>, u64)>,
+    /// Clés présentes uniquement dans B, avec la taille de leur valeur.
+    pub only_in_b: Vec<(Vec<u8>, u64)>,
+    /// Clés dont la valeur diffère, avec sa taille dans A puis dans B.
+    pub changed: Vec<(Vec<u8>, u64, u64)>,
+}
+
+impl DiffReport {
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compare les valeurs vivantes de deux fichiers, lus sans être ouverts.
+/// Seuls les index sont gardés en mémoire : les valeurs sont lues et
+/// comparées une clé à la fois, en parcourant les clés des deux fichiers
+/// dans l'ordre.
+pub fn diff(
+    path_a: impl AsRef<Path>,
+    path_b: impl AsRef<Path>,
+) -> Result<DiffReport, DatabaseError> {
+    let mut a = LiveSide::open(path_a.as_ref())?;
+    let mut b = LiveSide::open(path_b.as_ref())?;
+
+    let mut report = DiffReport::default();
+    loop {
+        let order = match (a.peek(), b.peek()) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some(key_a), Some(key_b)) => key_a.cmp(key_b),
+        };
+        match order {
+            Ordering::Less => {
+                if let Some((key, value)) = a.next_value()? {
+                    report.only_in_a.push((key, merge::value_size(&value)));
+                }
+            }
+            Ordering::Greater => {
+                if let Some((key, value)) = b.next_value()? {
+                    report.only_in_b.push((key, merge::value_size(&value)));
+                }
+            }
+            Ordering::Equal => match (a.next_value()?, b.next_value()?) {
+                (Some((key, value_a)), Some((_, value_b))) if value_a != value_b => {
+                    report.changed.push((
+                        key,
+                        merge::value_size(&value_a),
+                        merge::value_size(&value_b),
+                    ));
+                }
+                (Some(_), Some(_)) | (None, None) => {}
+                (Some((key, value)), None) => {
+                    report.only_in_a.push((key, merge::value_size(&value)));
+                }
+                (None, Some((key, value))) => {
+                    report.only_in_b.push((key, merge::value_size(&value)));
+                }
+            },
+        }
+    }
+    Ok(report)
+}
+
+/// Clés vivantes d'un fichier, triées, et lecteur pour résoudre leurs valeurs.
+struct LiveSide {
+    entries: Peekable<vec::IntoIter<(Vec<u8>, IndexEntry)>>,
+    reader: LogReader,
+}
+
+impl LiveSide {
+    fn open(path: &Path) -> Result<Self, DatabaseError> {
+        let (index, _) = MyDatabase::recover_index(path, codec::MAX_RECORD_SIZE)?;
+        let mut entries: Vec<(Vec<u8>, IndexEntry)> = index
+            .into_iter()
+            .filter(|(_, entry)| !entry.tombstone)
+            .collect();
+        entries.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
+        Ok(Self {
+            entries: entries.into_iter().peekable(),
+            reader: LogReader::new(path)?,
+        })
+    }
+
+    fn peek(&mut self) -> Option<&Vec<u8>> {
+        self.entries.peek().map(|(key, _)| key)
+    }
+
+    /// Valeur de la clé suivante ; `None` si elle est effacée par fusion.
+    fn next_value(&mut self) -> Result<Option<(Vec<u8>, Value)>, DatabaseError> {
+        let Some((key, entry)) = self.entries.next() else {
+            return Ok(None);
+        };
+        let (value, _) = MyDatabase::resolve(&mut self.reader, &entry, &key)?;
+        Ok(value.map(|value| (key, value)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::DatabaseConfig;
+    use crate::testutil::TempDir;
+
+    fn database(dir: &TempDir, name: &str, entries: &[(&str, &str)]) -> std::path::PathBuf {
+        let path = dir.path().join(name);
+        let db = MyDatabase::new(DatabaseConfig {
+            file_path: path.clone(),
+            ..DatabaseConfig::default()
+        })
+        .unwrap();
+        for (key, value) in entries {
+            db.set(key.as_bytes().to_vec(), value.as_bytes().to_vec())
+                .unwrap();
+        }
+        path
+    }
+
+    #[test]
+    fn diff_reports_keys_on_either_side_and_changes() {
+        let dir = TempDir::new("tools-diff");
+        let a = database(&dir, "a.db", &[("same", "1"), ("changed", "a"), ("a", "x")]);
+        let b = database(
+            &dir,
+            "b.db",
+            &[("same", "1"), ("changed", "bb"), ("b1", "yy"), ("b2", "z")],
+        );
+        let report = diff(&a, &b).unwrap();
+        assert_eq!(report.only_in_a, vec![(b"a".to_vec(), 1)]);
+        assert_eq!(
+            report.only_in_b,
+            vec![(b"b1".to_vec(), 2), (b"b2".to_vec(), 1)]
+        );
+        assert_eq!(report.changed, vec![(b"changed".to_vec(), 1, 2)]);
+        assert!(diff(&a, &a).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,252 @@
+//! Résistance aux pannes injectées par la fonctionnalité `failpoints` :
+//! écritures tronquées, disque plein, crashs pendant l'écriture, la
+//! compaction et la relecture. Les points d'injection étant globaux au
+//! processus, les tests s'exécutent l'un après l'autre.
+#![cfg(feature = "failpoints")]
+
+use rust_database::failpoints::{self, FailAction};
+use rust_database::{DatabaseConfig, DatabaseError, MyDatabase};
+use std::fs::File;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+
+static SERIAL: Mutex<()> = Mutex::new(());
+
+/// Répertoire de test exclusif : les autres tests attendent sa fin, et
+/// tous les points d'injection sont désactivés avant et après.
+struct Scenario {
+    dir: PathBuf,
+    _serial: MutexGuard<'static, ()>,
+}
+
+impl Scenario {
+    fn new(name: &str) -> Self {
+        let serial = SERIAL
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        failpoints::clear();
+        let dir =
+            std::env::temp_dir().join(format!("rdb-failpoints-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        Self {
+            dir,
+            _serial: serial,
+        }
+    }
+
+    fn log(&self) -> PathBuf {
+        self.dir.join("db.db")
+    }
+
+    fn open(&self) -> Result<MyDatabase, DatabaseError> {
+        MyDatabase::new(DatabaseConfig {
+            file_path: self.log(),
+            ..DatabaseConfig::default()
+        })
+    }
+
+    fn log_size(&self) -> u64 {
+        std::fs::metadata(self.log()).unwrap().len()
+    }
+
+    /// Fichiers du répertoire autres que le journal.
+    fn leftovers(&self) -> Vec<String> {
+        files(&self.dir)
+            .into_iter()
+            .filter(|name| name != "db.db")
+            .collect()
+    }
+}
+
+impl Drop for Scenario {
+    fn drop(&mut self) {
+        failpoints::clear();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn files(dir: &Path) -> Vec<String> {
+    std::fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect()
+}
+
+/// Recule de deux heures la date de modification de `path`.
+fn age(path: &Path) {
+    let modified = SystemTime::now() - Duration::from_secs(2 * 60 * 60);
+    File::options()
+        .write(true)
+        .open(path)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+}
+
+/// Exécute `f`, qui doit paniquer sur un point d'injection.
+fn crash(f: impl FnOnce()) {
+    let hook = panic::take_hook();
+    panic::set_hook(Box::new(|_| {}));
+    let result = panic::catch_unwind(AssertUnwindSafe(f));
+    panic::set_hook(hook);
+    assert!(result.is_err(), "le point d'injection n'a pas été atteint");
+}
+
+fn set(db: &MyDatabase, key: &str, value: &str) -> Result<(), DatabaseError> {
+    db.set(key.as_bytes().to_vec(), value.as_bytes().to_vec())
+        .map(|_| ())
+}
+
+#[test]
+fn short_write_is_rolled_back() {
+    let scenario = Scenario::new("short-write");
+    let db = scenario.open().unwrap();
+    set(&db, "k", "ancienne").unwrap();
+    let size = scenario.log_size();
+
+    failpoints::configure("set.write", FailAction::ShortWrite(5));
+    assert!(matches!(
+        set(&db, "k", "nouvelle"),
+        Err(DatabaseError::Io { .. })
+    ));
+    assert_eq!(scenario.log_size(), size);
+    assert_eq!(db.get(b"k").unwrap(), Some(b"ancienne".to_vec()));
+
+    failpoints::remove("set.write");
+    set(&db, "k", "nouvelle").unwrap();
+    drop(db);
+    let db = scenario.open().unwrap();
+    assert_eq!(db.recovery_summary().truncated_bytes, 0);
+    assert_eq!(db.get(b"k").unwrap(), Some(b"nouvelle".to_vec()));
+}
+
+#[test]
+fn disk_full_degrades_to_read_only_until_compaction() {
+    let scenario = Scenario::new("disk-full");
+    let db = scenario.open().unwrap();
+    set(&db, "k", "v1").unwrap();
+    set(&db, "k", "v2").unwrap();
+    let size = scenario.log_size();
+
+    failpoints::configure("set.write", FailAction::DiskFull(3));
+    assert!(matches!(set(&db, "k", "v3"), Err(DatabaseError::DiskFull)));
+    assert_eq!(scenario.log_size(), size);
+    failpoints::remove("set.write");
+
+    assert!(matches!(set(&db, "k", "v3"), Err(DatabaseError::DiskFull)));
+    assert!(!db.health().can_write);
+    assert_eq!(db.get(b"k").unwrap(), Some(b"v2".to_vec()));
+
+    db.compact().unwrap();
+    assert!(db.health().can_write);
+    set(&db, "k", "v3").unwrap();
+    drop(db);
+    let db = scenario.open().unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"v3".to_vec()));
+}
+
+#[test]
+fn crash_before_write_loses_only_that_write() {
+    let scenario = Scenario::new("crash-before-write");
+    let db = scenario.open().unwrap();
+    set(&db, "a", "1").unwrap();
+
+    failpoints::configure("set.before_write", FailAction::Panic);
+    crash(|| {
+        let _ = set(&db, "b", "2");
+    });
+    failpoints::clear();
+    drop(db);
+
+    let db = scenario.open().unwrap();
+    assert_eq!(db.get(b"a").unwrap(), Some(b"1".to_vec()));
+    assert_eq!(db.get(b"b").unwrap(), None);
+}
+
+#[test]
+fn crash_after_write_keeps_the_record() {
+    let scenario = Scenario::new("crash-after-write");
+    let db = scenario.open().unwrap();
+
+    failpoints::configure("set.after_write", FailAction::Panic);
+    crash(|| {
+        let _ = set(&db, "k", "v");
+    });
+    failpoints::clear();
+    drop(db);
+
+    let db = scenario.open().unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"v".to_vec()));
+}
+
+#[test]
+fn failed_compaction_keeps_the_log_and_no_temp_file() {
+    for point in ["compact.copy", "compact.before_rename"] {
+        let scenario = Scenario::new(point);
+        let db = scenario.open().unwrap();
+        set(&db, "k", "v1").unwrap();
+        set(&db, "k", "v2").unwrap();
+        let size = scenario.log_size();
+
+        failpoints::configure(point, FailAction::Error);
+        assert!(db.compact().is_err(), "{point}");
+        assert!(db.health().last_compaction.unwrap().error.is_some());
+        assert_eq!(scenario.log_size(), size);
+        assert_eq!(scenario.leftovers(), Vec::<String>::new(), "{point}");
+        assert_eq!(db.get(b"k").unwrap(), Some(b"v2".to_vec()));
+
+        failpoints::clear();
+        set(&db, "k", "v3").unwrap();
+        db.compact().unwrap();
+        assert_eq!(db.get(b"k").unwrap(), Some(b"v3".to_vec()));
+    }
+}
+
+#[test]
+fn crash_during_compaction_recovers_on_open() {
+    for point in [
+        "compact.copy",
+        "compact.before_rename",
+        "compact.after_rename",
+    ] {
+        let scenario = Scenario::new(&format!("crash-{point}"));
+        let db = scenario.open().unwrap();
+        set(&db, "a", "1").unwrap();
+        set(&db, "a", "2").unwrap();
+        set(&db, "b", "3").unwrap();
+
+        failpoints::configure(point, FailAction::Panic);
+        crash(|| {
+            let _ = db.compact();
+        });
+        failpoints::clear();
+        drop(db);
+        // Un fichier temporaire récent peut appartenir à une compaction en
+        // cours dans un autre processus : seul un fichier ancien est retiré.
+        for name in scenario.leftovers() {
+            assert!(name.ends_with(".compacted"), "{point}: {name}");
+            age(&scenario.dir.join(name));
+        }
+
+        let db = scenario.open().unwrap();
+        assert_eq!(db.get(b"a").unwrap(), Some(b"2".to_vec()), "{point}");
+        assert_eq!(db.get(b"b").unwrap(), Some(b"3".to_vec()), "{point}");
+        assert_eq!(scenario.leftovers(), Vec::<String>::new(), "{point}");
+    }
+}
+
+#[test]
+fn recovery_read_error_fails_the_open() {
+    let scenario = Scenario::new("recovery-read");
+    set(&scenario.open().unwrap(), "k", "v").unwrap();
+
+    failpoints::configure("recovery.read", FailAction::Error);
+    assert!(scenario.open().is_err());
+    failpoints::clear();
+
+    let db = scenario.open().unwrap();
+    assert_eq!(db.get(b"k").unwrap(), Some(b"v".to_vec()));
+}
@@ -0,0 +1,76 @@
+//! Vérifie que `include/rdb.h` correspond aux symboles exportés par
+//! `src/ffi.rs` : un programme C qui n'inclut que l'en-tête est compilé,
+//! lié à la bibliothèque statique puis exécuté.
+#![cfg(feature = "ffi")]
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const PROGRAM: &str = r#"
+#include <string.h>
+#include "rdb.h"
+
+int main(int argc, char **argv) {
+    if (argc < 2) return 10;
+    RdbHandle *db = rdb_open(argv[1], 0);
+    if (db == NULL) return 11;
+
+    const uint8_t key[] = "cle";
+    const uint8_t value[] = "valeur";
+    if (rdb_set(db, key, 3, value, 6) != 0) return 12;
+
+    uint8_t *out = NULL;
+    size_t out_len = 0;
+    if (rdb_get(db, key, 3, &out, &out_len) != 0) return 13;
+    if (out_len != 6 || memcmp(out, value, 6) != 0) return 14;
+    rdb_free(out, out_len);
+
+    if (rdb_delete(db, key, 3) != 0) return 15;
+    if (rdb_get(db, key, 3, &out, &out_len) != 1) return 16;
+
+    rdb_close(db);
+    return 0;
+}
+"#;
+
+/// Bibliothèque statique la plus récente construite à côté de ce test.
+fn static_library() -> PathBuf {
+    let deps = std::env::current_exe().unwrap();
+    let deps = deps.parent().unwrap();
+    std::fs::read_dir(deps)
+        .unwrap()
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            let name = path.file_name().unwrap().to_string_lossy();
+            name.starts_with("librust_database") && name.ends_with(".a")
+        })
+        .max_by_key(|path| path.metadata().and_then(|m| m.modified()).unwrap())
+        .expect("librust_database.a introuvable")
+}
+
+#[test]
+fn header_matches_exported_symbols() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("ffi_header");
+    std::fs::create_dir_all(&dir).unwrap();
+    let source = dir.join("main.c");
+    let program = dir.join("main");
+    let db_path = dir.join("test.db");
+    let _ = std::fs::remove_file(&db_path);
+    std::fs::write(&source, PROGRAM).unwrap();
+
+    let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+    let status = Command::new(&cc)
+        .args(["-Wall", "-Werror", "-std=c99", "-I"])
+        .arg(manifest.join("include"))
+        .arg(&source)
+        .arg(static_library())
+        .args(["-lpthread", "-ldl", "-lm", "-o"])
+        .arg(&program)
+        .status()
+        .unwrap_or_else(|err| panic!("compilateur C `{}` indisponible : {}", cc, err));
+    assert!(status.success(), "compilation de l'en-tête : {}", status);
+
+    let status = Command::new(&program).arg(&db_path).status().unwrap();
+    assert_eq!(status.code(), Some(0));
+}